@@ -0,0 +1,104 @@
+#ifndef MUPEN64_MOVIE_PARSER_H
+#define MUPEN64_MOVIE_PARSER_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * The M64 file.
+ */
+typedef struct M64 M64;
+
+/**
+ * The header fields of a movie.
+ * - Strings are the raw NUL padded bytes of the fields, and are not guaranteed to be NUL terminated when full.
+ */
+typedef struct M64Header {
+  uint32_t uid;
+  uint32_t vi_frames;
+  uint32_t input_frames;
+  uint32_t rerecords;
+  uint8_t fps;
+  uint8_t controller_count;
+  uint16_t movie_start_type;
+  uint32_t controller_flags;
+  char rom_internal_name[32];
+  uint32_t rom_crc_32;
+  uint16_t rom_country_code;
+  char video_plugin[64];
+  char sound_plugin[64];
+  char input_plugin[64];
+  char rsp_plugin[64];
+  char author[222];
+  char description[256];
+} M64Header;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Creates an empty movie with the default header.
+ */
+M64 *m64_new(void);
+
+/**
+ * Parses a movie, returning null if it's invalid.
+ */
+M64 *m64_parse(const uint8_t *data, size_t len);
+
+/**
+ * Releases a movie.
+ */
+void m64_free(M64 *m64);
+
+/**
+ * Copies the header of the movie into `header`.
+ */
+void m64_get_header(const M64 *m64, M64Header *header);
+
+/**
+ * Replaces the header of the movie, returning `false` and leaving the movie unchanged if a field is invalid.
+ * - Strings must be UTF-8, and are padded with NUL bytes.
+ */
+bool m64_set_header(M64 *m64, const M64Header *header);
+
+/**
+ * Returns the number of input samples.
+ */
+size_t m64_input_count(const M64 *m64);
+
+/**
+ * Writes the raw input sample at `index` to `input`, returning `false` if it's out of range.
+ */
+bool m64_get_input(const M64 *m64, size_t index, uint32_t *input);
+
+/**
+ * Replaces the input sample at `index`, returning `false` if it's out of range.
+ */
+bool m64_set_input(M64 *m64, size_t index, uint32_t input);
+
+/**
+ * Appends an input sample, increasing `input_frames` by 1.
+ */
+void m64_push_input(M64 *m64, uint32_t input);
+
+/**
+ * Writes the movie to a new buffer, storing its length in `len`.
+ * - The buffer must be released with `m64_bytes_free`.
+ */
+uint8_t *m64_to_bytes(const M64 *m64, size_t *len);
+
+/**
+ * Releases a buffer returned by `m64_to_bytes`.
+ */
+void m64_bytes_free(uint8_t *bytes, size_t len);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* MUPEN64_MOVIE_PARSER_H */
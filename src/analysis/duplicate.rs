@@ -0,0 +1,117 @@
+//! Finds long stretches of frames that repeat earlier frames of the same movie.
+//!
+//! Repeats point to inputs that were copied and pasted, and to regions that compress well.
+//! Windows of frames are matched with a rolling hash, and each match is extended as far as the frames keep repeating.
+use std::{collections::HashMap, ops::Range};
+
+use crate::m64::M64;
+
+/// Multiplier of the rolling hash.
+const HASH_BASE: u64 = 0x100_0000_01B3;
+
+/// A stretch of frames that repeats earlier frames.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DuplicateSegment {
+    /// The frames that are repeated.
+    pub original: Range<usize>,
+    /// The frames repeating them, which start after `original` ends.
+    pub copy: Range<usize>,
+}
+
+impl DuplicateSegment {
+    /// Returns the number of repeated frames.
+    pub fn len(&self) -> usize {
+        self.copy.len()
+    }
+
+    /// Returns if no frames are repeated.
+    pub fn is_empty(&self) -> bool {
+        self.copy.is_empty()
+    }
+}
+
+impl M64 {
+    /// Finds stretches of at least `min_len` frames that repeat earlier frames, in the order they appear.
+    /// - Each frame is part of at most one copy, and a copy always repeats the earliest matching frames.
+    /// - Holding the same input for a long time counts as a repeat, as the second half repeats the first half.
+    /// - An incomplete last frame, when the number of input samples isn't a multiple of the controller count, is never matched.
+    ///
+    /// # Panics
+    /// Panics if `min_len` is 0.
+    pub fn duplicate_segments(&self, min_len: usize) -> Vec<DuplicateSegment> {
+        assert!(min_len != 0, "minimum segment length must be non-zero");
+
+        let stride = self.samples_per_frame();
+        let frames = self
+            .frames()
+            .take(self.inputs.len() / stride)
+            .map(|frame| {
+                frame.iter().fold(0u64, |hash, &input| {
+                    hash.rotate_left(32) ^ u32::from(input) as u64
+                })
+            })
+            .collect::<Vec<_>>();
+        if frames.len() / 2 < min_len {
+            return Vec::new();
+        }
+
+        let samples =
+            |frames: Range<usize>| &self.inputs[frames.start * stride..frames.end * stride];
+        // frames are compared by their inputs, as more than 2 controllers don't fit in the frame hash
+        let same_frames = |a: usize, b: usize| samples(a..a + 1) == samples(b..b + 1);
+
+        let base_pow = wrapping_pow(HASH_BASE, min_len - 1);
+        let mut hash = frames[..min_len].iter().fold(0u64, |hash, &frame| {
+            hash.wrapping_mul(HASH_BASE).wrapping_add(frame)
+        });
+        // first window start for each hash
+        let mut seen = HashMap::new();
+        let mut segments = Vec::new();
+        let mut start = 0;
+        // windows starting before this are part of a copy that was already reported
+        let mut next_free = 0;
+
+        loop {
+            let earliest = *seen.entry(hash).or_insert(start);
+            if start >= next_free
+                && earliest + min_len <= start
+                && samples(earliest..earliest + min_len) == samples(start..start + min_len)
+            {
+                let max_len = (start - earliest).min(frames.len() - start);
+                let len = (0..max_len)
+                    .take_while(|&i| same_frames(earliest + i, start + i))
+                    .count();
+                segments.push(DuplicateSegment {
+                    original: earliest..earliest + len,
+                    copy: start..start + len,
+                });
+                next_free = start + len;
+            }
+
+            if start + min_len >= frames.len() {
+                break;
+            }
+            hash = hash
+                .wrapping_sub(frames[start].wrapping_mul(base_pow))
+                .wrapping_mul(HASH_BASE)
+                .wrapping_add(frames[start + min_len]);
+            start += 1;
+        }
+
+        segments
+    }
+}
+
+/// Raises `base` to the power of `exp`, wrapping around on overflow.
+/// - Unlike [`u64::wrapping_pow`], the exponent isn't limited to a `u32`.
+fn wrapping_pow(mut base: u64, mut exp: usize) -> u64 {
+    let mut pow = 1u64;
+    while exp != 0 {
+        if exp & 1 == 1 {
+            pow = pow.wrapping_mul(base);
+        }
+        base = base.wrapping_mul(base);
+        exp >>= 1;
+    }
+    pow
+}
@@ -0,0 +1,55 @@
+//! Finds idle frames, where no controller has any input.
+use std::ops::Range;
+
+use crate::{controller::Input, m64::M64};
+
+/// Result of the idle frame analysis.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct IdleReport {
+    /// Number of frames where every controller is neutral.
+    pub idle_frames: usize,
+    /// The longest range of consecutive idle frames, the earliest one if there are multiple.
+    pub longest_idle: Option<Range<usize>>,
+    /// Index of the last frame with any input.
+    pub last_active_frame: Option<usize>,
+}
+
+impl M64 {
+    /// Reports how many frames are idle, the longest idle stretch and the last frame with any input.
+    /// - A frame is idle if every controller's input is neutral, see [`Input::is_neutral`].
+    pub fn idle_report(&self) -> IdleReport {
+        let mut report = IdleReport::default();
+        let mut idle_start = None;
+        let mut frames = 0;
+
+        for (i, frame) in self.enumerate_frames() {
+            frames = i + 1;
+
+            if frame.iter().all(Input::is_neutral) {
+                report.idle_frames += 1;
+                idle_start.get_or_insert(i);
+                continue;
+            }
+
+            report.last_active_frame = Some(i);
+            if let Some(start) = idle_start.take() {
+                update_longest(&mut report.longest_idle, start..i);
+            }
+        }
+
+        if let Some(start) = idle_start {
+            update_longest(&mut report.longest_idle, start..frames);
+        }
+
+        report
+    }
+}
+
+fn update_longest(longest: &mut Option<Range<usize>>, range: Range<usize>) {
+    if longest
+        .as_ref()
+        .is_none_or(|longest| range.len() > longest.len())
+    {
+        *longest = Some(range);
+    }
+}
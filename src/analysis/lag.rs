@@ -0,0 +1,99 @@
+//! Estimates lag frames from the header counters, and flags counters that can't belong together.
+//!
+//! A game polls for input once per game frame, which takes `vis_per_poll` VIs when the game keeps up.
+//! Every VI beyond that is lag, so the lag is estimated from how many more VIs the movie has than its input frames need.
+use std::time::Duration;
+
+use crate::{error::LagAnalysisError, m64::M64};
+
+/// Options for the lag frame analysis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LagAnalysisOptions {
+    /// Number of VIs between input polls when the game doesn't lag.
+    /// - Must be a positive number, as the lag frames are counted in polls of this many VIs.
+    pub vis_per_poll: f64,
+    /// Fraction of the VIs that can be lag before the ratio is flagged as implausible.
+    pub max_lag_ratio: f64,
+}
+
+impl Default for LagAnalysisOptions {
+    /// Uses the 2 VIs per frame of Super Mario 64, and flags movies that lag for more than half of the VIs.
+    fn default() -> Self {
+        LagAnalysisOptions {
+            vis_per_poll: 2.0,
+            max_lag_ratio: 0.5,
+        }
+    }
+}
+
+/// A header inconsistency found by the lag frame analysis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LagIssue {
+    /// The movie has input frames, but no VIs.
+    NoViFrames,
+    /// The movie has fewer VIs than input frames, so input was polled more often than the game can.
+    FewerVisThanInputFrames,
+    /// More of the VIs are lag than allowed by [`LagAnalysisOptions::max_lag_ratio`].
+    ExcessiveLag,
+    /// `input_frames` isn't a multiple of the controller count, so the last frame is incomplete.
+    IncompleteFrame,
+}
+
+/// Result of the lag frame analysis.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct LagReport {
+    /// Number of input frames from the header, which is `input_frames` divided by the controller count.
+    pub input_frames: usize,
+    /// Number of VIs from the header.
+    pub vi_frames: u32,
+    /// Number of VIs for each input frame.
+    pub vis_per_input_frame: f64,
+    /// Estimated number of lag frames, in game frames of [`LagAnalysisOptions::vis_per_poll`] VIs.
+    pub lag_frames: usize,
+    /// Estimated time spent lagging, using the actual VI rate of the region.
+    pub lag_time: Duration,
+    /// Inconsistencies between the header counters.
+    pub issues: Vec<LagIssue>,
+}
+
+impl M64 {
+    /// Estimates the lag frames of the movie from its header, and flags counters that can't belong together.
+    /// - See the [module documentation](crate::analysis::lag) for how the lag is estimated.
+    /// - Returns an error if [`LagAnalysisOptions::vis_per_poll`] isn't a positive number.
+    pub fn lag_report(&self, options: &LagAnalysisOptions) -> Result<LagReport, LagAnalysisError> {
+        if !(options.vis_per_poll.is_finite() && options.vis_per_poll > 0.0) {
+            return Err(LagAnalysisError::InvalidVisPerPoll(options.vis_per_poll));
+        }
+
+        let controllers = self.samples_per_frame();
+        let input_frames = self.input_frames as usize / controllers;
+        let mut report = LagReport {
+            input_frames,
+            vi_frames: self.vi_frames,
+            ..Default::default()
+        };
+
+        if !(self.input_frames as usize).is_multiple_of(controllers) {
+            report.issues.push(LagIssue::IncompleteFrame);
+        }
+        if self.vi_frames == 0 {
+            if input_frames > 0 {
+                report.issues.push(LagIssue::NoViFrames);
+            }
+            return Ok(report);
+        }
+        if (self.vi_frames as usize) < input_frames {
+            report.issues.push(LagIssue::FewerVisThanInputFrames);
+        }
+
+        report.vis_per_input_frame = self.vi_frames as f64 / input_frames.max(1) as f64;
+        let lag_vis = (self.vi_frames as f64 - input_frames as f64 * options.vis_per_poll).max(0.0);
+        report.lag_frames = (lag_vis / options.vis_per_poll) as usize;
+        report.lag_time = Duration::from_secs_f64(lag_vis / self.vertical_rate().exact());
+        if lag_vis / self.vi_frames as f64 > options.max_lag_ratio {
+            report.issues.push(LagIssue::ExcessiveLag);
+        }
+
+        Ok(report)
+    }
+}
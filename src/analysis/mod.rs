@@ -0,0 +1,6 @@
+//! Analysis passes over the inputs of a movie.
+pub mod duplicate;
+pub mod idle;
+pub mod lag;
+pub mod pattern;
+pub mod stick;
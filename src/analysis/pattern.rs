@@ -0,0 +1,116 @@
+//! Searching for input patterns in a movie.
+use std::fmt;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+#[cfg(feature = "rayon")]
+use crate::storage::PARALLEL_MIN_SAMPLES;
+use crate::{controller::Input, m64::M64};
+
+/// A condition a single input sample has to meet to match a pattern.
+pub enum InputPredicate<'a> {
+    /// Matches any input.
+    Any,
+    /// Matches an input that is exactly the same.
+    Exact(Input),
+    /// Matches an input the closure returns `true` for.
+    Matches(Box<dyn PredicateFn + 'a>),
+}
+
+/// A closure an input can be matched against, see [`InputPredicate::new`].
+/// - With the `rayon` feature, the closure also has to be `Send + Sync` so patterns can be matched from several threads.
+#[cfg(feature = "rayon")]
+pub trait PredicateFn: Fn(&Input) -> bool + Send + Sync {}
+
+#[cfg(feature = "rayon")]
+impl<F> PredicateFn for F where F: Fn(&Input) -> bool + Send + Sync {}
+
+/// A closure an input can be matched against, see [`InputPredicate::new`].
+/// - With the `rayon` feature, the closure also has to be `Send + Sync` so patterns can be matched from several threads.
+#[cfg(not(feature = "rayon"))]
+pub trait PredicateFn: Fn(&Input) -> bool {}
+
+#[cfg(not(feature = "rayon"))]
+impl<F> PredicateFn for F where F: Fn(&Input) -> bool {}
+
+impl<'a> InputPredicate<'a> {
+    /// Creates a predicate from a closure.
+    pub fn new<F>(predicate: F) -> Self
+    where
+        F: PredicateFn + 'a,
+    {
+        InputPredicate::Matches(Box::new(predicate))
+    }
+
+    /// Returns if the input meets the condition.
+    pub fn matches(&self, input: &Input) -> bool {
+        match self {
+            InputPredicate::Any => true,
+            InputPredicate::Exact(expected) => expected == input,
+            InputPredicate::Matches(predicate) => predicate(input),
+        }
+    }
+}
+
+impl From<Input> for InputPredicate<'_> {
+    fn from(input: Input) -> Self {
+        InputPredicate::Exact(input)
+    }
+}
+
+impl fmt::Debug for InputPredicate<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputPredicate::Any => write!(f, "Any"),
+            InputPredicate::Exact(input) => f.debug_tuple("Exact").field(input).finish(),
+            InputPredicate::Matches(_) => write!(f, "Matches(..)"),
+        }
+    }
+}
+
+impl M64 {
+    /// Finds every frame where the pattern starts for the given controller.
+    /// - Each predicate in the pattern is matched against a consecutive frame.
+    /// - Returns the frame indices in ascending order, matches can overlap.
+    /// - An empty pattern, or a controller out of range doesn't match anything.
+    /// - With the `rayon` feature, long movies are searched in parallel.
+    pub fn find_pattern(&self, controller: usize, pattern: &[InputPredicate]) -> Vec<usize> {
+        if pattern.is_empty() {
+            return Vec::new();
+        }
+
+        let inputs = self.inputs_for_controller(controller).collect::<Vec<_>>();
+        find_windows(&inputs, pattern)
+    }
+}
+
+/// Returns the start of every window of `inputs` that matches the pattern.
+#[cfg(not(feature = "rayon"))]
+fn find_windows(inputs: &[Input], pattern: &[InputPredicate]) -> Vec<usize> {
+    inputs
+        .windows(pattern.len())
+        .enumerate()
+        .filter(|(_, window)| matches_window(window, pattern))
+        .map(|(frame, _)| frame)
+        .collect()
+}
+
+/// Returns the start of every window of `inputs` that matches the pattern, searching chunks of the inputs in parallel.
+#[cfg(feature = "rayon")]
+fn find_windows(inputs: &[Input], pattern: &[InputPredicate]) -> Vec<usize> {
+    inputs
+        .par_windows(pattern.len())
+        .enumerate()
+        .with_min_len(PARALLEL_MIN_SAMPLES)
+        .filter(|(_, window)| matches_window(window, pattern))
+        .map(|(frame, _)| frame)
+        .collect()
+}
+
+fn matches_window(window: &[Input], pattern: &[InputPredicate]) -> bool {
+    window
+        .iter()
+        .zip(pattern)
+        .all(|(input, predicate)| predicate.matches(input))
+}
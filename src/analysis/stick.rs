@@ -0,0 +1,81 @@
+//! Reports analog stick values that are out of range or inside a deadzone.
+use std::ops::RangeInclusive;
+
+use crate::m64::M64;
+
+/// Options for the analog stick analysis.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StickAnalysisOptions {
+    /// The range of values the emulator accepts on each axis.
+    pub accepted_range: RangeInclusive<i8>,
+    /// Values with an absolute value below this on both axes are considered inside the deadzone.
+    pub deadzone: i8,
+}
+
+impl Default for StickAnalysisOptions {
+    /// Accepts -127 to 127 like TAS Input does, and uses the 8 unit deadzone of Super Mario 64.
+    fn default() -> Self {
+        StickAnalysisOptions {
+            accepted_range: -127..=127,
+            deadzone: 8,
+        }
+    }
+}
+
+/// A single analog stick sample that was reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StickIssue {
+    /// Frame index of the sample.
+    pub frame: usize,
+    /// Controller index of the sample.
+    pub controller: usize,
+    /// Analog stick X-axis.
+    pub x: i8,
+    /// Analog stick Y-axis.
+    pub y: i8,
+}
+
+/// Result of the analog stick analysis.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct StickReport {
+    /// Samples with an axis outside the accepted range.
+    pub out_of_range: Vec<StickIssue>,
+    /// Samples that aren't neutral, but are inside the deadzone.
+    pub in_deadzone: Vec<StickIssue>,
+}
+
+impl StickReport {
+    /// Returns if nothing was reported.
+    pub fn is_empty(&self) -> bool {
+        self.out_of_range.is_empty() && self.in_deadzone.is_empty()
+    }
+}
+
+impl M64 {
+    /// Reports analog stick values that are outside the accepted range, or inside the deadzone.
+    /// - Such values often indicate corrupted or hand edited inputs.
+    pub fn stick_report(&self, options: &StickAnalysisOptions) -> StickReport {
+        let stride = self.samples_per_frame();
+        let deadzone = options.deadzone as i16;
+        let mut report = StickReport::default();
+
+        for (i, input) in self.inputs.iter().enumerate() {
+            let (x, y) = (input.x_axis, input.y_axis);
+            let issue = StickIssue {
+                frame: i / stride,
+                controller: i % stride,
+                x,
+                y,
+            };
+
+            if !options.accepted_range.contains(&x) || !options.accepted_range.contains(&y) {
+                report.out_of_range.push(issue);
+            } else if (x, y) != (0, 0) && (x as i16).abs() < deadzone && (y as i16).abs() < deadzone
+            {
+                report.in_deadzone.push(issue);
+            }
+        }
+
+        report
+    }
+}
@@ -0,0 +1,296 @@
+//! Command line tool for inspecting and editing `.m64` movies.
+use std::{
+    error::Error,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use mupen64_movie_parser::{
+    diff::DiffOp,
+    m64::{collect_into_m64, M64},
+    script::format_script,
+    PaddedString,
+};
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+#[derive(Parser)]
+#[command(
+    name = "m64tool",
+    version,
+    about = "Inspect and edit Mupen64-rerecording movies"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Prints the header of a movie.
+    Info {
+        /// The movie.
+        movie: PathBuf,
+    },
+    /// Prints the inputs of a movie.
+    Dump {
+        /// The movie.
+        movie: PathBuf,
+        /// Output format.
+        #[arg(short, long, value_enum, default_value_t = DumpFormat::Csv)]
+        format: DumpFormat,
+    },
+    /// Edits the metadata of a movie.
+    EditMeta {
+        /// The movie.
+        movie: PathBuf,
+        /// Where to write the edited movie, defaults to overwriting the movie.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// New author.
+        #[arg(long)]
+        author: Option<String>,
+        /// New description.
+        #[arg(long)]
+        description: Option<String>,
+        /// New rerecord count.
+        #[arg(long)]
+        rerecords: Option<u32>,
+    },
+    /// Joins the start of a movie with the rest of another movie.
+    Splice {
+        /// Movie to take the header and the frames before `--at` from.
+        first: PathBuf,
+        /// Movie to take the frames from `--from` onwards from.
+        second: PathBuf,
+        /// Frame of the first movie to splice at.
+        #[arg(long)]
+        at: usize,
+        /// Frame of the second movie to continue from, defaults to `--at`.
+        #[arg(long)]
+        from: Option<usize>,
+        /// Where to write the spliced movie.
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Keeps a range of frames of a movie.
+    Trim {
+        /// The movie.
+        movie: PathBuf,
+        /// First frame to keep.
+        #[arg(long, default_value_t = 0)]
+        start: usize,
+        /// Frame to stop at, exclusive, defaults to the end of the movie.
+        #[arg(long)]
+        end: Option<usize>,
+        /// Also removes idle frames at the end.
+        #[arg(long)]
+        idle: bool,
+        /// Where to write the trimmed movie, defaults to overwriting the movie.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Converts a movie to another format, picked from the output file extension.
+    ///
+    /// Reads `.m64`, `.json`, `.tasd` and `.bk2`, and writes those along with `.csv`, `.lua` and `.txt` input scripts.
+    Convert {
+        /// The movie to convert.
+        input: PathBuf,
+        /// Where to write the converted movie.
+        output: PathBuf,
+    },
+    /// Prints the input differences between two movies.
+    Diff {
+        /// The original movie.
+        first: PathBuf,
+        /// The changed movie.
+        second: PathBuf,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum DumpFormat {
+    /// One row per input sample.
+    Csv,
+    /// An input script with a line per run of identical inputs.
+    Script,
+    /// The whole movie as JSON.
+    Json,
+}
+
+fn main() {
+    if let Err(err) = run(Cli::parse()) {
+        eprintln!("error: {err}");
+        std::process::exit(1);
+    }
+}
+
+fn run(cli: Cli) -> Result<()> {
+    match cli.command {
+        Command::Info { movie } => print!("{}", read_movie(&movie)?.summary()),
+        Command::Dump { movie, format } => {
+            let m64 = read_movie(&movie)?;
+            let mut stdout = io::stdout().lock();
+            match format {
+                DumpFormat::Csv => m64.write_inputs_csv(&mut stdout)?,
+                DumpFormat::Script => write_script(&m64, &mut stdout)?,
+                DumpFormat::Json => writeln!(stdout, "{}", m64.to_json())?,
+            }
+        }
+        Command::EditMeta {
+            movie,
+            output,
+            author,
+            description,
+            rerecords,
+        } => {
+            let mut m64 = read_movie(&movie)?;
+            if let Some(author) = author {
+                m64.author = padded(&author, "author")?;
+            }
+            if let Some(description) = description {
+                m64.description = padded(&description, "description")?;
+            }
+            if let Some(rerecords) = rerecords {
+                m64.rerecords = rerecords;
+            }
+            write_movie(&m64, output.as_deref().unwrap_or(&movie))?;
+        }
+        Command::Splice {
+            first,
+            second,
+            at,
+            from,
+            output,
+        } => {
+            let first = read_movie(&first)?;
+            let second = read_movie(&second)?;
+            if first.samples_per_frame() != second.samples_per_frame() {
+                return Err("movies have different controller counts".into());
+            }
+
+            let stride = first.samples_per_frame();
+            let head = first.inputs.iter().take(frame_to_sample(at, stride)?);
+            let tail = second
+                .inputs
+                .iter()
+                .skip(frame_to_sample(from.unwrap_or(at), stride)?);
+            let inputs = head.chain(tail).copied().collect::<Vec<_>>();
+            write_movie(&collect_into_m64(first, inputs), &output)?;
+        }
+        Command::Trim {
+            movie,
+            start,
+            end,
+            idle,
+            output,
+        } => {
+            let m64 = read_movie(&movie)?;
+            let stride = m64.samples_per_frame();
+            let end = match end {
+                Some(end) => frame_to_sample(end, stride)?,
+                None => m64.inputs.len(),
+            };
+            let inputs = m64
+                .inputs
+                .get(frame_to_sample(start, stride)?..end.min(m64.inputs.len()))
+                .ok_or("invalid frame range")?
+                .to_vec();
+
+            let mut m64 = collect_into_m64(m64, inputs);
+            if idle {
+                m64.trim_trailing_idle();
+            }
+            write_movie(&m64, output.as_deref().unwrap_or(&movie))?;
+        }
+        Command::Convert { input, output } => write_movie(&read_movie(&input)?, &output)?,
+        Command::Diff { first, second } => {
+            for op in read_movie(&first)?.diff_inputs(&read_movie(&second)?) {
+                match op {
+                    DiffOp::Insert {
+                        controller,
+                        at,
+                        other,
+                    } => println!(
+                        "controller {}: insert frames {other:?} of the second movie at {at}",
+                        controller + 1
+                    ),
+                    DiffOp::Delete { controller, range } => {
+                        println!("controller {}: delete frames {range:?}", controller + 1)
+                    }
+                    DiffOp::Replace {
+                        controller,
+                        range,
+                        other,
+                    } => println!(
+                        "controller {}: replace frames {range:?} with frames {other:?} of the second movie",
+                        controller + 1
+                    ),
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+}
+
+fn read_movie(path: &Path) -> Result<M64> {
+    let m64 = match extension(path).as_str() {
+        "json" => M64::from_json(&fs::read_to_string(path)?)?,
+        "tasd" => M64::from_tasd(&fs::read(path)?)?,
+        #[cfg(feature = "bk2")]
+        "bk2" => M64::read_bk2(File::open(path)?)?.0,
+        _ => M64::from_path(path)?,
+    };
+    Ok(m64)
+}
+
+fn write_movie(m64: &M64, path: &Path) -> Result<()> {
+    let extension = extension(path);
+    #[cfg(feature = "bk2")]
+    if extension == "bk2" {
+        return Ok(m64.write_bk2(File::create(path)?)?);
+    }
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    match extension.as_str() {
+        "json" => writer.write_all(m64.to_json().as_bytes())?,
+        "tasd" => m64.write_tasd(&mut writer)?,
+        "csv" => m64.write_inputs_csv(&mut writer)?,
+        "lua" => writer.write_all(m64.to_lua_joypad_script().as_bytes())?,
+        "txt" => write_script(m64, &mut writer)?,
+        _ => m64.write_m64(&mut writer)?,
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes the inputs as an input script, with a section for each controller.
+fn write_script(m64: &M64, writer: &mut impl Write) -> Result<()> {
+    for controller in 0..m64.samples_per_frame() {
+        writeln!(writer, "# controller {}", controller + 1)?;
+        let inputs = m64.inputs_for_controller(controller).collect::<Vec<_>>();
+        write!(writer, "{}", format_script(&inputs))?;
+    }
+    Ok(())
+}
+
+/// Returns the index of the first input sample of a frame given on the command line.
+fn frame_to_sample(frame: usize, stride: usize) -> Result<usize> {
+    Ok(frame
+        .checked_mul(stride)
+        .ok_or_else(|| format!("frame {frame} is out of range"))?)
+}
+
+fn padded<const N: usize>(s: &str, field: &str) -> Result<PaddedString<N>> {
+    Ok(PaddedString::try_from(s).map_err(|_| format!("{field} is too long"))?)
+}
@@ -0,0 +1,339 @@
+//! Conversion of movies to and from BizHawk's BK2 movie format.
+//!
+//! A BK2 file is a zip archive holding the following files:
+//! - `Header.txt`: key value pairs separated by a space, such as the author and rerecord count.
+//! - `SyncSettings.json`: the settings of the N64 core, which holds which controllers are connected.
+//! - `Input Log.txt`: one line for each frame, with the input of each controller written with mnemonics.
+//!
+//! # Input log
+//! Each frame is written as `|..|    0,    0,..............|`, starting with the reset and power buttons,
+//! followed by a group for each controller holding the X and Y axis, and a character for each button in the order `UDLRSZBAudrlLR`.
+//! - The characters are D-pad up, down, left, right, start, Z, B, A, C-up, C-down, C-right, C-left, left shoulder and right shoulder.
+//! - A released button is written as `.`.
+//!
+//! # Limitations
+//! BizHawk stores a line for every VI frame, while `.m64` files only store input samples when the game polls for input.
+//! Movies are converted with one line for each input frame, so games that don't poll for input on every VI won't sync without adjustments.
+//!
+//! Each header entry takes a single line, so line breaks in the author and ROM name are replaced by spaces when exporting.
+//!
+//! When importing, anything that can't be stored in a `.m64` file is listed in a [`Bk2ImportReport`].
+use std::io::{Read, Seek, Write};
+
+use zip::{write::FileOptions, ZipArchive, ZipWriter};
+
+use crate::{
+    controller::{Buttons, Input},
+    error::{Bk2Error, FieldName},
+    m64::{MovieStartType, M64},
+    padded_string::PaddedString,
+};
+
+/// The N64 buttons of the BK2 input log in order, with their name and mnemonic.
+pub const N64_BUTTONS: [(Buttons, &str, char); 14] = [
+    (Buttons::UP_DPAD, "DPad U", 'U'),
+    (Buttons::DOWN_DPAD, "DPad D", 'D'),
+    (Buttons::LEFT_DPAD, "DPad L", 'L'),
+    (Buttons::RIGHT_DPAD, "DPad R", 'R'),
+    (Buttons::START, "Start", 'S'),
+    (Buttons::Z, "Z", 'Z'),
+    (Buttons::B, "B", 'B'),
+    (Buttons::A, "A", 'A'),
+    (Buttons::UP_CBUTTON, "C Up", 'u'),
+    (Buttons::DOWN_CBUTTON, "C Down", 'd'),
+    (Buttons::RIGHT_CBUTTON, "C Right", 'r'),
+    (Buttons::LEFT_CBUTTON, "C Left", 'l'),
+    (Buttons::LEFT_SHOULDER, "L", 'L'),
+    (Buttons::RIGHT_SHOULDER, "R", 'R'),
+];
+
+/// Mupen64Plus pak types used in the sync settings.
+const PAK_NONE: u8 = 1;
+const PAK_MEMPAK: u8 = 2;
+const PAK_RUMBLEPAK: u8 = 5;
+
+/// Information from a BK2 file that couldn't be represented in the imported movie.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Bk2ImportReport {
+    /// Header entries that have no matching `.m64` field, as key value pairs.
+    pub unmapped_header: Vec<(String, String)>,
+    /// Fields that were cut off because they were too long.
+    pub truncated_fields: Vec<FieldName>,
+    /// Files in the archive that were ignored.
+    pub ignored_files: Vec<String>,
+    /// Frames where the power button was pressed.
+    pub power_frames: Vec<usize>,
+    /// Frames where the reset button was pressed, which were converted to the reset input of controller 1.
+    pub reset_frames: Vec<usize>,
+}
+
+impl M64 {
+    /// Reads a BK2 archive from the given reader, converting it to a movie.
+    /// - Returns the movie along with a report of what couldn't be converted.
+    /// - See the [module documentation](crate::bk2) for the layout and limitations.
+    pub fn read_bk2<R>(reader: R) -> Result<(M64, Bk2ImportReport), Bk2Error>
+    where
+        R: Read + Seek,
+    {
+        let mut zip = ZipArchive::new(reader)?;
+        let mut report = Bk2ImportReport::default();
+        let mut m64 = M64::default();
+
+        let header = read_file(&mut zip, "Header.txt")?;
+        for line in header.lines().filter(|line| !line.trim().is_empty()) {
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            match key {
+                "Author" => {
+                    let (author, truncated) = PaddedString::truncated(value);
+                    m64.author = author;
+                    if truncated {
+                        report.truncated_fields.push(FieldName::Author);
+                    }
+                }
+                "GameName" => {
+                    let (name, truncated) = PaddedString::truncated(value);
+                    m64.rom_internal_name = name;
+                    if truncated {
+                        report.truncated_fields.push(FieldName::RomInternalName);
+                    }
+                }
+                "rerecordCount" => match value.parse() {
+                    Ok(rerecords) => m64.set_total_rerecords(rerecords),
+                    Err(_) => report
+                        .unmapped_header
+                        .push((key.to_string(), value.to_string())),
+                },
+                "StartsFromSavestate" if value.eq_ignore_ascii_case("true") => {
+                    m64.movie_start_type = MovieStartType::SnapShot;
+                }
+                "PAL" if value.eq_ignore_ascii_case("true") => m64.fps = 50,
+                _ => report
+                    .unmapped_header
+                    .push((key.to_string(), value.to_string())),
+            }
+        }
+
+        if let Ok(sync_settings) = read_file(&mut zip, "SyncSettings.json") {
+            let paks = sync_settings.split("\"PakType\":").skip(1).map(|s| {
+                s.chars()
+                    .take_while(char::is_ascii_digit)
+                    .collect::<String>()
+            });
+            for (flags, pak) in m64.controller_flags.iter_mut().zip(paks) {
+                let pak = pak.parse().unwrap_or(PAK_NONE);
+                flags.has_mempak = pak == PAK_MEMPAK;
+                flags.has_rumblepak = pak == PAK_RUMBLEPAK;
+            }
+        }
+
+        let log = read_file(&mut zip, "Input Log.txt")?;
+        let mut ports = Vec::new();
+        let mut frames = 0;
+        for (i, line) in log.lines().enumerate() {
+            let line = line.trim_end();
+            if let Some(key) = line.strip_prefix("LogKey:") {
+                ports = key
+                    .split('#')
+                    .filter_map(|group| group.strip_prefix('P')?.split(' ').next()?.parse().ok())
+                    .filter(|player: &usize| (1..=4).contains(player))
+                    .map(|player| player - 1)
+                    .collect();
+                continue;
+            }
+            let Some(groups) = line
+                .strip_prefix('|')
+                .and_then(|line| line.strip_suffix('|'))
+            else {
+                continue;
+            };
+
+            let mut groups = groups.split('|');
+            let system = groups.next().unwrap_or_default();
+            let mut inputs = groups
+                .map(parse_input_mnemonic)
+                .collect::<Option<Vec<_>>>()
+                .ok_or(Bk2Error::InvalidInputLog(i + 1))?;
+            if inputs.len() != ports.len() {
+                return Err(Bk2Error::InvalidInputLog(i + 1));
+            }
+
+            let mut system = system.chars();
+            if system.next().is_some_and(|ch| ch != '.') {
+                report.reset_frames.push(frames);
+                if let Some(input) = inputs.first_mut() {
+                    *input = Input {
+                        reserved_1: true,
+                        reserved_2: true,
+                        ..Default::default()
+                    };
+                }
+            }
+            if system.next().is_some_and(|ch| ch != '.') {
+                report.power_frames.push(frames);
+            }
+
+            m64.extend(inputs);
+            frames += 1;
+        }
+
+        for (port, flags) in m64.controller_flags.iter_mut().enumerate() {
+            flags.controller_present = ports.contains(&port);
+        }
+        m64.controller_count = ports.len() as u8;
+        m64.vi_frames = frames as u32;
+
+        for i in 0..zip.len() {
+            let name = zip.by_index(i)?.name().to_string();
+            if !matches!(
+                name.as_str(),
+                "Header.txt" | "SyncSettings.json" | "Input Log.txt"
+            ) {
+                report.ignored_files.push(name);
+            }
+        }
+
+        Ok((m64, report))
+    }
+
+    /// Writes the movie as a BK2 archive to the given writer.
+    /// - See the [module documentation](crate::bk2) for the layout and limitations.
+    pub fn write_bk2<W>(&self, writer: W) -> Result<(), Bk2Error>
+    where
+        W: Write + Seek,
+    {
+        let mut zip = ZipWriter::new(writer);
+        let options = FileOptions::default();
+
+        zip.start_file("Header.txt", options)?;
+        zip.write_all(self.bk2_header().as_bytes())?;
+        zip.start_file("SyncSettings.json", options)?;
+        zip.write_all(self.bk2_sync_settings().as_bytes())?;
+        zip.start_file("Input Log.txt", options)?;
+        zip.write_all(self.bk2_input_log().as_bytes())?;
+        zip.finish()?;
+
+        Ok(())
+    }
+
+    fn bk2_header(&self) -> String {
+        let mut header = String::new();
+        header.push_str("MovieVersion BizHawk v2.0.0\n");
+        header.push_str("Platform N64\n");
+        header.push_str("Core Mupen64Plus\n");
+        header.push_str(&format!(
+            "GameName {}\n",
+            header_value(&self.rom_internal_name)
+        ));
+        header.push_str(&format!("Author {}\n", header_value(&self.author)));
+        header.push_str(&format!("rerecordCount {}\n", self.total_rerecords()));
+        if self.movie_start_type.is_snapshot() {
+            header.push_str("StartsFromSavestate True\n");
+        }
+        if self.fps == 50 {
+            header.push_str("PAL True\n");
+        }
+        header
+    }
+
+    fn bk2_sync_settings(&self) -> String {
+        let controllers = self
+            .controller_flags
+            .iter()
+            .map(|flags| {
+                let pak = if flags.has_mempak {
+                    PAK_MEMPAK
+                } else if flags.has_rumblepak {
+                    PAK_RUMBLEPAK
+                } else {
+                    PAK_NONE
+                };
+                format!(
+                    r#"{{"IsConnected":{},"PakType":{}}}"#,
+                    flags.controller_present, pak
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+
+        format!(
+            r#"{{"o":{{"$type":"BizHawk.Emulation.Cores.Nintendo.N64.N64SyncSettings, BizHawk.Emulation.Cores","Controllers":[{controllers}]}}}}"#
+        )
+    }
+
+    fn bk2_input_log(&self) -> String {
+        let ports = self.controller_ports();
+        let mut log = String::from("[Input]\nLogKey:#Reset|Power|");
+        for port in &ports {
+            let player = port + 1;
+            log.push_str(&format!("#P{player} X Axis|P{player} Y Axis|"));
+            for (_, name, _) in N64_BUTTONS {
+                log.push_str(&format!("P{player} {name}|"));
+            }
+        }
+        log.push('\n');
+
+        for frame in self.frames() {
+            log.push_str("|..|");
+            for port in 0..ports.len() {
+                let input = frame.controller(port).unwrap_or_default();
+                log.push_str(&input_mnemonic(&input));
+                log.push('|');
+            }
+            log.push('\n');
+        }
+
+        log.push_str("[/Input]\n");
+        log
+    }
+}
+
+/// Writes a single controller's input as a BK2 input log group, without the surrounding `|`.
+fn input_mnemonic(input: &Input) -> String {
+    let buttons = input.buttons();
+    let mut mnemonic = format!("{:>5},{:>5},", input.x_axis, input.y_axis);
+    for (button, _, ch) in N64_BUTTONS {
+        mnemonic.push(if buttons.contains(button) { ch } else { '.' });
+    }
+    mnemonic
+}
+
+/// Parses a single controller's BK2 input log group, without the surrounding `|`.
+fn parse_input_mnemonic(group: &str) -> Option<Input> {
+    let mut parts = group.splitn(3, ',');
+    let x_axis = parts.next()?.trim().parse().ok()?;
+    let y_axis = parts.next()?.trim().parse().ok()?;
+    let mnemonic = parts.next()?;
+    if mnemonic.chars().count() != N64_BUTTONS.len() {
+        return None;
+    }
+
+    let mut buttons = Buttons::empty();
+    for ((button, _, _), ch) in N64_BUTTONS.iter().zip(mnemonic.chars()) {
+        if ch != '.' {
+            buttons |= *button;
+        }
+    }
+
+    let mut input = Input {
+        x_axis,
+        y_axis,
+        ..Default::default()
+    };
+    input.set_buttons(buttons);
+    Some(input)
+}
+
+fn read_file<R>(zip: &mut ZipArchive<R>, name: &'static str) -> Result<String, Bk2Error>
+where
+    R: Read + Seek,
+{
+    let mut file = zip.by_name(name).map_err(|_| Bk2Error::MissingFile(name))?;
+    let mut content = String::new();
+    file.read_to_string(&mut content)?;
+    Ok(content)
+}
+
+/// Returns a header value with its line breaks replaced by spaces, so it can't end its entry early and start another one.
+fn header_value(value: &impl std::fmt::Display) -> String {
+    value.to_string().replace(['\r', '\n'], " ")
+}
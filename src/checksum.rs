@@ -0,0 +1,97 @@
+//! Digests of movies over well-defined byte ranges.
+//!
+//! Digests are computed over the bytes the movie is written as, see [`M64::to_bytes`]:
+//! - [`M64::file_digest`] covers the whole file.
+//! - [`M64::header_digest`] covers the 1024 byte header, from offset `0x000` to `0x400`.
+//! - [`M64::inputs_digest`] covers the input samples, from offset `0x400` up to the [metadata block](crate::trailer) if there is one.
+//!
+//! Digests are returned as lowercase hex strings, with CRC32 as the 8 digit big endian value like most tools show it.
+use alloc::{format, string::String};
+use core::{convert::Infallible, ops::Range};
+
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+
+use crate::{m64::M64, parser::HEADER_SIZE, storage::InputStorage};
+
+/// The digest algorithms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DigestAlgorithm {
+    /// CRC32 with the IEEE polynomial, as used by zip.
+    Crc32,
+    /// SHA-1.
+    Sha1,
+    /// SHA-256.
+    Sha256,
+}
+
+impl<S> M64<S>
+where
+    S: InputStorage,
+{
+    /// Returns the digest of the whole file.
+    pub fn file_digest(&self, algorithm: DigestAlgorithm) -> String {
+        self.digest(algorithm, 0..usize::MAX)
+    }
+
+    /// Returns the digest of the header.
+    pub fn header_digest(&self, algorithm: DigestAlgorithm) -> String {
+        self.digest(algorithm, 0..HEADER_SIZE)
+    }
+
+    /// Returns the digest of the input samples.
+    pub fn inputs_digest(&self, algorithm: DigestAlgorithm) -> String {
+        let end = HEADER_SIZE.saturating_add(self.inputs.sample_count().saturating_mul(4));
+        self.digest(algorithm, HEADER_SIZE..end)
+    }
+
+    /// Returns the digest of `range` of the file bytes.
+    fn digest(&self, algorithm: DigestAlgorithm, range: Range<usize>) -> String {
+        let mut hasher = Hasher::new(algorithm);
+        let mut offset = 0;
+        let Ok(()) = self.write_with(|bytes| {
+            let start = range.start.saturating_sub(offset).min(bytes.len());
+            let end = range.end.saturating_sub(offset).min(bytes.len());
+            hasher.update(&bytes[start..end]);
+            offset += bytes.len();
+            Ok::<_, Infallible>(())
+        });
+        hasher.finish()
+    }
+}
+
+enum Hasher {
+    Crc32(crc32fast::Hasher),
+    Sha1(Sha1),
+    Sha256(Sha256),
+}
+
+impl Hasher {
+    fn new(algorithm: DigestAlgorithm) -> Self {
+        match algorithm {
+            DigestAlgorithm::Crc32 => Hasher::Crc32(crc32fast::Hasher::new()),
+            DigestAlgorithm::Sha1 => Hasher::Sha1(Sha1::new()),
+            DigestAlgorithm::Sha256 => Hasher::Sha256(Sha256::new()),
+        }
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Hasher::Crc32(hasher) => hasher.update(bytes),
+            Hasher::Sha1(hasher) => hasher.update(bytes),
+            Hasher::Sha256(hasher) => hasher.update(bytes),
+        }
+    }
+
+    fn finish(self) -> String {
+        match self {
+            Hasher::Crc32(hasher) => format!("{:08x}", hasher.finalize()),
+            Hasher::Sha1(hasher) => hex(&hasher.finalize()),
+            Hasher::Sha256(hasher) => hex(&hasher.finalize()),
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
@@ -0,0 +1,148 @@
+//! Compact encoding for storing and transmitting input samples.
+//!
+//! Consecutive samples of a controller rarely change much, so each sample is XORed with the previous sample
+//! of the same controller, leaving mostly zero deltas that are then run-length encoded.
+//!
+//! # Container
+//! | Offset | Size | Description                                               |
+//! |--------|------|-----------------------------------------------------------|
+//! | 0x00   | 4    | Signature `M64Z`                                          |
+//! | 0x04   | 1    | Version, currently 1                                      |
+//! | 0x05   | 1    | Number of interleaved controllers the deltas are taken by |
+//! | 0x06   | ...  | Number of samples as a varint                             |
+//! | ...    | ...  | Runs                                                      |
+//!
+//! Varints are unsigned LEB128. Each run starts with a varint whose lowest bit is the run kind
+//! and whose remaining bits are the number of samples in the run:
+//! - `0`: The deltas are all zero, so the samples repeat the ones before them.
+//! - `1`: The deltas follow as little endian `u32`s.
+//!
+//! The deltas of the first sample of each controller are taken against 0.
+use alloc::vec::Vec;
+
+use crate::{controller::Input, error::CompressError};
+
+/// Container format version written by this crate.
+pub const COMPRESSED_VERSION: u8 = 1;
+
+const SIGNATURE: &[u8; 4] = b"M64Z";
+
+/// Encodes input samples, taking deltas between samples `controllers` apart.
+/// - Use the controller count of the movie for interleaved samples, which is clamped to `1..=255`.
+pub fn encode_compressed(inputs: &[Input], controllers: usize) -> Vec<u8> {
+    let controllers = controllers.clamp(1, u8::MAX as usize);
+    let mut bytes = SIGNATURE.to_vec();
+    bytes.push(COMPRESSED_VERSION);
+    bytes.push(controllers as u8);
+    write_varint(&mut bytes, inputs.len() as u64);
+
+    let raw = inputs
+        .iter()
+        .map(|&input| u32::from(input))
+        .collect::<Vec<_>>();
+    let deltas = raw
+        .iter()
+        .enumerate()
+        .map(|(i, &sample)| match i.checked_sub(controllers) {
+            Some(previous) => sample ^ raw[previous],
+            None => sample,
+        })
+        .collect::<Vec<_>>();
+
+    let mut i = 0;
+    while i < deltas.len() {
+        let zeros = deltas[i..].iter().take_while(|&&delta| delta == 0).count();
+        if zeros > 0 {
+            write_varint(&mut bytes, (zeros as u64) << 1);
+            i += zeros;
+            continue;
+        }
+
+        // a single zero delta between literals costs more as its own run than as a literal
+        let mut end = i + 1;
+        while end < deltas.len()
+            && (deltas[end] != 0 || deltas.get(end + 1).is_some_and(|&next| next != 0))
+        {
+            end += 1;
+        }
+        let literals = end - i;
+        write_varint(&mut bytes, ((literals as u64) << 1) | 1);
+        for delta in &deltas[i..i + literals] {
+            bytes.extend_from_slice(&delta.to_le_bytes());
+        }
+        i += literals;
+    }
+
+    bytes
+}
+
+/// Decodes input samples encoded with [`encode_compressed`].
+pub fn decode_compressed(bytes: &[u8]) -> Result<Vec<Input>, CompressError> {
+    let rest = bytes
+        .strip_prefix(SIGNATURE)
+        .ok_or(CompressError::InvalidSignature)?;
+    let [version, controllers, ref rest @ ..] = *rest else {
+        return Err(CompressError::UnexpectedEof);
+    };
+    if version != COMPRESSED_VERSION {
+        return Err(CompressError::UnsupportedVersion(version));
+    }
+    let controllers = (controllers as usize).max(1);
+
+    let mut rest = rest;
+    let count = read_varint(&mut rest)? as usize;
+    // every sample takes at least a bit, so this can't be used to allocate more than the input allows
+    let mut raw = Vec::with_capacity(count.min(rest.len() * 8));
+
+    while raw.len() < count {
+        let run = read_varint(&mut rest)?;
+        let len = (run >> 1) as usize;
+        if len > count - raw.len() {
+            return Err(CompressError::RunTooLong);
+        }
+
+        for _ in 0..len {
+            let delta = if run & 1 == 0 {
+                0
+            } else {
+                let (delta, tail) = rest
+                    .split_first_chunk::<4>()
+                    .ok_or(CompressError::UnexpectedEof)?;
+                rest = tail;
+                u32::from_le_bytes(*delta)
+            };
+            let previous = raw
+                .len()
+                .checked_sub(controllers)
+                .map_or(0, |previous| raw[previous]);
+            raw.push(delta ^ previous);
+        }
+    }
+
+    if !rest.is_empty() {
+        return Err(CompressError::TrailingData(rest.len()));
+    }
+
+    Ok(raw.into_iter().map(Input::from).collect())
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn read_varint(bytes: &mut &[u8]) -> Result<u64, CompressError> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes.split_first().ok_or(CompressError::UnexpectedEof)?;
+        *bytes = rest;
+        value |= ((byte & 0x7F) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(CompressError::InvalidVarint)
+}
@@ -0,0 +1,53 @@
+//! Comparing movies by the data that affects gameplay, ignoring the metadata.
+//!
+//! Two movies play back the same if they have the same inputs, start type, controllers and ROM,
+//! regardless of their uid, rerecords, VI count, plugins, author and description.
+use core::hash::{Hash, Hasher};
+
+use crate::m64::M64;
+
+/// Compares and hashes a movie by the data that affects gameplay only.
+/// - Compares the inputs, start type, controller count and flags, and the ROM name, CRC32 and country code.
+/// - Can be used as a `HashSet` or `HashMap` key to find duplicate movies.
+#[derive(Debug, Clone, Copy)]
+pub struct ContentEq<'a>(pub &'a M64);
+
+impl PartialEq for ContentEq<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        let (a, b) = (self.0, other.0);
+        a.eq_inputs(b)
+            && a.movie_start_type == b.movie_start_type
+            && a.controller_count == b.controller_count
+            && a.controller_flags == b.controller_flags
+            && a.rom_internal_name == b.rom_internal_name
+            && a.rom_crc_32 == b.rom_crc_32
+            && a.rom_country_code == b.rom_country_code
+    }
+}
+
+impl Eq for ContentEq<'_> {}
+
+impl Hash for ContentEq<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let m64 = self.0;
+        m64.inputs.hash(state);
+        m64.movie_start_type.hash(state);
+        m64.controller_count.hash(state);
+        m64.controller_flags.hash(state);
+        m64.rom_internal_name.hash(state);
+        m64.rom_crc_32.hash(state);
+        m64.rom_country_code.hash(state);
+    }
+}
+
+impl M64 {
+    /// Returns if both movies have the same input samples, ignoring everything else.
+    pub fn eq_inputs(&self, other: &M64) -> bool {
+        self.inputs == other.inputs
+    }
+
+    /// Returns if both movies play back the same, see [`ContentEq`].
+    pub fn eq_content(&self, other: &M64) -> bool {
+        ContentEq(self) == ContentEq(other)
+    }
+}
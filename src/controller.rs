@@ -1,8 +1,14 @@
 //! Contains structs related to controller information.
-use std::ops::Shr;
+#[cfg(feature = "bytemuck")]
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+
+use bitflags::bitflags;
 
 /// The controller status flags.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Flags {
     /// If the controller is plugged in.
     pub controller_present: bool,
@@ -18,6 +24,7 @@ impl Flags {
     /// - bit 0: controller present
     /// - bit 4: has mempak
     /// - bit 8: has rumblepak
+    ///
     /// add bit 1..3 for controllers 2..4.
     pub fn from_u32(value: u32) -> [Flags; 4] {
         let mut flags = [Flags {
@@ -50,18 +57,65 @@ impl Flags {
 }
 
 fn nth_bit(value: u32, n: usize) -> bool {
-    value.shr(n) & 0x01 != 0
+    (value >> n) & 0x01 != 0
+}
+
+bitflags! {
+    /// The buttons of a single controller input.
+    /// - The bits follow the same layout as the raw input data.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+    pub struct Buttons: u16 {
+        /// Digital pad right.
+        const RIGHT_DPAD = 0x0001;
+        /// Digital pad left.
+        const LEFT_DPAD = 0x0002;
+        /// Digital pad down.
+        const DOWN_DPAD = 0x0004;
+        /// Digital pad up.
+        const UP_DPAD = 0x0008;
+        /// Start button.
+        const START = 0x0010;
+        /// Z button.
+        const Z = 0x0020;
+        /// B button.
+        const B = 0x0040;
+        /// A button.
+        const A = 0x0080;
+        /// C-right.
+        const RIGHT_CBUTTON = 0x0100;
+        /// C-left.
+        const LEFT_CBUTTON = 0x0200;
+        /// C-down.
+        const DOWN_CBUTTON = 0x0400;
+        /// C-up.
+        const UP_CBUTTON = 0x0800;
+        /// Right shoulder button.
+        const RIGHT_SHOULDER = 0x1000;
+        /// Left shoulder button.
+        const LEFT_SHOULDER = 0x2000;
+        /// Reserved.
+        const RESERVED_1 = 0x4000;
+        /// Reserved.
+        const RESERVED_2 = 0x8000;
+
+        /// All digital pad directions.
+        const DPAD = Self::RIGHT_DPAD.bits() | Self::LEFT_DPAD.bits() | Self::DOWN_DPAD.bits() | Self::UP_DPAD.bits();
+        /// All C buttons.
+        const CBUTTONS = Self::RIGHT_CBUTTON.bits() | Self::LEFT_CBUTTON.bits() | Self::DOWN_CBUTTON.bits() | Self::UP_CBUTTON.bits();
+        /// Both reserved bits.
+        const RESERVED = Self::RESERVED_1.bits() | Self::RESERVED_2.bits();
+    }
 }
 
 /// A single frame of controller input.
 /// - Mupen64 re-recordingv2 and later versions will trigger a power off/on reset when the value for the controller info is specifically set to
-/// Reserved1 = 0x01 and Reserved2 = 0x01. The controller info is then cleared from being sent to the PIF RAM to avoid errors.
-/// 
+///   Reserved1 = 0x01 and Reserved2 = 0x01. The controller info is then cleared from being sent to the PIF RAM to avoid errors.
+///
 /// # Raw data structure
 /// | 000 - 001 | 002      | 003      |
 /// |-----------|----------|----------|
 /// | Buttons   | Analog X | Analog Y |
-/// 
+///
 /// ## Buttons
 /// Buttons pressed are determined by or-ing together values for whichever of those are pressed:
 /// - 0x0001 C-Right
@@ -80,7 +134,12 @@ fn nth_bit(value: u32, n: usize) -> bool {
 /// - 0x2000 Z
 /// - 0x4000 B
 /// - 0x8000 A
+///
+/// The fields are laid out in the order they're declared, so the buttons take the first 16 bytes and the axes the last 2.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[repr(C)]
 pub struct Input {
     /// Digital pad up.
     pub up_dpad: bool,
@@ -122,42 +181,11 @@ pub struct Input {
 }
 
 impl From<u32> for Input {
-    fn from(mut value: u32) -> Self {
-        let right_dpad = value & 0x01 != 0;
-        value = value.shr(1);
-        let left_dpad = value & 0x01 != 0;
-        value = value.shr(1);
-        let down_dpad = value & 0x01 != 0;
-        value = value.shr(1);
-        let up_dpad = value & 0x01 != 0;
-        value = value.shr(1);
-        let start = value & 0x01 != 0;
-        value = value.shr(1);
-        let z_button = value & 0x01 != 0;
-        value = value.shr(1);
-        let b_button = value & 0x01 != 0;
-        value = value.shr(1);
-        let a_button = value & 0x01 != 0;
-        value = value.shr(1);
-        let right_cbutton = value & 0x01 != 0;
-        value = value.shr(1);
-        let left_cbutton = value & 0x01 != 0;
-        value = value.shr(1);
-        let down_cbutton = value & 0x01 != 0;
-        value = value.shr(1);
-        let up_cbutton = value & 0x01 != 0;
-        value = value.shr(1);
-        let right_shoulder = value & 0x01 != 0;
-        value = value.shr(1);
-        let left_shoulder = value & 0x01 != 0;
-        value = value.shr(1);
-        let reserved_1 = value & 0x01 != 0;
-        value = value.shr(1);
-        let reserved_2 = value & 0x01 != 0;
-        value = value.shr(1);
-        let x_axis = (value & 0xFF) as i8;
-        value = value.shr(8);
-        let y_axis = value as i8;
+    fn from(value: u32) -> Self {
+        let [right_dpad, left_dpad, down_dpad, up_dpad, start, z_button, b_button, a_button] =
+            SPREAD_BITS[value as u8 as usize];
+        let [right_cbutton, left_cbutton, down_cbutton, up_cbutton, right_shoulder, left_shoulder, reserved_1, reserved_2] =
+            SPREAD_BITS[(value >> 8) as u8 as usize];
 
         Input {
             up_dpad,
@@ -176,35 +204,247 @@ impl From<u32> for Input {
             right_cbutton,
             reserved_1,
             reserved_2,
-            x_axis,
-            y_axis,
+            x_axis: (value >> 16) as i8,
+            y_axis: (value >> 24) as i8,
         }
     }
 }
 
 impl From<Input> for u32 {
     fn from(input: Input) -> Self {
-        let mut value = 0;
+        u32::from_le_bytes([
+            gather_bits([
+                input.right_dpad,
+                input.left_dpad,
+                input.down_dpad,
+                input.up_dpad,
+                input.start,
+                input.z_button,
+                input.b_button,
+                input.a_button,
+            ]),
+            gather_bits([
+                input.right_cbutton,
+                input.left_cbutton,
+                input.down_cbutton,
+                input.up_cbutton,
+                input.right_shoulder,
+                input.left_shoulder,
+                input.reserved_1,
+                input.reserved_2,
+            ]),
+            input.x_axis as u8,
+            input.y_axis as u8,
+        ])
+    }
+}
 
-        value |= input.right_dpad as u32;
-        value |= (input.left_dpad as u32) << 1;
-        value |= (input.down_dpad as u32) << 2;
-        value |= (input.up_dpad as u32) << 3;
-        value |= (input.start as u32) << 4;
-        value |= (input.z_button as u32) << 5;
-        value |= (input.b_button as u32) << 6;
-        value |= (input.a_button as u32) << 7;
-        value |= (input.right_cbutton as u32) << 8;
-        value |= (input.left_cbutton as u32) << 9;
-        value |= (input.down_cbutton as u32) << 10;
-        value |= (input.up_cbutton as u32) << 11;
-        value |= (input.right_shoulder as u32) << 12;
-        value |= (input.left_shoulder as u32) << 13;
-        value |= (input.reserved_1 as u32) << 14;
-        value |= (input.reserved_2 as u32) << 15;
-        value |= ((input.x_axis as u32) & 0xFF) << 16;
-        value |= ((input.y_axis as u32) & 0xFF) << 24;
+/// A raw input sample, as stored in the file.
+/// - Takes 4 bytes instead of the 18 of [`Input`], which it's converted to and from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[repr(transparent)]
+pub struct RawInput(pub u32);
 
-        value
+// SAFETY: `RawInput` is a `repr(transparent)` wrapper of a `u32`, so it has no padding and every bit pattern is valid
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Zeroable for RawInput {}
+#[cfg(feature = "bytemuck")]
+unsafe impl bytemuck::Pod for RawInput {}
+
+impl From<Input> for RawInput {
+    fn from(input: Input) -> Self {
+        RawInput(input.into())
+    }
+}
+
+impl From<RawInput> for Input {
+    fn from(raw: RawInput) -> Self {
+        Input::from(raw.0)
     }
 }
+
+impl Input {
+    /// Returns the pressed buttons.
+    pub fn buttons(&self) -> Buttons {
+        Buttons::from_bits_retain(u32::from(*self) as u16)
+    }
+
+    /// Returns if no buttons are pressed and the analog stick is centered.
+    pub fn is_neutral(&self) -> bool {
+        *self == Input::default()
+    }
+
+    /// Returns if the input triggers a reset, which is when both reserved bits are set.
+    pub fn is_reset(&self) -> bool {
+        self.reserved_1 && self.reserved_2
+    }
+
+    /// Sets the pressed buttons, leaving the analog stick untouched.
+    pub fn set_buttons(&mut self, buttons: Buttons) {
+        let raw = (u32::from(*self) & 0xFFFF_0000) | buttons.bits() as u32;
+        *self = Input::from(raw);
+    }
+}
+
+/// Decodes input samples from the little endian bytes stored in the file.
+/// - Trailing bytes that don't make up a whole sample are ignored.
+pub fn decode_inputs(bytes: &[u8]) -> Vec<Input> {
+    bytes
+        .chunks_exact(4)
+        .map(|sample| decode_sample(u32::from_le_bytes(sample.try_into().unwrap())))
+        .collect()
+}
+
+/// Decodes input samples from their raw values.
+pub fn decode_raw_inputs(raw: &[u32]) -> Vec<Input> {
+    raw.iter().map(|&raw| decode_sample(raw)).collect()
+}
+
+/// Encodes input samples into the little endian bytes stored in the file.
+pub fn encode_inputs(inputs: &[Input]) -> Vec<u8> {
+    inputs
+        .iter()
+        .flat_map(|&input| encode_sample(input).to_le_bytes())
+        .collect()
+}
+
+/// Reads raw input samples from the little endian bytes stored in the file.
+/// - On little endian targets the bytes are borrowed as they are if they're 4 byte aligned, and copied in one go otherwise.
+/// - Trailing bytes that don't make up a whole sample are ignored.
+#[cfg(feature = "bytemuck")]
+pub fn raw_inputs_from_le_bytes(bytes: &[u8]) -> Cow<'_, [RawInput]> {
+    let bytes = &bytes[..bytes.len() - bytes.len() % 4];
+    if cfg!(target_endian = "little") {
+        match bytemuck::try_cast_slice(bytes) {
+            Ok(raw) => Cow::Borrowed(raw),
+            Err(_) => Cow::Owned(bytemuck::pod_collect_to_vec(bytes)),
+        }
+    } else {
+        Cow::Owned(
+            bytes
+                .chunks_exact(4)
+                .map(|sample| RawInput(u32::from_le_bytes(sample.try_into().unwrap())))
+                .collect(),
+        )
+    }
+}
+
+/// Returns the little endian bytes of raw input samples, as they're stored in the file.
+/// - On little endian targets the samples are borrowed as bytes without copying.
+#[cfg(feature = "bytemuck")]
+pub fn raw_inputs_to_le_bytes(raw: &[RawInput]) -> Cow<'_, [u8]> {
+    if cfg!(target_endian = "little") {
+        Cow::Borrowed(bytemuck::cast_slice(raw))
+    } else {
+        Cow::Owned(raw.iter().flat_map(|raw| raw.0.to_le_bytes()).collect())
+    }
+}
+
+/// Encodes input samples into their raw values.
+pub fn encode_raw_inputs(inputs: &[Input]) -> Vec<u32> {
+    inputs.iter().map(|&input| encode_sample(input)).collect()
+}
+
+/// Decodes a raw input sample for the bulk conversions.
+/// - Uses SSE2 on x86_64, where every target has it, and falls back to [`Input::from`] elsewhere.
+#[inline]
+pub(crate) fn decode_sample(raw: u32) -> Input {
+    #[cfg(target_arch = "x86_64")]
+    // SAFETY: SSE2 is part of the x86_64 baseline
+    return unsafe { decode_sample_sse2(raw) };
+    #[cfg(not(target_arch = "x86_64"))]
+    Input::from(raw)
+}
+
+/// Encodes an input sample for the bulk conversions.
+/// - Uses SSE2 on x86_64, where every target has it, and falls back to [`u32::from`] elsewhere.
+#[inline]
+fn encode_sample(input: Input) -> u32 {
+    #[cfg(target_arch = "x86_64")]
+    // SAFETY: SSE2 is part of the x86_64 baseline
+    return unsafe { encode_sample_sse2(input) };
+    #[cfg(not(target_arch = "x86_64"))]
+    u32::from(input)
+}
+
+/// The bit of its button byte each button field of [`Input`] comes from, as a byte per field in field order.
+/// - The first 8 fields come from the low byte of a raw sample, and the last 8 from the high byte.
+#[cfg(target_arch = "x86_64")]
+const BUTTON_FIELD_BITS: [u64; 2] = [0x4080_2010_0102_0408, 0x8040_0102_0408_2010];
+
+/// Decodes a raw input sample with SSE2.
+/// - Each button byte is copied into the 8 lanes of its fields, and each lane is compared against the bit of its field,
+///   which gives the 16 button fields in one go instead of a bit at a time.
+#[cfg(target_arch = "x86_64")]
+#[inline]
+#[target_feature(enable = "sse2")]
+fn decode_sample_sse2(raw: u32) -> Input {
+    use core::arch::x86_64::*;
+
+    let bytes = _mm_cvtsi32_si128(raw as i32);
+    let bytes = _mm_unpacklo_epi8(bytes, bytes);
+    let bytes = _mm_unpacklo_epi16(bytes, bytes);
+    let bytes = _mm_unpacklo_epi32(bytes, bytes);
+    let bits = _mm_set_epi64x(BUTTON_FIELD_BITS[1] as i64, BUTTON_FIELD_BITS[0] as i64);
+    let pressed = _mm_cmpeq_epi8(_mm_and_si128(bytes, bits), bits);
+    let pressed = _mm_and_si128(pressed, _mm_set1_epi8(1));
+
+    let mut fields = [0; 18];
+    fields[..8].copy_from_slice(&_mm_cvtsi128_si64(pressed).to_le_bytes());
+    fields[8..16]
+        .copy_from_slice(&_mm_cvtsi128_si64(_mm_unpackhi_epi64(pressed, pressed)).to_le_bytes());
+    fields[16..].copy_from_slice(&raw.to_le_bytes()[2..]);
+    // SAFETY: `Input` is `repr(C)` with 16 `bool`s followed by 2 `i8`s, and each button byte is 0 or 1
+    unsafe { core::mem::transmute::<[u8; 18], Input>(fields) }
+}
+
+/// Encodes an input sample with SSE2.
+/// - Each pressed button field is turned into the bit it's stored in, and the bits of each button byte are added together in one go.
+#[cfg(target_arch = "x86_64")]
+#[inline]
+#[target_feature(enable = "sse2")]
+fn encode_sample_sse2(input: Input) -> u32 {
+    use core::arch::x86_64::*;
+
+    // SAFETY: `Input` is `repr(C)` with 16 `bool`s followed by 2 `i8`s, so it has no padding
+    let fields = unsafe { core::mem::transmute::<Input, [u8; 18]>(input) };
+    let pressed = _mm_set_epi64x(
+        i64::from_le_bytes(fields[8..16].try_into().unwrap()),
+        i64::from_le_bytes(fields[..8].try_into().unwrap()),
+    );
+    let bits = _mm_set_epi64x(BUTTON_FIELD_BITS[1] as i64, BUTTON_FIELD_BITS[0] as i64);
+    let bits = _mm_and_si128(_mm_sub_epi8(_mm_setzero_si128(), pressed), bits);
+    let buttons = _mm_sad_epu8(bits, _mm_setzero_si128());
+
+    u32::from_le_bytes([
+        _mm_cvtsi128_si32(buttons) as u8,
+        _mm_extract_epi16::<4>(buttons) as u8,
+        fields[16],
+        fields[17],
+    ])
+}
+
+/// Each possible byte spread into one `bool` per bit, starting from the lowest bit.
+/// - Decoding a button byte is a single lookup instead of shifting out each bit.
+const SPREAD_BITS: [[bool; 8]; 256] = {
+    let mut table = [[false; 8]; 256];
+    let mut byte = 0;
+    while byte < 256 {
+        let mut bit = 0;
+        while bit < 8 {
+            table[byte][bit] = byte & (1 << bit) != 0;
+            bit += 1;
+        }
+        byte += 1;
+    }
+    table
+};
+
+/// Packs 8 `bool`s into a byte, starting from the lowest bit.
+/// - As each `bool` is a 0 or 1 byte, a single multiplication moves every bit into the top byte without carries.
+fn gather_bits(bits: [bool; 8]) -> u8 {
+    let bytes = u64::from_le_bytes(bits.map(u8::from));
+    (bytes.wrapping_mul(0x0102_0408_1020_4080) >> 56) as u8
+}
@@ -0,0 +1,266 @@
+//! Conversions between the supported movie formats through [`TryFrom`].
+//!
+//! Movies in other formats are held as their raw bytes in a [`TasdFile`] or [`Bk2File`],
+//! and every conversion returns the converted movie in a [`Converted`] along with a [`ConversionReport`].
+//! - Converting from a `.m64` file reads the result back, and lists every header field that didn't survive.
+//! - Converting to a `.m64` file lists the entries of the source that have no matching field.
+//! - BK2 and TASD are converted into each other through a `.m64` file, with both reports merged.
+//!
+//! ```
+//! use mupen64_movie_parser::{
+//!     convert::{Converted, TasdFile},
+//!     error::FieldName,
+//!     M64,
+//! };
+//!
+//! let m64 = M64::from_u8_array(include_bytes!("./tests/m64s/120 star tas (2012).m64")).unwrap();
+//! let tasd = Converted::<TasdFile>::try_from(&m64).unwrap();
+//! assert!(tasd.report.lossy_fields.contains(&FieldName::Uid));
+//! ```
+#[cfg(feature = "bk2")]
+use std::io::Cursor;
+
+use crate::{
+    error::{ConversionError, FieldName},
+    m64::M64,
+    tasd::skipped_packets,
+};
+
+/// The raw bytes of a TASD file.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct TasdFile(pub Vec<u8>);
+
+/// The raw bytes of a BK2 archive.
+#[cfg(feature = "bk2")]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Bk2File(pub Vec<u8>);
+
+/// A converted movie, along with what couldn't be converted.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Converted<T> {
+    /// The converted movie.
+    pub movie: T,
+    /// What was lost in the conversion.
+    pub report: ConversionReport,
+}
+
+/// What was lost when converting a movie from one format to another.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct ConversionReport {
+    /// Header fields that the target format couldn't store as they were.
+    pub lossy_fields: Vec<FieldName>,
+    /// The inputs couldn't be stored as they were.
+    pub lossy_inputs: bool,
+    /// Entries of the source that have no matching field in the target format, as key value pairs.
+    pub unmapped: Vec<(String, String)>,
+}
+
+impl ConversionReport {
+    /// Returns `true` if nothing was lost in the conversion.
+    pub fn is_lossless(&self) -> bool {
+        self.lossy_fields.is_empty() && !self.lossy_inputs && self.unmapped.is_empty()
+    }
+
+    /// Adds the findings of a later conversion step, skipping the ones already listed.
+    pub fn merge(&mut self, other: ConversionReport) {
+        for field in other.lossy_fields {
+            if !self.lossy_fields.contains(&field) {
+                self.lossy_fields.push(field);
+            }
+        }
+        self.lossy_inputs |= other.lossy_inputs;
+        for entry in other.unmapped {
+            if !self.unmapped.contains(&entry) {
+                self.unmapped.push(entry);
+            }
+        }
+    }
+}
+
+impl TryFrom<&M64> for Converted<TasdFile> {
+    type Error = ConversionError;
+
+    fn try_from(m64: &M64) -> Result<Self, Self::Error> {
+        let mut bytes = Vec::new();
+        m64.write_tasd(&mut bytes)?;
+        let report = ConversionReport::compare(m64, &M64::from_tasd(&bytes)?);
+
+        Ok(Converted {
+            movie: TasdFile(bytes),
+            report,
+        })
+    }
+}
+
+impl TryFrom<&TasdFile> for Converted<M64> {
+    type Error = ConversionError;
+
+    fn try_from(tasd: &TasdFile) -> Result<Self, Self::Error> {
+        let movie = M64::from_tasd(&tasd.0)?;
+        let report = ConversionReport {
+            unmapped: skipped_packets(&tasd.0)?
+                .into_iter()
+                .map(|key| ("packet".to_string(), format!("{key:#06X}")))
+                .collect(),
+            ..Default::default()
+        };
+
+        Ok(Converted { movie, report })
+    }
+}
+
+#[cfg(feature = "bk2")]
+impl TryFrom<&M64> for Converted<Bk2File> {
+    type Error = ConversionError;
+
+    fn try_from(m64: &M64) -> Result<Self, Self::Error> {
+        let mut cursor = Cursor::new(Vec::new());
+        m64.write_bk2(&mut cursor)?;
+        let bytes = cursor.into_inner();
+        let (read_back, _) = M64::read_bk2(Cursor::new(&bytes))?;
+        let report = ConversionReport::compare(m64, &read_back);
+
+        Ok(Converted {
+            movie: Bk2File(bytes),
+            report,
+        })
+    }
+}
+
+#[cfg(feature = "bk2")]
+impl TryFrom<&Bk2File> for Converted<M64> {
+    type Error = ConversionError;
+
+    fn try_from(bk2: &Bk2File) -> Result<Self, Self::Error> {
+        let (movie, import) = M64::read_bk2(Cursor::new(&bk2.0))?;
+
+        let mut report = ConversionReport {
+            lossy_fields: import.truncated_fields,
+            unmapped: import.unmapped_header,
+            ..Default::default()
+        };
+        report.unmapped.extend(
+            import
+                .ignored_files
+                .into_iter()
+                .map(|name| ("file".to_string(), name)),
+        );
+        report.unmapped.extend(
+            import
+                .power_frames
+                .into_iter()
+                .map(|frame| ("Power".to_string(), frame.to_string())),
+        );
+
+        Ok(Converted { movie, report })
+    }
+}
+
+#[cfg(feature = "bk2")]
+impl TryFrom<&Bk2File> for Converted<TasdFile> {
+    type Error = ConversionError;
+
+    fn try_from(bk2: &Bk2File) -> Result<Self, Self::Error> {
+        let Converted { movie, mut report } = Converted::<M64>::try_from(bk2)?;
+        let tasd = Converted::<TasdFile>::try_from(&movie)?;
+        report.merge(tasd.report);
+
+        Ok(Converted {
+            movie: tasd.movie,
+            report,
+        })
+    }
+}
+
+#[cfg(feature = "bk2")]
+impl TryFrom<&TasdFile> for Converted<Bk2File> {
+    type Error = ConversionError;
+
+    fn try_from(tasd: &TasdFile) -> Result<Self, Self::Error> {
+        let Converted { movie, mut report } = Converted::<M64>::try_from(tasd)?;
+        let bk2 = Converted::<Bk2File>::try_from(&movie)?;
+        report.merge(bk2.report);
+
+        Ok(Converted {
+            movie: bk2.movie,
+            report,
+        })
+    }
+}
+
+impl ConversionReport {
+    /// Compares the original movie with the converted one read back.
+    fn compare(original: &M64, converted: &M64) -> Self {
+        let fields = [
+            (FieldName::Uid, original.uid == converted.uid),
+            (
+                FieldName::ViFrames,
+                original.vi_frames == converted.vi_frames,
+            ),
+            (
+                FieldName::InputFrames,
+                original.input_frames == converted.input_frames,
+            ),
+            (
+                FieldName::Rerecords,
+                original.total_rerecords() == converted.total_rerecords(),
+            ),
+            (FieldName::Fps, original.fps == converted.fps),
+            (
+                FieldName::ControllerCount,
+                original.controller_count == converted.controller_count,
+            ),
+            (
+                FieldName::MovieStartType,
+                original.movie_start_type == converted.movie_start_type,
+            ),
+            (
+                FieldName::ControllerFlags,
+                original.controller_flags == converted.controller_flags,
+            ),
+            (
+                FieldName::RomInternalName,
+                original.rom_internal_name == converted.rom_internal_name,
+            ),
+            (
+                FieldName::RomCrc32,
+                original.rom_crc_32 == converted.rom_crc_32,
+            ),
+            (
+                FieldName::RomCountryCode,
+                original.rom_country_code == converted.rom_country_code,
+            ),
+            (
+                FieldName::VideoPlugin,
+                original.video_plugin == converted.video_plugin,
+            ),
+            (
+                FieldName::SoundPlugin,
+                original.sound_plugin == converted.sound_plugin,
+            ),
+            (
+                FieldName::InputPlugin,
+                original.input_plugin == converted.input_plugin,
+            ),
+            (
+                FieldName::RspPlugin,
+                original.rsp_plugin == converted.rsp_plugin,
+            ),
+            (FieldName::Author, original.author == converted.author),
+            (
+                FieldName::Description,
+                original.description == converted.description,
+            ),
+        ];
+
+        ConversionReport {
+            lossy_fields: fields
+                .into_iter()
+                .filter(|(_, same)| !same)
+                .map(|(field, _)| field)
+                .collect(),
+            lossy_inputs: original.inputs != converted.inputs,
+            unmapped: Vec::new(),
+        }
+    }
+}
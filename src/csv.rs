@@ -0,0 +1,114 @@
+//! Exporting and importing inputs as CSV.
+//!
+//! The CSV starts with the header row `frame,controller,buttons,x,y`, followed by a row for each input sample:
+//! - `frame`: frame index, starting from 0.
+//! - `controller`: controller index, starting from 0.
+//! - `buttons`: names of the pressed buttons joined by `+` such as `A+Z+UP_DPAD`, or empty if none are pressed.
+//!   The names are the same as the constants of [`Buttons`].
+//! - `x`, `y`: analog stick axes.
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+use crate::{
+    controller::{Buttons, Input},
+    error::CsvError,
+    m64::M64,
+};
+
+/// The header row of the CSV.
+pub const CSV_HEADER: &str = "frame,controller,buttons,x,y";
+
+/// Most input samples a single row can add past the end of the samples read so far, including the neutral ones skipped over.
+/// - Keeps a row with a huge frame index from allocating a huge movie.
+pub const MAX_INPUT_GAP: usize = 0x40_0000;
+
+impl M64 {
+    /// Writes the inputs as CSV to the given writer.
+    /// - See the [module documentation](crate::csv) for the columns.
+    pub fn write_inputs_csv<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        writeln!(writer, "{CSV_HEADER}")?;
+        for (frame, inputs) in self.enumerate_frames() {
+            for (controller, input) in inputs.iter().enumerate() {
+                let buttons = input
+                    .buttons()
+                    .iter_names()
+                    .map(|(name, _)| name)
+                    .collect::<Vec<_>>()
+                    .join("+");
+                writeln!(
+                    writer,
+                    "{frame},{controller},{buttons},{},{}",
+                    input.x_axis, input.y_axis
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Creates a movie from the header of `header` and inputs read as CSV from the given reader.
+    /// - The inputs of `header` are discarded, and `input_frames` is set to the number of samples read.
+    /// - Rows can be in any order, and samples without a row are neutral.
+    /// - A row can't be more than [`MAX_INPUT_GAP`] samples past the last sample read so far.
+    /// - See the [module documentation](crate::csv) for the columns.
+    pub fn read_inputs_csv<R>(reader: R, header: M64) -> Result<M64, CsvError>
+    where
+        R: Read,
+    {
+        let mut m64 = M64 {
+            input_frames: 0,
+            inputs: Vec::new(),
+            ..header
+        };
+        let stride = m64.samples_per_frame();
+        let mut inputs = Vec::new();
+
+        for (i, line) in BufReader::new(reader).lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || (i == 0 && line == CSV_HEADER) {
+                continue;
+            }
+
+            let (index, input) = parse_row(line, stride).ok_or(CsvError::InvalidRow(i + 1))?;
+            if inputs.len() <= index {
+                if index - inputs.len() >= MAX_INPUT_GAP {
+                    return Err(CsvError::InputGap(i + 1));
+                }
+                inputs.resize(index + 1, Input::default());
+            }
+            inputs[index] = input;
+        }
+
+        m64.extend(inputs);
+        Ok(m64)
+    }
+}
+
+/// Parses a single row, returning the sample index and the input.
+fn parse_row(line: &str, stride: usize) -> Option<(usize, Input)> {
+    let mut columns = line.split(',').map(str::trim);
+    let frame = columns.next()?.parse::<usize>().ok()?;
+    let controller = columns.next()?.parse::<usize>().ok()?;
+    let buttons_column = columns.next()?;
+    let x_axis = columns.next()?.parse().ok()?;
+    let y_axis = columns.next()?.parse().ok()?;
+    if columns.next().is_some() || controller >= stride {
+        return None;
+    }
+
+    let mut buttons = Buttons::empty();
+    for name in buttons_column.split('+').filter(|name| !name.is_empty()) {
+        buttons |= Buttons::from_name(name)?;
+    }
+
+    let mut input = Input {
+        x_axis,
+        y_axis,
+        ..Default::default()
+    };
+    input.set_buttons(buttons);
+    Some((frame.checked_mul(stride)?.checked_add(controller)?, input))
+}
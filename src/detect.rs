@@ -0,0 +1,84 @@
+//! Recognizing which movie format a file is in from its first bytes.
+//!
+//! Only the signatures and the fixed fields after them are checked, so detection is cheap and never fails,
+//! but a file that is detected still has to be parsed to know that it's valid.
+
+/// The file formats that [`detect_format`] recognizes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MovieFileFormat {
+    /// A Mupen64 `.m64` movie.
+    M64,
+    /// A BizHawk `.bk2` movie, which is a zip archive.
+    Bk2,
+    /// A TASD interchange file.
+    Tasd,
+    /// A gzip compressed file, such as a gzipped movie or a Mupen64 savestate.
+    Gzip,
+    /// None of the known formats.
+    Unknown,
+}
+
+/// How sure [`detect_format`] is about the format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Confidence {
+    /// Nothing or only part of the signature matched.
+    Low,
+    /// The signature matched, but the fields after it don't look like the format.
+    Medium,
+    /// The signature and the fields after it match the format.
+    High,
+}
+
+/// The result of [`detect_format`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DetectedFormat {
+    /// The detected format.
+    pub format: MovieFileFormat,
+    /// How sure the detection is.
+    pub confidence: Confidence,
+}
+
+/// Detects the format of a movie file from its bytes.
+/// - `.m64` files need the signature and version 3, and a length that fits a header followed by whole input samples.
+/// - BK2 files are zip archives, and need a `Header.txt` or `Input Log.txt` file to be told apart from other zip archives.
+/// - TASD files need the signature, version 1 and a key length of 2.
+/// - Gzip files need the magic number and the deflate compression method.
+pub fn detect_format(data: &[u8]) -> DetectedFormat {
+    let detected = |format, high: bool| DetectedFormat {
+        format,
+        confidence: if high {
+            Confidence::High
+        } else {
+            Confidence::Medium
+        },
+    };
+
+    if data.starts_with(b"M64\x1A") {
+        let valid = data.get(4..8) == Some(&3u32.to_le_bytes())
+            && data.len() >= 0x400
+            && (data.len() - 0x400).is_multiple_of(4);
+        detected(MovieFileFormat::M64, valid)
+    } else if data.starts_with(b"PK\x03\x04") {
+        let bk2 = [&b"Header.txt"[..], b"Input Log.txt"]
+            .iter()
+            .any(|name| data.windows(name.len()).any(|window| window == *name));
+        if bk2 {
+            detected(MovieFileFormat::Bk2, true)
+        } else {
+            DetectedFormat {
+                format: MovieFileFormat::Bk2,
+                confidence: Confidence::Low,
+            }
+        }
+    } else if data.starts_with(b"TASD") {
+        let valid = data.get(4..7) == Some(&[0, 1, 2]);
+        detected(MovieFileFormat::Tasd, valid)
+    } else if data.starts_with(&[0x1F, 0x8B]) {
+        detected(MovieFileFormat::Gzip, data.get(2) == Some(&8))
+    } else {
+        DetectedFormat {
+            format: MovieFileFormat::Unknown,
+            confidence: Confidence::Low,
+        }
+    }
+}
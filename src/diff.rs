@@ -0,0 +1,292 @@
+//! Computing the differences between the inputs of two movies.
+use std::ops::Range;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+#[cfg(feature = "rayon")]
+use crate::storage::PARALLEL_MIN_SAMPLES;
+use crate::{controller::Input, frame::Frame, m64::M64};
+
+/// Number of frames around the divergence returned by [`M64::first_divergence`].
+pub const DEFAULT_DIVERGENCE_CONTEXT: usize = 5;
+
+/// Above this many edits, the differing section is reported as a single replacement instead.
+const MAX_EDIT_DISTANCE: usize = 2000;
+
+/// A single edit that turns the inputs of one movie into the inputs of another movie.
+/// - Ranges are in frames of a single controller.
+/// - Ranges of `self` refer to the original movie, ranges of `other` to the movie being compared against.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DiffOp {
+    /// Frames from `other` are inserted before frame `at` of `self`.
+    Insert {
+        controller: usize,
+        at: usize,
+        other: Range<usize>,
+    },
+    /// Frames of `self` are deleted.
+    Delete {
+        controller: usize,
+        range: Range<usize>,
+    },
+    /// Frames of `self` are replaced with frames from `other`.
+    Replace {
+        controller: usize,
+        range: Range<usize>,
+        other: Range<usize>,
+    },
+}
+
+impl DiffOp {
+    /// Returns the controller the edit is for.
+    pub fn controller(&self) -> usize {
+        match self {
+            DiffOp::Insert { controller, .. }
+            | DiffOp::Delete { controller, .. }
+            | DiffOp::Replace { controller, .. } => *controller,
+        }
+    }
+}
+
+/// The first frame where the inputs of two movies differ, along with the frames around it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Divergence {
+    /// The first frame that differs.
+    pub frame: usize,
+    /// The first controller whose input differs on that frame.
+    /// - If one movie ends before the other, this is the first controller the shorter movie is missing an input for.
+    pub controller: usize,
+    /// Index of the first frame in `ours` and `theirs`.
+    pub context_start: usize,
+    /// Frames of the first movie around the divergence.
+    pub ours: Vec<Frame>,
+    /// Frames of the second movie around the divergence.
+    pub theirs: Vec<Frame>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    Equal,
+    Insert,
+    Delete,
+}
+
+impl M64 {
+    /// Returns the edits that turn the inputs of `self` into the inputs of `other`, for every controller.
+    /// - Each controller is compared separately, in controller order.
+    /// - A controller missing from either movie is treated as having no inputs.
+    pub fn diff_inputs(&self, other: &M64) -> Vec<DiffOp> {
+        let controllers = self.samples_per_frame().max(other.samples_per_frame());
+
+        (0..controllers)
+            .flat_map(|controller| {
+                let a = self.inputs_for_controller(controller).collect::<Vec<_>>();
+                let b = other.inputs_for_controller(controller).collect::<Vec<_>>();
+                diff(controller, &a, &b)
+            })
+            .collect()
+    }
+}
+
+impl M64 {
+    /// Returns the first frame where the inputs of `self` and `other` differ,
+    /// with [`DEFAULT_DIVERGENCE_CONTEXT`] frames of context on each side.
+    /// - Returns `None` if the inputs are the same.
+    pub fn first_divergence(&self, other: &M64) -> Option<Divergence> {
+        self.first_divergence_with_context(other, DEFAULT_DIVERGENCE_CONTEXT)
+    }
+
+    /// Returns the first frame where the inputs of `self` and `other` differ, with `context` frames of context on each side.
+    /// - Returns `None` if the inputs are the same.
+    /// - With the `rayon` feature, long movies are compared in parallel.
+    pub fn first_divergence_with_context(&self, other: &M64, context: usize) -> Option<Divergence> {
+        let (frame, controller) = first_different_frame(self, other).or_else(|| {
+            let (frames, other_frames) = (self.frame_count(), other.frame_count());
+            (frames != other_frames).then_some((frames.min(other_frames), 0))
+        })?;
+
+        let context_start = frame.saturating_sub(context);
+        let window = |m64: &M64| {
+            m64.frames()
+                .skip(context_start)
+                .take(frame - context_start + context + 1)
+                .collect()
+        };
+
+        Some(Divergence {
+            frame,
+            controller,
+            context_start,
+            ours: window(self),
+            theirs: window(other),
+        })
+    }
+}
+
+/// Returns the first frame where the inputs of the movies differ, along with the first controller that differs.
+/// - Frames past the end of either movie aren't compared.
+#[cfg(not(feature = "rayon"))]
+fn first_different_frame(a: &M64, b: &M64) -> Option<(usize, usize)> {
+    a.inputs
+        .chunks(a.samples_per_frame())
+        .zip(b.inputs.chunks(b.samples_per_frame()))
+        .enumerate()
+        .find_map(|(frame, (a, b))| Some((frame, different_controller(a, b)?)))
+}
+
+/// Returns the first frame where the inputs of the movies differ, along with the first controller that differs.
+/// - Frames past the end of either movie aren't compared.
+/// - Chunks of frames are compared in parallel, and the earliest difference is returned.
+#[cfg(feature = "rayon")]
+fn first_different_frame(a: &M64, b: &M64) -> Option<(usize, usize)> {
+    a.inputs
+        .par_chunks(a.samples_per_frame())
+        .zip(b.inputs.par_chunks(b.samples_per_frame()))
+        .enumerate()
+        .with_min_len(PARALLEL_MIN_SAMPLES)
+        .find_map_first(|(frame, (a, b))| Some((frame, different_controller(a, b)?)))
+}
+
+/// Returns the first controller whose input differs between the frames.
+fn different_controller(a: &[Input], b: &[Input]) -> Option<usize> {
+    (0..a.len().max(b.len())).find(|&controller| a.get(controller) != b.get(controller))
+}
+
+/// Diffs the inputs of a single controller.
+pub(crate) fn diff(controller: usize, a: &[Input], b: &[Input]) -> Vec<DiffOp> {
+    let prefix = a.iter().zip(b).take_while(|(a, b)| a == b).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a_middle = &a[prefix..a.len() - suffix];
+    let b_middle = &b[prefix..b.len() - suffix];
+
+    let edits = myers(a_middle, b_middle, MAX_EDIT_DISTANCE).unwrap_or_else(|| {
+        let mut edits = vec![Edit::Delete; a_middle.len()];
+        edits.extend(std::iter::repeat_n(Edit::Insert, b_middle.len()));
+        edits
+    });
+
+    let mut ops = Vec::new();
+    let (mut i, mut j) = (prefix, prefix);
+    let mut edits = edits.into_iter().peekable();
+
+    while let Some(edit) = edits.next() {
+        if edit == Edit::Equal {
+            i += 1;
+            j += 1;
+            continue;
+        }
+
+        let (start_i, start_j) = (i, j);
+        let mut edit = Some(edit);
+        while let Some(current) = edit {
+            match current {
+                Edit::Delete => i += 1,
+                Edit::Insert => j += 1,
+                Edit::Equal => unreachable!(),
+            }
+            edit = edits.next_if(|edit| *edit != Edit::Equal);
+        }
+
+        ops.push(match (start_i < i, start_j < j) {
+            (true, true) => DiffOp::Replace {
+                controller,
+                range: start_i..i,
+                other: start_j..j,
+            },
+            (true, false) => DiffOp::Delete {
+                controller,
+                range: start_i..i,
+            },
+            _ => DiffOp::Insert {
+                controller,
+                at: start_i,
+                other: start_j..j,
+            },
+        });
+    }
+
+    ops
+}
+
+/// Myers' diff algorithm, returning the edits in order.
+/// - Returns `None` if more than `max_distance` inserts and deletes are needed.
+fn myers(a: &[Input], b: &[Input], max_distance: usize) -> Option<Vec<Edit>> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = (a.len() + b.len()).min(max_distance) as isize;
+    let offset = max + 1;
+    let mut v = vec![0isize; 2 * max as usize + 3];
+    // snapshot of `v` at the start of each step `d`, for the diagonals `-d..=d`
+    let mut trace = Vec::new();
+
+    for d in 0..=max {
+        trace.push(v[(offset - d) as usize..=(offset + d) as usize].to_vec());
+
+        for k in (-d..=d).step_by(2) {
+            let index = (k + offset) as usize;
+            let mut x = if k == -d || (k != d && v[index - 1] < v[index + 1]) {
+                v[index + 1]
+            } else {
+                v[index - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[index] = x;
+
+            if x >= n && y >= m {
+                return Some(backtrack(&trace, n, m));
+            }
+        }
+    }
+
+    None
+}
+
+fn backtrack(trace: &[Vec<isize>], n: isize, m: isize) -> Vec<Edit> {
+    let (mut x, mut y) = (n, m);
+    let mut edits = Vec::new();
+
+    for (d, snapshot) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let get = |k: isize| snapshot[(k + d) as usize];
+        let k = x - y;
+
+        let (prev_x, prev_y) = if d == 0 {
+            (0, 0)
+        } else {
+            let prev_k = if k == -d || (k != d && get(k - 1) < get(k + 1)) {
+                k + 1
+            } else {
+                k - 1
+            };
+            let prev_x = get(prev_k);
+            (prev_x, prev_x - prev_k)
+        };
+
+        while x > prev_x && y > prev_y {
+            edits.push(Edit::Equal);
+            x -= 1;
+            y -= 1;
+        }
+        if d > 0 {
+            edits.push(if x == prev_x {
+                Edit::Insert
+            } else {
+                Edit::Delete
+            });
+            x = prev_x;
+            y = prev_y;
+        }
+    }
+
+    edits.reverse();
+    edits
+}
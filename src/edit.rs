@@ -0,0 +1,201 @@
+//! Editing operations on the inputs of a movie.
+use alloc::vec::Vec;
+use core::ops::{Bound, Range, RangeBounds};
+
+use crate::{
+    controller::{Buttons, Input},
+    frame::Frame,
+    m64::M64,
+};
+
+/// How edits made through a [`MovieEditor`] change the rerecord count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum RerecordPolicy {
+    /// The rerecord count is left as it is.
+    #[default]
+    Keep,
+    /// The rerecord count is increased by 1 for every edit.
+    PerEdit,
+    /// The rerecord count is increased by 1 on the first edit only.
+    Once,
+}
+
+/// Edits a movie while keeping track of the number of edits, and updating the rerecord count by a [`RerecordPolicy`].
+/// - Created with [`M64::editor`].
+/// - Each method call counts as a single edit, even if it changes nothing.
+#[derive(Debug)]
+pub struct MovieEditor<'a> {
+    m64: &'a mut M64,
+    policy: RerecordPolicy,
+    edits: usize,
+}
+
+impl MovieEditor<'_> {
+    /// Returns the movie being edited.
+    pub fn m64(&self) -> &M64 {
+        self.m64
+    }
+
+    /// Returns the number of edits made through the editor.
+    pub fn edit_count(&self) -> usize {
+        self.edits
+    }
+
+    /// Applies any change to the movie as a single edit.
+    /// - This is the hook for edits that have no method of their own.
+    pub fn apply<F, T>(&mut self, edit: F) -> T
+    where
+        F: FnOnce(&mut M64) -> T,
+    {
+        let result = edit(self.m64);
+        self.edits += 1;
+        let increment = match self.policy {
+            RerecordPolicy::Keep => false,
+            RerecordPolicy::PerEdit => true,
+            RerecordPolicy::Once => self.edits == 1,
+        };
+        if increment {
+            let rerecords = self.m64.total_rerecords();
+            self.m64.set_total_rerecords(rerecords.saturating_add(1));
+        }
+        result
+    }
+
+    /// Replaces an input sample, see [`M64::set_input`].
+    pub fn set_input(&mut self, index: usize, input: Input) -> Option<Input> {
+        self.apply(|m64| m64.set_input(index, input))
+    }
+
+    /// Appends a frame, see [`M64::push_frame`].
+    pub fn push_frame(&mut self, frame: Frame) -> Result<(), Frame> {
+        self.apply(|m64| m64.push_frame(frame))
+    }
+
+    /// Releases buttons over a range of frames, see [`M64::strip_buttons`].
+    pub fn strip_buttons<R>(&mut self, mask: Buttons, frames: R)
+    where
+        R: RangeBounds<usize>,
+    {
+        self.apply(|m64| m64.strip_buttons(mask, frames))
+    }
+
+    /// Releases every button not in `mask`, see [`M64::retain_buttons`].
+    pub fn retain_buttons(&mut self, mask: Buttons) {
+        self.apply(|m64| m64.retain_buttons(mask))
+    }
+
+    /// Removes the trailing idle frames, see [`M64::trim_trailing_idle`].
+    #[cfg(feature = "std")]
+    pub fn trim_trailing_idle(&mut self) -> usize {
+        self.apply(M64::trim_trailing_idle)
+    }
+}
+
+impl M64 {
+    /// Returns an editor that updates the rerecord count by `policy` as edits are made.
+    pub fn editor(&mut self, policy: RerecordPolicy) -> MovieEditor<'_> {
+        MovieEditor {
+            m64: self,
+            policy,
+            edits: 0,
+        }
+    }
+
+    /// Converts a range of frames into a range of input samples, clamped to the inputs.
+    pub fn frame_range_to_samples<R>(&self, frames: R) -> Range<usize>
+    where
+        R: RangeBounds<usize>,
+    {
+        let stride = self.samples_per_frame();
+        let start = match frames.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        let end = match frames.end_bound() {
+            Bound::Included(&end) => end.saturating_add(1),
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => usize::MAX,
+        };
+
+        let len = self.inputs.len();
+        let start = start.saturating_mul(stride).min(len);
+        let end = end.saturating_mul(stride).clamp(start, len);
+        start..end
+    }
+
+    /// Inserts frames before the frame at `at`, and increases `input_frames` by the number of samples inserted.
+    /// - `at` is clamped to the end of the inputs.
+    /// - Returns the first frame that doesn't hold an input for every controller, without inserting anything.
+    pub fn insert_frames<I>(&mut self, at: usize, frames: I) -> Result<(), Frame>
+    where
+        I: IntoIterator<Item = Frame>,
+    {
+        let stride = self.samples_per_frame();
+        let mut samples = Vec::new();
+        for frame in frames {
+            if frame.len() != stride {
+                return Err(frame);
+            }
+            samples.extend_from_slice(&frame);
+        }
+
+        let at = self.frame_range_to_samples(at..).start;
+        self.input_frames = self.input_frames.saturating_add(samples.len() as u32);
+        self.inputs.splice(at..at, samples);
+        Ok(())
+    }
+
+    /// Removes a range of frames, and reduces `input_frames` by the number of samples removed.
+    /// - Returns the removed frames.
+    pub fn remove_frames<R>(&mut self, frames: R) -> Vec<Frame>
+    where
+        R: RangeBounds<usize>,
+    {
+        let range = self.frame_range_to_samples(frames);
+        self.input_frames = self.input_frames.saturating_sub(range.len() as u32);
+        let removed = self.inputs.drain(range).collect::<Vec<_>>();
+        removed
+            .chunks(self.samples_per_frame())
+            .map(|inputs| Frame::from_inputs(inputs).unwrap())
+            .collect()
+    }
+
+    /// Releases the buttons in `mask` for every controller in the range of frames.
+    /// - The analog stick is left untouched.
+    pub fn strip_buttons<R>(&mut self, mask: Buttons, frames: R)
+    where
+        R: RangeBounds<usize>,
+    {
+        let range = self.frame_range_to_samples(frames);
+        for input in &mut self.inputs[range] {
+            input.set_buttons(input.buttons() - mask);
+        }
+    }
+
+    /// Releases every button not in `mask` for the whole movie.
+    /// - The analog stick is left untouched.
+    pub fn retain_buttons(&mut self, mask: Buttons) {
+        for input in &mut self.inputs {
+            input.set_buttons(input.buttons() & mask);
+        }
+    }
+
+    /// Removes the idle frames at the end of the movie, where no controller has any input.
+    /// - `input_frames` is reduced by the number of removed samples.
+    /// - Returns the number of removed frames.
+    #[cfg(feature = "std")]
+    pub fn trim_trailing_idle(&mut self) -> usize {
+        let frames = self.frame_count();
+        let keep = self
+            .idle_report()
+            .last_active_frame
+            .map_or(0, |frame| frame + 1);
+        let samples = self.frame_range_to_samples(keep..).len();
+
+        self.inputs.truncate(self.inputs.len() - samples);
+        self.input_frames = self.input_frames.saturating_sub(samples as u32);
+
+        frames - keep
+    }
+}
@@ -0,0 +1,171 @@
+//! Best-effort decoding of string fields that aren't UTF-8, for movies recorded by old or localized versions of Mupen.
+//!
+//! The fields are decoded with the first encoding that fits:
+//! - UTF-8, which is what the format expects, unless there are NULs in between the characters.
+//! - UTF-16, if the text starts with a byte order mark, or if most of the high or low bytes are 0 as they are for Latin text.
+//! - Shift-JIS, if the bytes are valid Shift-JIS and hold kana, or at least 2 kanji.
+//! - Latin-1, which every byte sequence is valid in.
+use encoding_rs::{SHIFT_JIS, UTF_16BE, UTF_16LE};
+
+use crate::{
+    error::{FieldName, M64ParseError},
+    m64::M64,
+};
+
+/// A text encoding that a string field can be decoded from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextEncoding {
+    /// UTF-8.
+    Utf8,
+    /// UTF-16 little endian.
+    Utf16Le,
+    /// UTF-16 big endian.
+    Utf16Be,
+    /// Shift-JIS, used by Japanese versions of Windows.
+    ShiftJis,
+    /// ISO-8859-1, where every byte is the code point of the same value.
+    Latin1,
+}
+
+/// A string decoded with [`decode_text`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DecodedText {
+    /// The decoded text, without the NUL padding.
+    pub text: String,
+    /// The encoding the text was decoded from.
+    pub encoding: TextEncoding,
+}
+
+/// Decodes the bytes of a string field with the encoding that fits them best.
+/// - The trailing NUL padding is removed before decoding.
+/// - See the [module documentation](crate::encoding) for how the encoding is picked.
+pub fn decode_text(bytes: &[u8]) -> DecodedText {
+    let end = bytes.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+    let trimmed = &bytes[..end];
+
+    // UTF-16 text of ASCII characters is also valid UTF-8, but has NULs in between the characters
+    let utf8 = std::str::from_utf8(trimmed).ok();
+    let utf8_text = |text: &str| DecodedText {
+        text: text.to_string(),
+        encoding: TextEncoding::Utf8,
+    };
+    if let Some(text) = utf8.filter(|text| !text.contains('\0')) {
+        return utf8_text(text);
+    }
+    if let Some(decoded) = decode_utf16(bytes, end) {
+        return decoded;
+    }
+    if let Some(text) = utf8 {
+        return utf8_text(text);
+    }
+    if let Some(text) = SHIFT_JIS.decode_without_bom_handling_and_without_replacement(trimmed) {
+        let kana = text
+            .chars()
+            .any(|ch| matches!(ch, '\u{3040}'..='\u{30FF}' | '\u{FF66}'..='\u{FF9F}'));
+        let kanji = text
+            .chars()
+            .filter(|ch| matches!(ch, '\u{4E00}'..='\u{9FFF}'))
+            .count();
+        if kana || kanji >= 2 {
+            return DecodedText {
+                text: text.into_owned(),
+                encoding: TextEncoding::ShiftJis,
+            };
+        }
+    }
+
+    DecodedText {
+        text: trimmed.iter().map(|&b| char::from(b)).collect(),
+        encoding: TextEncoding::Latin1,
+    }
+}
+
+impl M64 {
+    /// Creates an instance of `M64` from an array of bytes, decoding string fields that aren't UTF-8 with [`decode_text`].
+    /// - Returns the movie, along with the fields that were decoded and the encoding each was decoded from.
+    /// - Decoded strings are stored as UTF-8, and cut off at a character boundary if they no longer fit their field.
+    pub fn from_u8_array_detect_encoding(
+        data: &[u8],
+    ) -> Result<(Self, Vec<(FieldName, TextEncoding)>), M64ParseError> {
+        let mut data = data.to_vec();
+        let mut decoded = Vec::new();
+
+        for field in STRING_FIELDS {
+            let Some(bytes) = data.get_mut(field.offset()..field.offset() + field.size()) else {
+                break;
+            };
+            let text = decode_text(bytes);
+            if text.encoding == TextEncoding::Utf8 {
+                continue;
+            }
+            bytes.copy_from_slice(&padded_bytes(&text.text, bytes.len()));
+            decoded.push((field, text.encoding));
+        }
+
+        Ok((Self::from_u8_array(&data)?, decoded))
+    }
+}
+
+/// The string fields, in file order.
+const STRING_FIELDS: [FieldName; 7] = [
+    FieldName::RomInternalName,
+    FieldName::VideoPlugin,
+    FieldName::SoundPlugin,
+    FieldName::InputPlugin,
+    FieldName::RspPlugin,
+    FieldName::Author,
+    FieldName::Description,
+];
+
+/// Returns the text as UTF-8 padded with NUL bytes to `len`, cut off at a character boundary if it's too long.
+fn padded_bytes(text: &str, len: usize) -> Vec<u8> {
+    let mut end = text.len().min(len);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    let mut bytes = text.as_bytes()[..end].to_vec();
+    bytes.resize(len, 0);
+    bytes
+}
+
+/// Decodes the bytes as UTF-16 if they start with a byte order mark, or if they look like UTF-16.
+/// - `end` is where the NUL padding starts, which can cut off the 0 byte of the last character.
+fn decode_utf16(bytes: &[u8], end: usize) -> Option<DecodedText> {
+    let (encoding, text) = match bytes {
+        [0xFF, 0xFE, ..] => (TextEncoding::Utf16Le, &bytes[2..]),
+        [0xFE, 0xFF, ..] => (TextEncoding::Utf16Be, &bytes[2..]),
+        _ => {
+            let text = &bytes[..(end + 1).min(bytes.len()) & !1];
+            let zeros = |parity: usize| {
+                text.iter()
+                    .skip(parity)
+                    .step_by(2)
+                    .filter(|&&b| b == 0)
+                    .count()
+            };
+            let units = text.len() / 2;
+            // text that is mostly ASCII has a 0 in every high byte
+            match (zeros(0), zeros(1)) {
+                (even, odd) if units >= 2 && odd * 2 > units && even == 0 => {
+                    (TextEncoding::Utf16Le, text)
+                }
+                (even, odd) if units >= 2 && even * 2 > units && odd == 0 => {
+                    (TextEncoding::Utf16Be, text)
+                }
+                _ => return None,
+            }
+        }
+    };
+
+    let decoder = match encoding {
+        TextEncoding::Utf16Le => UTF_16LE,
+        _ => UTF_16BE,
+    };
+    let text = &text[..text.len() & !1];
+    let text = decoder.decode_without_bom_handling_and_without_replacement(text)?;
+    Some(DecodedText {
+        text: text.trim_end_matches('\0').to_string(),
+        encoding,
+    })
+}
@@ -1,16 +1,34 @@
 //! Error types for the M64 parser.
 
-use std::io;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::{io, path::PathBuf};
 
 use strum_macros::Display;
 use thiserror::Error;
 
+use crate::detect::MovieFileFormat;
+
+/// How serious an error or finding is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Severity {
+    /// Worth knowing, but doesn't affect the movie.
+    Info,
+    /// Likely a mistake, or needs a justification in a submission.
+    Warning,
+    /// The movie can't be used as it is.
+    Error,
+}
+
 /// All possible M64 parsing errors.
 #[derive(Debug, Error)]
 pub enum M64ParseError {
     /// File signature didn't match.
     #[error("Invalid file signature, expected [4D 36 34 1A], got {0:X?}")]
     InvalidSignature(Vec<u8>),
+    /// The file is a different movie format, see [`detect_format`](crate::detect::detect_format).
+    #[error("File is a {0:?} file, not a .m64 movie")]
+    WrongFormat(MovieFileFormat),
     /// File version number wasn't 3.
     #[error("Invalid version, expected 3, got {0}")]
     InvalidVersion(u32),
@@ -31,13 +49,390 @@ pub enum M64ParseError {
     /// Invalid UTF-8 string.
     #[error("Invalid UTF-8 string for field {0}")]
     InvalidString(FieldName),
+    /// The parser failed in a way no other variant describes.
+    #[error("Failed to parse the movie at offset 0x{offset:X}")]
+    Unknown {
+        /// The parser context that failed, if any.
+        context: Option<&'static str>,
+        /// Offset of the data the parser failed at.
+        offset: usize,
+    },
+    /// Io error.
+    #[cfg(feature = "std")]
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+impl M64ParseError {
+    /// Returns the stable code of the error, which never changes between versions.
+    pub fn code(&self) -> &'static str {
+        match self {
+            M64ParseError::InvalidSignature(_) => "P001",
+            M64ParseError::WrongFormat(_) => "P010",
+            M64ParseError::InvalidVersion(_) => "P002",
+            M64ParseError::ReservedNotZero(_) => "P003",
+            M64ParseError::NotEnoughBytes { .. } => "P004",
+            M64ParseError::InputNot4BytesAligned(_) => "P005",
+            M64ParseError::InvalidMovieStartType => "P006",
+            M64ParseError::InvalidString(_) => "P007",
+            M64ParseError::Unknown { .. } => "P008",
+            #[cfg(feature = "std")]
+            M64ParseError::Io(_) => "P009",
+        }
+    }
+
+    /// Returns how serious the error is.
+    /// - Parsing stops at the error, so this is always [`Severity::Error`].
+    pub fn severity(&self) -> Severity {
+        Severity::Error
+    }
+}
+
+/// All possible errors of loading a movie with [`M64::from_path`](crate::M64::from_path).
+/// - Every variant holds the path of the movie, and tells which step failed.
+#[cfg(feature = "std")]
+#[derive(Debug, Error)]
+pub enum LoadError {
+    /// The file couldn't be opened.
+    #[error("Failed to open {}: {source}", path.display())]
+    Open {
+        /// Path of the movie.
+        path: PathBuf,
+        /// The error opening the file.
+        source: io::Error,
+    },
+    /// The file was opened, but couldn't be read.
+    #[error("Failed to read {}: {source}", path.display())]
+    Read {
+        /// Path of the movie.
+        path: PathBuf,
+        /// The error reading the file.
+        source: io::Error,
+    },
+    /// The file was read, but isn't a valid movie.
+    #[error("Failed to parse {}: {source}", path.display())]
+    Parse {
+        /// Path of the movie.
+        path: PathBuf,
+        /// The error parsing the movie.
+        source: M64ParseError,
+    },
+}
+
+#[cfg(feature = "std")]
+impl LoadError {
+    /// Returns the path of the movie that failed to load.
+    pub fn path(&self) -> &std::path::Path {
+        match self {
+            LoadError::Open { path, .. }
+            | LoadError::Read { path, .. }
+            | LoadError::Parse { path, .. } => path,
+        }
+    }
+}
+
+/// All possible errors of editing a movie in place, see [`InPlaceM64`](crate::in_place::InPlaceM64).
+#[derive(Debug, Error)]
+pub enum InPlaceError {
+    /// The bytes aren't a valid movie.
+    #[error(transparent)]
+    Parse(#[from] M64ParseError),
+    /// The input sample is past the end of the inputs.
+    #[error("Input sample {index} is out of range, the movie has {samples} samples")]
+    InputOutOfRange {
+        /// Index of the input sample.
+        index: usize,
+        /// Number of input samples in the movie.
+        samples: usize,
+    },
+    /// The new value of a header field isn't the size of the field.
+    #[error("The {field} field takes {expected} bytes, got {got}")]
+    FieldSize {
+        /// The header field.
+        field: FieldName,
+        /// Size of the field.
+        expected: usize,
+        /// Size of the new value.
+        got: usize,
+    },
+}
+
+/// All possible errors of adding entries to a [`MetadataBlock`](crate::trailer::MetadataBlock).
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum MetadataBlockError {
+    /// The key is longer than its `u16` length can hold.
+    #[error("Metadata key is {0} bytes long, the most is 65535")]
+    KeyTooLong(usize),
+    /// The entries are larger than the `u32` length of the block can hold.
+    #[error("Metadata entries take {0} bytes, more than the block can hold")]
+    BlockTooLarge(usize),
+}
+
+/// All possible errors of the lag frame analysis.
+#[cfg(feature = "std")]
+#[derive(Debug, Error, PartialEq)]
+pub enum LagAnalysisError {
+    /// [`vis_per_poll`](crate::analysis::lag::LagAnalysisOptions::vis_per_poll) isn't a positive number, which the lag is measured in.
+    #[error("Invalid VIs per poll {0}, expected a positive number")]
+    InvalidVisPerPoll(f64),
+}
+
+/// All possible CSV import errors.
+#[cfg(feature = "std")]
+#[derive(Debug, Error)]
+pub enum CsvError {
+    /// A row couldn't be parsed.
+    #[error("Invalid CSV row at line {0}")]
+    InvalidRow(usize),
+    /// A row is too far past the last sample read so far, see [`MAX_INPUT_GAP`](crate::csv::MAX_INPUT_GAP).
+    #[error("CSV row at line {0} is too far past the end of the inputs")]
+    InputGap(usize),
+    /// Io error.
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// All possible input script parsing errors.
+#[derive(Debug, Error)]
+pub enum ScriptError {
+    /// A line couldn't be parsed.
+    #[error("Invalid script line {0}")]
+    InvalidLine(usize),
+    /// A line reaches too many frames past the frames of the lines before it.
+    #[error("Script line {0} reaches too far past the end of the inputs")]
+    InputGap(usize),
+}
+
+/// All possible JSON import errors.
+#[cfg(feature = "json")]
+#[derive(Debug, Error)]
+pub enum JsonError {
+    /// A field is missing or has the wrong type.
+    #[error("Missing or invalid JSON field {0}")]
+    InvalidField(&'static str),
+    /// A string is too long for its field.
+    #[error("String for field {0} is too long")]
+    FieldTooLong(FieldName),
+    /// The JSON itself is invalid.
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+/// All possible sidecar loading errors.
+#[cfg(feature = "json")]
+#[derive(Debug, Error)]
+pub enum SidecarError {
+    /// A field is missing or has the wrong type.
+    #[error("Missing or invalid sidecar field {0}")]
+    InvalidField(&'static str),
+    /// The sidecar version isn't supported.
+    #[error("Unsupported sidecar version {0}")]
+    UnsupportedVersion(u64),
+    /// The sidecar belongs to a different movie.
+    #[error("Sidecar belongs to the movie with UID {found}, expected {expected}")]
+    UidMismatch {
+        /// UID of the movie.
+        expected: u32,
+        /// UID stored in the sidecar.
+        found: u32,
+    },
+    /// The JSON itself is invalid.
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    /// Io error.
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// All possible ROM header parsing errors.
+#[derive(Debug, Error)]
+pub enum RomError {
+    /// The ROM is shorter than its header.
+    #[error("ROM is too short to hold a header")]
+    TooShort,
+    /// The ROM doesn't start with the magic number.
+    #[error("Invalid ROM magic number")]
+    InvalidMagic,
+}
+
+/// All possible savestate parsing errors.
+#[cfg(feature = "std")]
+#[derive(Debug, Error)]
+pub enum StError {
+    /// The state ended before the header.
+    #[error("Unexpected end of savestate")]
+    UnexpectedEof,
+    /// The ROM MD5 hash isn't hex.
+    #[error("Invalid ROM MD5 hash in savestate")]
+    InvalidRomMd5,
+    /// Io error while decompressing.
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// All possible TASD parsing errors.
+#[derive(Debug, Error)]
+pub enum TasdError {
+    /// File signature didn't match.
+    #[error("Invalid TASD file signature, expected \"TASD\"")]
+    InvalidSignature,
+    /// Only 2 byte packet keys are supported.
+    #[error("Unsupported packet key length {0}, expected 2")]
+    UnsupportedKeyLength(u8),
+    /// The movie isn't for the N64.
+    #[error("Unsupported console type {0:#04X}, expected N64")]
+    UnsupportedConsole(u8),
+    /// A packet length doesn't fit in memory.
+    #[error("Invalid packet length")]
+    InvalidPacketLength,
+    /// A packet refers to a port that doesn't exist.
+    #[error("Invalid controller port")]
+    InvalidPort,
+    /// The data ended in the middle of a packet.
+    #[error("Unexpected end of data")]
+    UnexpectedEof,
+}
+
+/// All possible BK2 conversion errors.
+#[cfg(feature = "bk2")]
+#[derive(Debug, Error)]
+pub enum Bk2Error {
+    /// A required file is missing from the archive.
+    #[error("BK2 archive is missing the file {0}")]
+    MissingFile(&'static str),
+    /// A line of the input log couldn't be parsed.
+    #[error("Invalid input log entry at line {0}")]
+    InvalidInputLog(usize),
+    /// Zip archive error.
+    #[error(transparent)]
+    Zip(#[from] zip::result::ZipError),
+    /// Io error.
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// All possible errors when converting a movie between formats.
+#[cfg(feature = "std")]
+#[derive(Debug, Error)]
+pub enum ConversionError {
+    /// The TASD data couldn't be read.
+    #[error(transparent)]
+    Tasd(#[from] TasdError),
+    /// The BK2 archive couldn't be read or written.
+    #[cfg(feature = "bk2")]
+    #[error(transparent)]
+    Bk2(#[from] Bk2Error),
+    /// Io error.
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// All possible errors when fetching a movie over HTTP.
+#[cfg(feature = "http")]
+#[derive(Debug, Error)]
+pub enum HttpError {
+    /// The request failed or the server responded with an error status.
+    #[error(transparent)]
+    Request(#[from] Box<ureq::Error>),
+    /// The movie is larger than the size limit.
+    #[error("Movie is larger than the size limit of {0} bytes")]
+    TooLarge(u64),
+    /// The downloaded movie couldn't be parsed.
+    #[error(transparent)]
+    Parse(#[from] M64ParseError),
+    /// Io error while downloading.
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// All possible compressed input decoding errors.
+#[derive(Debug, Error)]
+pub enum CompressError {
+    /// Signature didn't match.
+    #[error("Invalid compressed input signature, expected \"M64Z\"")]
+    InvalidSignature,
+    /// The data was written by a newer version.
+    #[error("Unsupported compressed input version {0}")]
+    UnsupportedVersion(u8),
+    /// The data ended in the middle of a field.
+    #[error("Unexpected end of compressed input data")]
+    UnexpectedEof,
+    /// A varint is longer than 64 bits.
+    #[error("Invalid varint in compressed input data")]
+    InvalidVarint,
+    /// A run goes past the number of samples.
+    #[error("Run goes past the end of the compressed inputs")]
+    RunTooLong,
+    /// There were bytes after the last run.
+    #[error("{0} bytes of trailing data after the compressed inputs")]
+    TrailingData(usize),
+}
+
+/// All possible patch errors.
+#[cfg(feature = "std")]
+#[derive(Debug, Error)]
+pub enum PatchError {
+    /// File signature didn't match.
+    #[error("Invalid patch file signature, expected \"M64P\"")]
+    InvalidSignature,
+    /// The patch was written by a newer version.
+    #[error("Unsupported patch version {0}")]
+    UnsupportedVersion(u8),
+    /// The patch ended in the middle of a field.
+    #[error("Unexpected end of patch file")]
+    UnexpectedEof,
+    /// There were bytes after the last input edit.
+    #[error("{0} bytes of trailing data after the patch")]
+    TrailingData(usize),
+    /// An edit is out of range or out of order.
+    #[error("Patch edit is out of range")]
+    InvalidEdit,
+    /// The movie isn't the one the patch was created from.
+    #[error("The movie doesn't match the patch source")]
+    SourceMismatch,
+    /// The patched movie isn't the one the patch was created for.
+    #[error("The patched movie doesn't match the patch target")]
+    TargetMismatch,
+    /// The patched movie couldn't be parsed.
+    #[error(transparent)]
+    Parse(#[from] M64ParseError),
+}
+
+/// All possible project branch errors.
+#[cfg(feature = "std")]
+#[derive(Debug, Error)]
+pub enum ProjectError {
+    /// No branch has the name.
+    #[error("No branch named {0}")]
+    NoSuchBranch(String),
+    /// A branch with the name already exists.
+    #[error("A branch named {0} already exists")]
+    BranchExists(String),
+    /// The current branch can't be deleted.
+    #[error("Can't delete the current branch {0}")]
+    CurrentBranch(String),
+}
+
+/// All possible movie signature errors.
+#[cfg(feature = "signing")]
+#[derive(Debug, Error)]
+pub enum SignatureError {
+    /// The signature file has the wrong size or signature.
+    #[error("Invalid signature file")]
+    InvalidFile,
+    /// The public key isn't a valid ed25519 key.
+    #[error("Invalid public key in signature file")]
+    InvalidKey,
+    /// The signature doesn't match the movie.
+    #[error("Signature doesn't match the movie")]
+    Mismatch,
     /// Io error.
     #[error(transparent)]
     Io(#[from] io::Error),
 }
 
 /// M64 field names used for the error messages.
-#[derive(Debug, Display)]
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum FieldName {
     Version,
     Uid,
@@ -58,3 +453,51 @@ pub enum FieldName {
     Author,
     Description,
 }
+
+impl FieldName {
+    /// Returns the offset of the field in the header.
+    pub fn offset(self) -> usize {
+        match self {
+            FieldName::Version => 0x004,
+            FieldName::Uid => 0x008,
+            FieldName::ViFrames => 0x00C,
+            FieldName::Rerecords => 0x010,
+            FieldName::Fps => 0x014,
+            FieldName::ControllerCount => 0x015,
+            FieldName::InputFrames => 0x018,
+            FieldName::MovieStartType => 0x01C,
+            FieldName::ControllerFlags => 0x020,
+            FieldName::RomInternalName => 0x0C4,
+            FieldName::RomCrc32 => 0x0E4,
+            FieldName::RomCountryCode => 0x0E8,
+            FieldName::VideoPlugin => 0x122,
+            FieldName::SoundPlugin => 0x162,
+            FieldName::InputPlugin => 0x1A2,
+            FieldName::RspPlugin => 0x1E2,
+            FieldName::Author => 0x222,
+            FieldName::Description => 0x300,
+        }
+    }
+
+    /// Returns the size of the field in the header in bytes.
+    pub fn size(self) -> usize {
+        match self {
+            FieldName::Fps | FieldName::ControllerCount => 1,
+            FieldName::MovieStartType | FieldName::RomCountryCode => 2,
+            FieldName::Version
+            | FieldName::Uid
+            | FieldName::ViFrames
+            | FieldName::InputFrames
+            | FieldName::Rerecords
+            | FieldName::ControllerFlags
+            | FieldName::RomCrc32 => 4,
+            FieldName::RomInternalName => 32,
+            FieldName::VideoPlugin
+            | FieldName::SoundPlugin
+            | FieldName::InputPlugin
+            | FieldName::RspPlugin => 64,
+            FieldName::Author => 222,
+            FieldName::Description => 256,
+        }
+    }
+}
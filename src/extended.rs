@@ -0,0 +1,160 @@
+//! The extended header of mupen64-rr-lua, which is stored in bytes that older versions leave reserved.
+//!
+//! A movie has an extended header when the extended version at offset `0x16` isn't 0, and it's then laid out as:
+//! - `0x16`: the extended version.
+//! - `0x17`: the extended flags, see [`ExtendedFlags`].
+//! - `0x24`: 32 bytes of extended data, starting with the authorship tag, the bruteforce data and the upper 32 bits of the rerecord count.
+//!
+//! Movies without an extended header must have those bytes set to 0,
+//! and [`M64::from_u8_array_strict`](crate::M64::from_u8_array_strict) rejects the extended header altogether.
+
+use crate::m64::M64;
+
+/// Offset of the extended version and flags.
+pub const EXTENDED_VERSION_OFFSET: usize = 0x16;
+/// Offset of the extended data.
+pub const EXTENDED_DATA_OFFSET: usize = 0x24;
+/// Size of the extended data in bytes, the rest of the reserved bytes after it must be 0.
+pub const EXTENDED_DATA_SIZE: usize = 32;
+
+/// The extended header of a movie recorded with mupen64-rr-lua.
+/// - See the [module documentation](crate::extended) for where each field is stored.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExtendedHeader {
+    /// Version of the extended header, which is never 0 in a parsed movie.
+    /// - An extended header with a version of 0 isn't written, the same as if the movie had none.
+    pub version: u8,
+    /// The extended flags.
+    pub flags: ExtendedFlags,
+    /// Identifies the program that recorded the movie, such as `MUPN` for mupen64-rr-lua.
+    pub authorship_tag: [u8; 4],
+    /// Data used by bruteforcing scripts.
+    pub bruteforce_data: u32,
+    /// The upper 32 bits of the rerecord count, for counts that overflow the `rerecords` field.
+    /// - Use [`M64::total_rerecords`] for the whole count.
+    pub rerecords_high: u32,
+    /// The rest of the extended data, which has no known meaning yet.
+    pub unknown: [u8; 20],
+}
+
+impl ExtendedHeader {
+    /// Creates the extended header of the latest version, tagged as recorded with mupen64-rr-lua.
+    pub fn new() -> Self {
+        ExtendedHeader {
+            version: 1,
+            flags: ExtendedFlags::default(),
+            authorship_tag: *b"MUPN",
+            bruteforce_data: 0,
+            rerecords_high: 0,
+            unknown: [0; 20],
+        }
+    }
+
+    /// Reads the extended header from the 2 bytes at offset `0x16` and the extended data.
+    /// - Returns `None` if the extended version is 0.
+    pub fn from_bytes(version_and_flags: [u8; 2], data: &[u8; EXTENDED_DATA_SIZE]) -> Option<Self> {
+        let [version, flags] = version_and_flags;
+        if version == 0 {
+            return None;
+        }
+
+        Some(ExtendedHeader {
+            version,
+            flags: ExtendedFlags::from_u8(flags),
+            authorship_tag: data[0..4].try_into().unwrap(),
+            bruteforce_data: u32::from_le_bytes(data[4..8].try_into().unwrap()),
+            rerecords_high: u32::from_le_bytes(data[8..12].try_into().unwrap()),
+            unknown: data[12..].try_into().unwrap(),
+        })
+    }
+
+    /// Returns the extended data as it's written at offset `0x24`.
+    pub fn data_bytes(&self) -> [u8; EXTENDED_DATA_SIZE] {
+        let mut data = [0; EXTENDED_DATA_SIZE];
+        data[0..4].copy_from_slice(&self.authorship_tag);
+        data[4..8].copy_from_slice(&self.bruteforce_data.to_le_bytes());
+        data[8..12].copy_from_slice(&self.rerecords_high.to_le_bytes());
+        data[12..].copy_from_slice(&self.unknown);
+        data
+    }
+
+    /// Returns the authorship tag as a string, if it's ASCII.
+    pub fn authorship(&self) -> Option<&str> {
+        core::str::from_utf8(&self.authorship_tag)
+            .ok()
+            .filter(|tag| tag.is_ascii())
+    }
+}
+
+impl<S> M64<S> {
+    /// Returns if the movie was recorded with the timing of the Wii Virtual Console.
+    /// - Only movies with an extended header can have the flag set.
+    pub fn is_wii_vc(&self) -> bool {
+        self.extended.is_some_and(|extended| extended.flags.wii_vc)
+    }
+
+    /// Sets if the movie was recorded with the timing of the Wii Virtual Console.
+    /// - Setting the flag adds an [extended header](ExtendedHeader::new) if the movie doesn't have one.
+    pub fn set_wii_vc(&mut self, wii_vc: bool) {
+        if wii_vc || self.extended.is_some() {
+            self.extended
+                .get_or_insert_with(ExtendedHeader::new)
+                .flags
+                .wii_vc = wii_vc;
+        }
+    }
+
+    /// Returns the whole rerecord count, with the upper 32 bits from the extended header.
+    pub fn total_rerecords(&self) -> u64 {
+        let high = self.extended.map_or(0, |extended| extended.rerecords_high);
+        ((high as u64) << 32) | self.rerecords as u64
+    }
+
+    /// Sets the whole rerecord count, storing the upper 32 bits in the extended header.
+    /// - An [extended header](ExtendedHeader::new) is added if the count doesn't fit in `rerecords` and the movie doesn't have one.
+    pub fn set_total_rerecords(&mut self, rerecords: u64) {
+        self.rerecords = rerecords as u32;
+        let high = (rerecords >> 32) as u32;
+        if high != 0 || self.extended.is_some() {
+            self.extended
+                .get_or_insert_with(ExtendedHeader::new)
+                .rerecords_high = high;
+        }
+    }
+}
+
+impl Default for ExtendedHeader {
+    /// Same as [`ExtendedHeader::new`].
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The extended flags of a movie recorded with mupen64-rr-lua.
+/// - Bit 0 is set if the movie was recorded with Wii VC timing, the other bits are reserved.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExtendedFlags {
+    /// If the movie was recorded with the timing of the Wii Virtual Console, which it only syncs on.
+    pub wii_vc: bool,
+    /// The reserved bits, kept so they're written back as they were.
+    pub reserved: u8,
+}
+
+impl ExtendedFlags {
+    const WII_VC: u8 = 0x01;
+
+    /// Creates a new instance from the raw extended flags.
+    pub fn from_u8(value: u8) -> Self {
+        ExtendedFlags {
+            wii_vc: value & Self::WII_VC != 0,
+            reserved: value & !Self::WII_VC,
+        }
+    }
+
+    /// Returns the raw extended flags.
+    pub fn to_u8(self) -> u8 {
+        (self.reserved & !Self::WII_VC) | if self.wii_vc { Self::WII_VC } else { 0 }
+    }
+}
@@ -0,0 +1,264 @@
+//! C bindings for parsing, inspecting and writing movies.
+//!
+//! The shared library is built with `cargo rustc --lib --release --features ffi --crate-type cdylib`.
+//! The C header is at `include/mupen64_movie_parser.h`, and can be regenerated with
+//! `cbindgen --config cbindgen.toml --output include/mupen64_movie_parser.h`.
+//!
+//! Movies are passed around as opaque `M64` pointers, which are created by [`m64_parse`] or [`m64_new`]
+//! and must be released with [`m64_free`].
+//! Inputs are exchanged as the raw `u32` values stored in the file.
+use std::{ffi::c_char, ptr, slice};
+
+use crate::{
+    controller::{Flags, Input},
+    m64::{MovieStartType, M64},
+    padded_string::PaddedString,
+};
+
+/// The header fields of a movie.
+/// - Strings are the raw NUL padded bytes of the fields, and are not guaranteed to be NUL terminated when full.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct M64Header {
+    /// Identifies the movie-savestate relationship.
+    pub uid: u32,
+    /// Number of vertical interrupt frames.
+    pub vi_frames: u32,
+    /// Number of input samples for any controllers.
+    pub input_frames: u32,
+    /// Rerecord count.
+    pub rerecords: u32,
+    /// Frames per second.
+    pub fps: u8,
+    /// Number of controllers.
+    pub controller_count: u8,
+    /// Movie start type, 1 for snapshot, 2 for power on, 4 for EEPROM and 8 for an existing snapshot.
+    pub movie_start_type: u16,
+    /// Raw controller flags.
+    pub controller_flags: u32,
+    /// Internal name of the ROM.
+    pub rom_internal_name: [c_char; 32],
+    /// CRC32 of the ROM.
+    pub rom_crc_32: u32,
+    /// Country code of the ROM.
+    pub rom_country_code: u16,
+    /// Name of the video plugin.
+    pub video_plugin: [c_char; 64],
+    /// Name of the sound plugin.
+    pub sound_plugin: [c_char; 64],
+    /// Name of the input plugin.
+    pub input_plugin: [c_char; 64],
+    /// Name of the RSP plugin.
+    pub rsp_plugin: [c_char; 64],
+    /// Author of the movie.
+    pub author: [c_char; 222],
+    /// Description of the movie.
+    pub description: [c_char; 256],
+}
+
+/// Creates an empty movie with the [default](M64::default) header.
+#[no_mangle]
+pub extern "C" fn m64_new() -> *mut M64 {
+    Box::into_raw(Box::default())
+}
+
+/// Parses a movie, returning null if it's invalid.
+///
+/// # Safety
+/// `data` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn m64_parse(data: *const u8, len: usize) -> *mut M64 {
+    if data.is_null() {
+        return ptr::null_mut();
+    }
+
+    match M64::from_u8_array(slice::from_raw_parts(data, len)) {
+        Ok(m64) => Box::into_raw(Box::new(m64)),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Releases a movie.
+///
+/// # Safety
+/// `m64` must be null or a movie that hasn't been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn m64_free(m64: *mut M64) {
+    if !m64.is_null() {
+        drop(Box::from_raw(m64));
+    }
+}
+
+/// Copies the header of the movie into `header`.
+///
+/// # Safety
+/// `m64` must be a valid movie and `header` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn m64_get_header(m64: *const M64, header: *mut M64Header) {
+    let m64 = &*m64;
+    header.write(M64Header {
+        uid: m64.uid,
+        vi_frames: m64.vi_frames,
+        input_frames: m64.input_frames,
+        rerecords: m64.rerecords,
+        fps: m64.fps,
+        controller_count: m64.controller_count,
+        movie_start_type: m64.movie_start_type as u16,
+        controller_flags: Flags::to_u32(&m64.controller_flags),
+        rom_internal_name: c_string(m64.rom_internal_name.as_padded_str()),
+        rom_crc_32: m64.rom_crc_32,
+        rom_country_code: m64.rom_country_code,
+        video_plugin: c_string(m64.video_plugin.as_padded_str()),
+        sound_plugin: c_string(m64.sound_plugin.as_padded_str()),
+        input_plugin: c_string(m64.input_plugin.as_padded_str()),
+        rsp_plugin: c_string(m64.rsp_plugin.as_padded_str()),
+        author: c_string(m64.author.as_padded_str()),
+        description: c_string(m64.description.as_padded_str()),
+    });
+}
+
+/// Replaces the header of the movie, returning `false` and leaving the movie unchanged if a field is invalid.
+/// - Strings must be UTF-8, and are padded with NUL bytes.
+///
+/// # Safety
+/// `m64` must be a valid movie and `header` must be readable.
+#[no_mangle]
+pub unsafe extern "C" fn m64_set_header(m64: *mut M64, header: *const M64Header) -> bool {
+    let m64 = &mut *m64;
+    let header = &*header;
+    let Some(movie_start_type) = MovieStartType::from_repr(header.movie_start_type as usize) else {
+        return false;
+    };
+    let (
+        Some(rom_internal_name),
+        Some(video_plugin),
+        Some(sound_plugin),
+        Some(input_plugin),
+        Some(rsp_plugin),
+        Some(author),
+        Some(description),
+    ) = (
+        padded_string(&header.rom_internal_name),
+        padded_string(&header.video_plugin),
+        padded_string(&header.sound_plugin),
+        padded_string(&header.input_plugin),
+        padded_string(&header.rsp_plugin),
+        padded_string(&header.author),
+        padded_string(&header.description),
+    )
+    else {
+        return false;
+    };
+
+    m64.uid = header.uid;
+    m64.vi_frames = header.vi_frames;
+    m64.input_frames = header.input_frames;
+    m64.rerecords = header.rerecords;
+    m64.fps = header.fps;
+    m64.controller_count = header.controller_count;
+    m64.movie_start_type = movie_start_type;
+    m64.controller_flags = Flags::from_u32(header.controller_flags);
+    m64.rom_internal_name = rom_internal_name;
+    m64.rom_crc_32 = header.rom_crc_32;
+    m64.rom_country_code = header.rom_country_code;
+    m64.video_plugin = video_plugin;
+    m64.sound_plugin = sound_plugin;
+    m64.input_plugin = input_plugin;
+    m64.rsp_plugin = rsp_plugin;
+    m64.author = author;
+    m64.description = description;
+    true
+}
+
+/// Returns the number of input samples.
+///
+/// # Safety
+/// `m64` must be a valid movie.
+#[no_mangle]
+pub unsafe extern "C" fn m64_input_count(m64: *const M64) -> usize {
+    let m64 = &*m64;
+    m64.inputs.len()
+}
+
+/// Writes the raw input sample at `index` to `input`, returning `false` if it's out of range.
+///
+/// # Safety
+/// `m64` must be a valid movie and `input` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn m64_get_input(m64: *const M64, index: usize, input: *mut u32) -> bool {
+    let m64 = &*m64;
+    match m64.inputs.get(index) {
+        Some(&sample) => {
+            input.write(sample.into());
+            true
+        }
+        None => false,
+    }
+}
+
+/// Replaces the input sample at `index`, returning `false` if it's out of range.
+///
+/// # Safety
+/// `m64` must be a valid movie.
+#[no_mangle]
+pub unsafe extern "C" fn m64_set_input(m64: *mut M64, index: usize, input: u32) -> bool {
+    let m64 = &mut *m64;
+    match m64.inputs.get_mut(index) {
+        Some(sample) => {
+            *sample = Input::from(input);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Appends an input sample, increasing `input_frames` by 1.
+///
+/// # Safety
+/// `m64` must be a valid movie.
+#[no_mangle]
+pub unsafe extern "C" fn m64_push_input(m64: *mut M64, input: u32) {
+    let m64 = &mut *m64;
+    m64.extend([Input::from(input)]);
+}
+
+/// Writes the movie to a new buffer, storing its length in `len`.
+/// - The buffer must be released with [`m64_bytes_free`].
+///
+/// # Safety
+/// `m64` must be a valid movie and `len` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn m64_to_bytes(m64: *const M64, len: *mut usize) -> *mut u8 {
+    let m64 = &*m64;
+    let mut bytes = Vec::new();
+    m64.write_m64(&mut bytes)
+        .expect("writing to a Vec can't fail");
+
+    let bytes = bytes.into_boxed_slice();
+    len.write(bytes.len());
+    Box::into_raw(bytes) as *mut u8
+}
+
+/// Releases a buffer returned by [`m64_to_bytes`].
+///
+/// # Safety
+/// `bytes` must be null or a buffer returned by [`m64_to_bytes`] with its length, that hasn't been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn m64_bytes_free(bytes: *mut u8, len: usize) {
+    if !bytes.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(bytes, len)));
+    }
+}
+
+fn c_string<const N: usize>(s: &str) -> [c_char; N] {
+    let mut c_string = [0; N];
+    for (c, &b) in c_string.iter_mut().zip(s.as_bytes()) {
+        *c = b as c_char;
+    }
+    c_string
+}
+
+fn padded_string<const N: usize>(c_string: &[c_char; N]) -> Option<PaddedString<N>> {
+    let bytes = c_string.map(|c| c as u8);
+    PaddedString::try_from(std::str::from_utf8(&bytes).ok()?).ok()
+}
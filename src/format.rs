@@ -0,0 +1,98 @@
+//! A common interface over movie formats, for tools that work with any of them.
+use std::io::{self, Write};
+
+use crate::{frame::Frame, m64::M64, padded_string::PaddedString};
+
+/// A movie in any format.
+/// - Tools written against this trait work with every format, and [`MovieFormat::to_m64`] converts any of them to a `.m64` movie.
+/// - The trait is object safe, so different formats can be handled as `Box<dyn MovieFormat>`.
+pub trait MovieFormat {
+    /// Returns the name of the format, which is also the file extension.
+    fn format_name(&self) -> &'static str;
+
+    /// Returns the author(s) of the movie.
+    fn author(&self) -> &str;
+
+    /// Returns the description of the movie.
+    fn description(&self) -> &str;
+
+    /// Returns the name of the ROM the movie was recorded on.
+    fn rom_name(&self) -> &str;
+
+    /// Returns the rerecord count.
+    fn rerecords(&self) -> u64;
+
+    /// Returns the number of controllers that have an input in each frame.
+    fn controller_count(&self) -> usize;
+
+    /// Returns the number of frames.
+    fn frame_count(&self) -> usize;
+
+    /// Returns an iterator over the frames, each holding an input for every controller.
+    fn frames(&self) -> Box<dyn Iterator<Item = Frame> + '_>;
+
+    /// Writes the movie in its own format.
+    fn write_movie(&self, writer: &mut dyn Write) -> io::Result<()>;
+
+    /// Converts the movie to a `.m64` movie.
+    /// - Fields the format doesn't have are left at their [default](M64::default) values,
+    ///   and strings too long for their `.m64` field are cut off.
+    fn to_m64(&self) -> M64 {
+        let controllers = self.controller_count().clamp(1, 4);
+        let mut m64 = M64 {
+            controller_count: controllers as u8,
+            rom_internal_name: PaddedString::truncated(self.rom_name()).0,
+            author: PaddedString::truncated(self.author()).0,
+            description: PaddedString::truncated(self.description()).0,
+            ..Default::default()
+        };
+        m64.set_total_rerecords(self.rerecords());
+        for (port, flags) in m64.controller_flags.iter_mut().enumerate() {
+            flags.controller_present = port < controllers;
+        }
+        m64.extend(self.frames().flat_map(|frame| frame.to_vec()));
+        m64
+    }
+}
+
+impl MovieFormat for M64 {
+    fn format_name(&self) -> &'static str {
+        "m64"
+    }
+
+    fn author(&self) -> &str {
+        self.author.as_str()
+    }
+
+    fn description(&self) -> &str {
+        self.description.as_str()
+    }
+
+    fn rom_name(&self) -> &str {
+        self.rom_internal_name.as_str()
+    }
+
+    fn rerecords(&self) -> u64 {
+        self.total_rerecords()
+    }
+
+    fn controller_count(&self) -> usize {
+        self.samples_per_frame()
+    }
+
+    fn frame_count(&self) -> usize {
+        M64::frame_count(self)
+    }
+
+    fn frames(&self) -> Box<dyn Iterator<Item = Frame> + '_> {
+        Box::new(M64::frames(self))
+    }
+
+    fn write_movie(&self, mut writer: &mut dyn Write) -> io::Result<()> {
+        self.write_m64(&mut writer)
+    }
+
+    fn to_m64(&self) -> M64 {
+        self.clone()
+    }
+}
@@ -0,0 +1,121 @@
+//! Contains the frame type, which groups the inputs of all controllers for a single frame.
+use core::ops::{Deref, DerefMut, Range};
+
+use arrayvec::ArrayVec;
+
+use crate::{controller::Input, m64::M64};
+
+/// A single logical frame of a movie.
+/// - Holds one input sample for each controller, in controller order.
+/// - A movie with `n` controllers stores `n` input samples for each frame.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Frame {
+    inputs: ArrayVec<Input, 4>,
+}
+
+impl Frame {
+    /// Creates an empty frame with no controller inputs.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a frame from the inputs of each controller.
+    /// - Returns `None` if there are more than 4 inputs.
+    pub fn from_inputs(inputs: &[Input]) -> Option<Self> {
+        let inputs = ArrayVec::try_from(inputs).ok()?;
+        Some(Frame { inputs })
+    }
+
+    /// Returns the input of the given controller.
+    pub fn controller(&self, controller: usize) -> Option<Input> {
+        self.inputs.get(controller).copied()
+    }
+
+    /// Adds the input of the next controller.
+    /// - Returns the input back if the frame already holds 4 inputs.
+    pub fn push(&mut self, input: Input) -> Result<(), Input> {
+        self.inputs.try_push(input).map_err(|err| err.element())
+    }
+}
+
+impl Deref for Frame {
+    type Target = [Input];
+
+    fn deref(&self) -> &Self::Target {
+        &self.inputs
+    }
+}
+
+impl DerefMut for Frame {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.inputs
+    }
+}
+
+/// A type that can index into the inputs of a [`M64`] by frame.
+/// - `usize` indexes a whole frame, returning the inputs of every controller for that frame.
+/// - `(usize, usize)` indexes a single controller's input as `(frame, controller)`.
+pub trait FrameIndex: private::Sealed {
+    /// The type returned by the index.
+    type Output: ?Sized;
+
+    /// Returns a reference to the indexed inputs, or `None` if out of range.
+    fn get(self, m64: &M64) -> Option<&Self::Output>;
+
+    /// Returns a mutable reference to the indexed inputs, or `None` if out of range.
+    fn get_mut(self, m64: &mut M64) -> Option<&mut Self::Output>;
+}
+
+fn frame_range(m64: &M64, frame: usize) -> Option<Range<usize>> {
+    let stride = m64.samples_per_frame();
+    let start = frame.checked_mul(stride)?;
+
+    if start >= m64.inputs.len() {
+        return None;
+    }
+
+    Some(start..(start + stride).min(m64.inputs.len()))
+}
+
+fn sample_index(m64: &M64, (frame, controller): (usize, usize)) -> Option<usize> {
+    let stride = m64.samples_per_frame();
+    if controller >= stride {
+        return None;
+    }
+
+    frame.checked_mul(stride)?.checked_add(controller)
+}
+
+impl FrameIndex for usize {
+    type Output = [Input];
+
+    fn get(self, m64: &M64) -> Option<&Self::Output> {
+        let range = frame_range(m64, self)?;
+        Some(&m64.inputs[range])
+    }
+
+    fn get_mut(self, m64: &mut M64) -> Option<&mut Self::Output> {
+        let range = frame_range(m64, self)?;
+        Some(&mut m64.inputs[range])
+    }
+}
+
+impl FrameIndex for (usize, usize) {
+    type Output = Input;
+
+    fn get(self, m64: &M64) -> Option<&Self::Output> {
+        m64.inputs.get(sample_index(m64, self)?)
+    }
+
+    fn get_mut(self, m64: &mut M64) -> Option<&mut Self::Output> {
+        let index = sample_index(m64, self)?;
+        m64.inputs.get_mut(index)
+    }
+}
+
+mod private {
+    pub trait Sealed {}
+
+    impl Sealed for usize {}
+    impl Sealed for (usize, usize) {}
+}
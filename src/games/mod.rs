@@ -0,0 +1,3 @@
+//! Helpers for specific games, each behind a feature of the same name.
+#[cfg(feature = "sm64")]
+pub mod sm64;
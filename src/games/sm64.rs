@@ -0,0 +1,75 @@
+//! Helpers for Super Mario 64.
+//!
+//! The stick math follows the game's controller and Mario input handling:
+//! - Each axis has a deadzone of 8, and is moved 6 units towards the center when outside of it.
+//! - The resulting position is clamped to a magnitude of 64.
+//! - Mario's intended magnitude is the square of the clamped magnitude as a fraction of 64, scaled to 32.
+use crate::m64::M64;
+
+/// The stick position as seen by the game, after the deadzone and clamping.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct EffectiveStick {
+    /// Stick X-axis, from -64 to 64.
+    pub x: f32,
+    /// Stick Y-axis, from -64 to 64.
+    pub y: f32,
+    /// Distance from the center, from 0 to 64.
+    pub magnitude: f32,
+}
+
+/// Returns the stick position as seen by the game for the raw stick axes.
+pub fn effective_stick(x: i8, y: i8) -> EffectiveStick {
+    let axis = |value: i8| match value {
+        ..=-8 => value as f32 + 6.0,
+        8.. => value as f32 - 6.0,
+        _ => 0.0,
+    };
+    let (mut x, mut y) = (axis(x), axis(y));
+    let mut magnitude = x.hypot(y);
+    if magnitude > 64.0 {
+        x *= 64.0 / magnitude;
+        y *= 64.0 / magnitude;
+        magnitude = 64.0;
+    }
+
+    EffectiveStick { x, y, magnitude }
+}
+
+/// Returns Mario's intended magnitude for the raw stick axes, from 0 to 32.
+/// - This is the speed Mario tries to reach when walking or running.
+pub fn intended_magnitude(x: i8, y: i8) -> f32 {
+    let fraction = effective_stick(x, y).magnitude / 64.0;
+    fraction * fraction * 32.0
+}
+
+/// Returns the number of discrete A presses of a controller, counting a hold as a single press.
+pub fn a_presses(m64: &M64, controller: usize) -> usize {
+    let mut held = false;
+    m64.inputs_for_controller(controller)
+        .filter(|input| {
+            let pressed = input.a_button && !held;
+            held = input.a_button;
+            pressed
+        })
+        .count()
+}
+
+/// Returns the frames of a controller that look like long jump inputs.
+/// - A long jump is pressing A during the slide that follows pressing Z while moving,
+///   so it's detected as A being pressed while Z is held, at most `window` frames after Z was pressed.
+/// - Pressing A after holding Z for longer usually means Mario is crouching, which backflips instead.
+/// - This only looks at the inputs, so it can't know if Mario was moving fast enough to slide.
+pub fn long_jumps(m64: &M64, controller: usize, window: usize) -> Vec<usize> {
+    let mut frames = Vec::new();
+    let (mut a_held, mut z_held_for) = (false, 0);
+
+    for (frame, input) in m64.inputs_for_controller(controller).enumerate() {
+        z_held_for = if input.z_button { z_held_for + 1 } else { 0 };
+        if input.a_button && !a_held && (1..=window + 1).contains(&z_held_for) {
+            frames.push(frame);
+        }
+        a_held = input.a_button;
+    }
+
+    frames
+}
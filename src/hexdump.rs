@@ -0,0 +1,100 @@
+//! An annotated hexdump of the header, for inspecting files written by other tools.
+//!
+//! Each header field is dumped on its own, with its offset and raw bytes on the left, 16 bytes per line,
+//! and its name and decoded value next to the first line of bytes.
+use std::fmt::Write;
+
+use crate::{controller::Flags, m64::M64, parser::HEADER_SIZE};
+
+/// Number of bytes shown on each line.
+const BYTES_PER_LINE: usize = 16;
+
+impl M64 {
+    /// Returns a hexdump of the 1024 byte header, with the offset, name and decoded value of each field.
+    pub fn hexdump_header(&self) -> String {
+        let bytes = &self.raw_header();
+        let present = self
+            .controller_flags
+            .iter()
+            .enumerate()
+            .filter(|(_, flags)| flags.controller_present)
+            .map(|(port, _)| (port + 1).to_string())
+            .collect::<Vec<_>>();
+
+        let fields: [(usize, &str, String); 23] = [
+            (0x000, "signature", format!("{:?}", "M64\x1A")),
+            (0x004, "version", "3".to_string()),
+            (0x008, "uid", self.uid.to_string()),
+            (0x00C, "vi_frames", self.vi_frames.to_string()),
+            (0x010, "rerecords", self.rerecords.to_string()),
+            (0x014, "fps", self.fps.to_string()),
+            (0x015, "controller_count", self.controller_count.to_string()),
+            (0x016, "reserved", reserved(&bytes[0x016..0x018])),
+            (0x018, "input_frames", self.input_frames.to_string()),
+            (
+                0x01C,
+                "movie_start_type",
+                format!("{:?}", self.movie_start_type),
+            ),
+            (0x01E, "reserved", reserved(&bytes[0x01E..0x020])),
+            (
+                0x020,
+                "controller_flags",
+                format!(
+                    "{:#05X}, present: [{}]",
+                    Flags::to_u32(&self.controller_flags),
+                    present.join(", ")
+                ),
+            ),
+            (0x024, "reserved", reserved(&bytes[0x024..0x0C4])),
+            (0x0C4, "rom_internal_name", string(&self.rom_internal_name)),
+            (0x0E4, "rom_crc_32", format!("{:08X}", self.rom_crc_32)),
+            (
+                0x0E8,
+                "rom_country_code",
+                format!("{:#06X} ({:?})", self.rom_country_code, self.country_code()),
+            ),
+            (0x0EA, "reserved", reserved(&bytes[0x0EA..0x122])),
+            (0x122, "video_plugin", string(&self.video_plugin)),
+            (0x162, "sound_plugin", string(&self.sound_plugin)),
+            (0x1A2, "input_plugin", string(&self.input_plugin)),
+            (0x1E2, "rsp_plugin", string(&self.rsp_plugin)),
+            (0x222, "author", string(&self.author)),
+            (0x300, "description", string(&self.description)),
+        ];
+
+        let mut dump = String::new();
+        for (i, (offset, name, value)) in fields.iter().enumerate() {
+            let end = fields.get(i + 1).map_or(HEADER_SIZE, |field| field.0);
+            for (line, chunk) in bytes[*offset..end].chunks(BYTES_PER_LINE).enumerate() {
+                let hex = chunk
+                    .iter()
+                    .map(|byte| format!("{byte:02X}"))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                let offset = offset + line * BYTES_PER_LINE;
+                let _ = if line == 0 {
+                    let width = BYTES_PER_LINE * 3 - 1;
+                    writeln!(dump, "0x{offset:03X}  {hex:<width$}  {name}: {value}")
+                } else {
+                    writeln!(dump, "0x{offset:03X}  {hex}")
+                };
+            }
+        }
+        dump
+    }
+}
+
+/// Describes a reserved field.
+fn reserved(bytes: &[u8]) -> String {
+    if bytes.iter().all(|&byte| byte == 0) {
+        "all zero".to_string()
+    } else {
+        "not zero".to_string()
+    }
+}
+
+/// Quotes a string field without its NUL padding.
+fn string(s: &str) -> String {
+    format!("{:?}", s.trim_end_matches('\0'))
+}
@@ -0,0 +1,41 @@
+//! Fetching movies over HTTP.
+//!
+//! Downloads are limited in size, checked against the `Content-Length` header first
+//! and then against the bytes actually received, so a wrong or missing header can't exhaust memory.
+use std::io::Read;
+
+use crate::{error::HttpError, m64::M64};
+
+/// The default size limit for downloaded movies, 64 MiB.
+/// - This is over 16 million input samples, far longer than any real movie.
+pub const DEFAULT_SIZE_LIMIT: u64 = 64 * 1024 * 1024;
+
+impl M64 {
+    /// Downloads and parses the movie at `url`, limited to [`DEFAULT_SIZE_LIMIT`] bytes.
+    pub fn from_url(url: &str) -> Result<Self, HttpError> {
+        Self::from_url_with_limit(url, DEFAULT_SIZE_LIMIT)
+    }
+
+    /// Downloads and parses the movie at `url`, failing with [`HttpError::TooLarge`] if it's larger than `limit` bytes.
+    pub fn from_url_with_limit(url: &str, limit: u64) -> Result<Self, HttpError> {
+        let response = ureq::get(url).call().map_err(Box::new)?;
+
+        let content_length = response
+            .header("Content-Length")
+            .and_then(|len| len.parse::<u64>().ok());
+        if content_length.is_some_and(|len| len > limit) {
+            return Err(HttpError::TooLarge(limit));
+        }
+
+        let mut data = Vec::new();
+        response
+            .into_reader()
+            .take(limit + 1)
+            .read_to_end(&mut data)?;
+        if data.len() as u64 > limit {
+            return Err(HttpError::TooLarge(limit));
+        }
+
+        Ok(Self::from_u8_array(&data)?)
+    }
+}
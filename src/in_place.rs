@@ -0,0 +1,144 @@
+//! Editing a movie in place in the bytes of its file, such as a writable memory map.
+//!
+//! Only the changed bytes are written, so single inputs or header fields of huge movies can be changed without rewriting the file.
+//! The length of the file never changes, so inputs can't be added or removed, and the [metadata block](crate::trailer) is left as it is.
+//!
+//! ```
+//! use mupen64_movie_parser::{controller::Input, error::FieldName, in_place::InPlaceM64};
+//!
+//! // the bytes can come from anything that gives a `&mut [u8]`, such as `memmap2::MmapMut`
+//! let mut file = include_bytes!("./tests/m64s/120 star tas (2012).m64").to_vec();
+//! let mut movie = InPlaceM64::new(&mut file).unwrap();
+//! movie.set_input(0, Input::from(0x8000)).unwrap();
+//! movie.set_field(FieldName::Rerecords, &0u32.to_le_bytes()).unwrap();
+//! assert_eq!(movie.header().rerecords, 0);
+//! ```
+use alloc::vec::Vec;
+
+use nom::Finish;
+
+use crate::{
+    controller::{Input, RawInput},
+    error::{FieldName, InPlaceError, M64ParseError},
+    m64::parse_error,
+    metadata::M64Metadata,
+    parser::{self, HEADER_SIZE},
+    trailer::MetadataBlock,
+};
+
+/// A movie edited in place in the bytes of its file.
+/// - The header and input section are checked when it's created, the same as [`M64::from_u8_array`](crate::M64::from_u8_array) would.
+#[derive(Debug)]
+pub struct InPlaceM64<'a> {
+    data: &'a mut [u8],
+    /// Number of input samples, which take up the bytes after the header.
+    samples: usize,
+}
+
+impl<'a> InPlaceM64<'a> {
+    /// Checks the bytes of a movie for editing in place.
+    /// - Fails if the header isn't valid, or if the input section isn't made of whole 4 byte samples.
+    pub fn new(data: &'a mut [u8]) -> Result<Self, InPlaceError> {
+        let (_, header) = parser::header(data)
+            .finish()
+            .map_err(|err| parse_error(data, err))?;
+
+        let inputs = &data[HEADER_SIZE..];
+        let inputs = MetadataBlock::split_from_inputs(inputs, header.input_frames)
+            .map_or(inputs, |(inputs, _)| inputs);
+        if !inputs.len().is_multiple_of(4) {
+            return Err(M64ParseError::InputNot4BytesAligned(inputs.len() % 4).into());
+        }
+
+        Ok(InPlaceM64 {
+            samples: inputs.len() / 4,
+            data,
+        })
+    }
+
+    /// Returns the number of input samples, across all controllers.
+    pub fn sample_count(&self) -> usize {
+        self.samples
+    }
+
+    /// Returns the input sample at `index`, or `None` if it's out of range.
+    pub fn input(&self, index: usize) -> Option<Input> {
+        self.raw_input(index).map(Input::from)
+    }
+
+    /// Returns the raw input sample at `index`, or `None` if it's out of range.
+    pub fn raw_input(&self, index: usize) -> Option<RawInput> {
+        let offset = self.sample_offset(index).ok()?;
+        let bytes = self.data[offset..offset + 4].try_into().unwrap();
+        Some(RawInput(u32::from_le_bytes(bytes)))
+    }
+
+    /// Replaces the input sample at `index`, returning the previous one.
+    pub fn set_input(&mut self, index: usize, input: Input) -> Result<Input, InPlaceError> {
+        self.set_raw_input(index, input.into()).map(Input::from)
+    }
+
+    /// Replaces the raw input sample at `index`, returning the previous one.
+    pub fn set_raw_input(&mut self, index: usize, raw: RawInput) -> Result<RawInput, InPlaceError> {
+        let offset = self.sample_offset(index)?;
+        let bytes = &mut self.data[offset..offset + 4];
+        let previous = RawInput(u32::from_le_bytes((&*bytes).try_into().unwrap()));
+        bytes.copy_from_slice(&raw.0.to_le_bytes());
+        Ok(previous)
+    }
+
+    /// Returns the header, parsed from the bytes as they are now.
+    /// - The metadata block isn't read, and is always empty.
+    pub fn header(&self) -> M64Metadata {
+        // the header is checked on creation, and only replaced with valid headers
+        let (_, header) = parser::header(self.data).unwrap();
+        header
+    }
+
+    /// Replaces the whole header.
+    /// - The metadata block of `header` is ignored, as it's stored after the inputs.
+    /// - The new header is checked the same as when parsing, and left unchanged if it isn't valid.
+    pub fn set_header(&mut self, header: &M64Metadata) -> Result<(), InPlaceError> {
+        let bytes = header.with_inputs(Vec::<RawInput>::new()).raw_header();
+        parser::header(&bytes)
+            .finish()
+            .map_err(|err| parse_error(&bytes, err))?;
+
+        self.data[..HEADER_SIZE].copy_from_slice(&bytes);
+        Ok(())
+    }
+
+    /// Replaces the raw bytes of a single header field, which have to be the size of the field.
+    /// - Numbers are little endian, and strings are UTF-8 padded with NUL bytes.
+    /// - The field is checked the same as when parsing, and left unchanged if the new value isn't valid.
+    pub fn set_field(&mut self, field: FieldName, bytes: &[u8]) -> Result<(), InPlaceError> {
+        if bytes.len() != field.size() {
+            return Err(InPlaceError::FieldSize {
+                field,
+                expected: field.size(),
+                got: bytes.len(),
+            });
+        }
+
+        let range = field.offset()..field.offset() + field.size();
+        let mut header: [u8; HEADER_SIZE] = self.data[..HEADER_SIZE].try_into().unwrap();
+        header[range.clone()].copy_from_slice(bytes);
+        parser::header(&header)
+            .finish()
+            .map_err(|err| parse_error(&header, err))?;
+
+        self.data[range].copy_from_slice(bytes);
+        Ok(())
+    }
+
+    /// Returns the offset of the input sample at `index`.
+    fn sample_offset(&self, index: usize) -> Result<usize, InPlaceError> {
+        if index >= self.samples {
+            return Err(InPlaceError::InputOutOfRange {
+                index,
+                samples: self.samples,
+            });
+        }
+        Ok(HEADER_SIZE + index * 4)
+    }
+}
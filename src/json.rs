@@ -0,0 +1,284 @@
+//! Exporting and importing whole movies as JSON.
+//!
+//! # Schema
+//! The movie is a single object with the following keys:
+//! - `uid`, `vi_frames`, `input_frames`, `fps`, `controller_count`, `rom_crc_32`, `rom_country_code`: numbers.
+//! - `rerecords`: the whole rerecord count, see [`M64::total_rerecords`].
+//! - `movie_start_type`: one of `"snapshot"`, `"power_on"`, `"eeprom"` or `"existing_snapshot"`.
+//! - `controller_flags`: array of 4 objects with the booleans `present`, `mempak` and `rumblepak`.
+//! - `rom_internal_name`, `video_plugin`, `sound_plugin`, `input_plugin`, `rsp_plugin`, `author`, `description`:
+//!   strings, without the trailing NUL padding.
+//! - `extended`: the [extended header](crate::extended), or `null` if the movie has none. It's an object with:
+//!   - `version`: the extended version, which can't be 0.
+//!   - `flags`: the extended flags as a number, where bit 0 is set for Wii VC timing.
+//!   - `authorship_tag`: array of the 4 bytes of the authorship tag.
+//!   - `bruteforce_data`: number.
+//!   - `unknown`: array of the 20 bytes of extended data with no known meaning.
+//!
+//!   The key can be left out when importing, which is the same as `null`.
+//! - `metadata`: the entries of the [metadata block](crate::trailer) in order, each an array of the key and the value.
+//!   The value is a string if it's UTF-8, and an array of its bytes otherwise.
+//!   The key can be left out when importing, which is the same as an empty array.
+//! - `inputs`: array of input samples in file order, each an object with:
+//!   - `buttons`: names of the pressed buttons joined by `+`, the same as the `buttons` column of the [CSV](crate::csv) export.
+//!   - `x`, `y`: analog stick axes.
+use serde_json::{json, Map, Value};
+
+use crate::{
+    controller::{Buttons, Flags, Input},
+    error::{FieldName, JsonError},
+    extended::{ExtendedFlags, ExtendedHeader},
+    m64::{MovieStartType, M64},
+    padded_string::PaddedString,
+    trailer::MetadataBlock,
+};
+
+impl M64 {
+    /// Returns the movie as JSON.
+    /// - See the [module documentation](crate::json) for the schema.
+    pub fn to_json(&self) -> String {
+        let flags = self
+            .controller_flags
+            .iter()
+            .map(|flags| {
+                json!({
+                    "present": flags.controller_present,
+                    "mempak": flags.has_mempak,
+                    "rumblepak": flags.has_rumblepak,
+                })
+            })
+            .collect::<Vec<_>>();
+        let inputs = self
+            .inputs
+            .iter()
+            .map(|input| {
+                let buttons = input
+                    .buttons()
+                    .iter_names()
+                    .map(|(name, _)| name)
+                    .collect::<Vec<_>>()
+                    .join("+");
+                json!({ "buttons": buttons, "x": input.x_axis, "y": input.y_axis })
+            })
+            .collect::<Vec<_>>();
+        let extended = self.extended.map(|extended| {
+            json!({
+                "version": extended.version,
+                "flags": extended.flags.to_u8(),
+                "authorship_tag": extended.authorship_tag,
+                "bruteforce_data": extended.bruteforce_data,
+                "unknown": extended.unknown,
+            })
+        });
+        let metadata = self
+            .metadata_block
+            .entries()
+            .iter()
+            .map(|(key, value)| {
+                let value = match std::str::from_utf8(value) {
+                    Ok(value) => Value::from(value),
+                    Err(_) => Value::from(value.as_slice()),
+                };
+                json!([key, value])
+            })
+            .collect::<Vec<_>>();
+        let movie_start_type = match self.movie_start_type {
+            MovieStartType::SnapShot => "snapshot",
+            MovieStartType::PowerOn => "power_on",
+            MovieStartType::Eeprom => "eeprom",
+            MovieStartType::ExistingSnapshot => "existing_snapshot",
+        };
+
+        json!({
+            "uid": self.uid,
+            "vi_frames": self.vi_frames,
+            "input_frames": self.input_frames,
+            "rerecords": self.total_rerecords(),
+            "fps": self.fps,
+            "controller_count": self.controller_count,
+            "movie_start_type": movie_start_type,
+            "controller_flags": flags,
+            "rom_internal_name": self.rom_internal_name.as_str(),
+            "rom_crc_32": self.rom_crc_32,
+            "rom_country_code": self.rom_country_code,
+            "video_plugin": self.video_plugin.as_str(),
+            "sound_plugin": self.sound_plugin.as_str(),
+            "input_plugin": self.input_plugin.as_str(),
+            "rsp_plugin": self.rsp_plugin.as_str(),
+            "author": self.author.as_str(),
+            "description": self.description.as_str(),
+            "extended": extended,
+            "metadata": metadata,
+            "inputs": inputs,
+        })
+        .to_string()
+    }
+
+    /// Creates an instance of `M64` from JSON.
+    /// - See the [module documentation](crate::json) for the schema.
+    pub fn from_json(json: &str) -> Result<Self, JsonError> {
+        let value = serde_json::from_str::<Value>(json)?;
+        let object = value.as_object().ok_or(JsonError::InvalidField("movie"))?;
+
+        let movie_start_type = match string(object, "movie_start_type")? {
+            "snapshot" => MovieStartType::SnapShot,
+            "power_on" => MovieStartType::PowerOn,
+            "eeprom" => MovieStartType::Eeprom,
+            "existing_snapshot" => MovieStartType::ExistingSnapshot,
+            _ => return Err(JsonError::InvalidField("movie_start_type")),
+        };
+
+        let flags = object
+            .get("controller_flags")
+            .and_then(Value::as_array)
+            .filter(|flags| flags.len() == 4)
+            .ok_or(JsonError::InvalidField("controller_flags"))?;
+        let mut controller_flags = Flags::from_u32(0);
+        for (flags, value) in controller_flags.iter_mut().zip(flags) {
+            let flag = |key| {
+                value
+                    .get(key)
+                    .and_then(Value::as_bool)
+                    .ok_or(JsonError::InvalidField("controller_flags"))
+            };
+            flags.controller_present = flag("present")?;
+            flags.has_mempak = flag("mempak")?;
+            flags.has_rumblepak = flag("rumblepak")?;
+        }
+
+        let extended = match object.get("extended") {
+            None | Some(Value::Null) => None,
+            Some(value) => Some(parse_extended(value).ok_or(JsonError::InvalidField("extended"))?),
+        };
+
+        let metadata_block = match object.get("metadata") {
+            None => MetadataBlock::default(),
+            Some(value) => parse_metadata(value)
+                .and_then(|entries| MetadataBlock::try_from(entries).ok())
+                .ok_or(JsonError::InvalidField("metadata"))?,
+        };
+
+        let inputs = object
+            .get("inputs")
+            .and_then(Value::as_array)
+            .ok_or(JsonError::InvalidField("inputs"))?
+            .iter()
+            .map(|value| parse_input(value).ok_or(JsonError::InvalidField("inputs")))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut m64 = M64 {
+            uid: number(object, "uid")?,
+            vi_frames: number(object, "vi_frames")?,
+            input_frames: number(object, "input_frames")?,
+            rerecords: 0,
+            fps: number(object, "fps")?,
+            controller_count: number(object, "controller_count")?,
+            movie_start_type,
+            controller_flags,
+            rom_internal_name: padded(object, "rom_internal_name", FieldName::RomInternalName)?,
+            rom_crc_32: number(object, "rom_crc_32")?,
+            rom_country_code: number(object, "rom_country_code")?,
+            video_plugin: padded(object, "video_plugin", FieldName::VideoPlugin)?,
+            sound_plugin: padded(object, "sound_plugin", FieldName::SoundPlugin)?,
+            input_plugin: padded(object, "input_plugin", FieldName::InputPlugin)?,
+            rsp_plugin: padded(object, "rsp_plugin", FieldName::RspPlugin)?,
+            author: padded(object, "author", FieldName::Author)?,
+            description: padded(object, "description", FieldName::Description)?,
+            extended,
+            metadata_block,
+            inputs,
+        };
+        m64.set_total_rerecords(number(object, "rerecords")?);
+        Ok(m64)
+    }
+}
+
+fn number<T>(object: &Map<String, Value>, key: &'static str) -> Result<T, JsonError>
+where
+    T: TryFrom<u64>,
+{
+    object
+        .get(key)
+        .and_then(Value::as_u64)
+        .and_then(|value| T::try_from(value).ok())
+        .ok_or(JsonError::InvalidField(key))
+}
+
+fn string<'a>(object: &'a Map<String, Value>, key: &'static str) -> Result<&'a str, JsonError> {
+    object
+        .get(key)
+        .and_then(Value::as_str)
+        .ok_or(JsonError::InvalidField(key))
+}
+
+fn padded<const N: usize>(
+    object: &Map<String, Value>,
+    key: &'static str,
+    field: FieldName,
+) -> Result<PaddedString<N>, JsonError> {
+    match PaddedString::truncated(string(object, key)?) {
+        (_, true) => Err(JsonError::FieldTooLong(field)),
+        (s, false) => Ok(s),
+    }
+}
+
+fn parse_input(value: &Value) -> Option<Input> {
+    let mut buttons = Buttons::empty();
+    for name in value.get("buttons")?.as_str()?.split('+') {
+        if !name.is_empty() {
+            buttons |= Buttons::from_name(name)?;
+        }
+    }
+
+    let axis = |key| i8::try_from(value.get(key)?.as_i64()?).ok();
+    let mut input = Input {
+        x_axis: axis("x")?,
+        y_axis: axis("y")?,
+        ..Default::default()
+    };
+    input.set_buttons(buttons);
+    Some(input)
+}
+
+fn parse_extended(value: &Value) -> Option<ExtendedHeader> {
+    let bytes = |key| -> Option<_> {
+        value
+            .get(key)?
+            .as_array()?
+            .iter()
+            .map(|byte| u8::try_from(byte.as_u64()?).ok())
+            .collect::<Option<Vec<_>>>()
+    };
+
+    let version = u8::try_from(value.get("version")?.as_u64()?).ok()?;
+    let flags = u8::try_from(value.get("flags")?.as_u64()?).ok()?;
+    Some(ExtendedHeader {
+        version: (version != 0).then_some(version)?,
+        flags: ExtendedFlags::from_u8(flags),
+        authorship_tag: bytes("authorship_tag")?.try_into().ok()?,
+        bruteforce_data: u32::try_from(value.get("bruteforce_data")?.as_u64()?).ok()?,
+        rerecords_high: 0,
+        unknown: bytes("unknown")?.try_into().ok()?,
+    })
+}
+
+fn parse_metadata(value: &Value) -> Option<Vec<(String, Vec<u8>)>> {
+    value
+        .as_array()?
+        .iter()
+        .map(|entry| {
+            let [key, value] = entry.as_array()?.as_slice() else {
+                return None;
+            };
+            let value = match value {
+                Value::String(value) => value.as_bytes().to_vec(),
+                value => value
+                    .as_array()?
+                    .iter()
+                    .map(|byte| u8::try_from(byte.as_u64()?).ok())
+                    .collect::<Option<_>>()?,
+            };
+            Some((key.as_str()?.to_string(), value))
+        })
+        .collect()
+}
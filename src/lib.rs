@@ -7,19 +7,128 @@
 //!
 //! let m64 = include_bytes!("./tests/m64s/120 star tas (2012).m64");
 //! let m64 = M64::from_u8_array(m64).unwrap();
-//! assert_eq!(m64.author.as_str().trim_matches(char::from(0)),
+//! assert_eq!(m64.author.as_str(),
 //!     "MKDasher, Nahoc, sonicpacker, Bauru, Eru, Goronem, Jesus, Kyman, Mokkori, Moltov, Nothing693, pasta, SilentSlayers, Snark, and ToT");
-//! assert_eq!(m64.description.as_str().trim_matches(char::from(0)),
+//! assert_eq!(m64.description.as_str(),
 //!     "18:08.33 saved over Rikku.");
 //! assert_eq!(m64.rerecords, 2136942);
 //! assert_eq!(m64.vi_frames, 290491);
 //! ```
+//!
+//! # Features
+//!
+//! - `std` (default): Enables everything that needs the standard library, such as reading and writing through `std::io`
+//!   and the conversion, analysis and editing modules.
+//!   Without it, the crate is `#![no_std]` and only needs `alloc`, keeping the parser, [`M64::to_bytes`] and the core types.
+//! - `chrono` (default): Enables [`M64::recording_time`] returning a `chrono` date.
+//!   [`M64::recording_system_time`] returns the same time without any extra dependency.
+//! - `bytemuck`: Implements `Pod` for [`RawInput`], and reads and writes the input section of movies kept as raw samples in bulk,
+//!   see [`raw_inputs_from_le_bytes`](controller::raw_inputs_from_le_bytes).
+//! - `encoding`: Enables [decoding string fields](encoding) that aren't UTF-8, such as Shift-JIS author names.
+//! - `rayon`: Decodes the inputs of long movies, and searches, compares and computes statistics over them, in parallel.
+//!   The results are the same as without it.
+//! - `sm64`: Enables the [Super Mario 64 helpers](games::sm64).
+//! - `time`: Enables [`M64::recording_offset_date_time`] returning a `time` date.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod analysis;
+#[cfg(feature = "bk2")]
+pub mod bk2;
+pub mod checksum;
+pub mod compress;
+pub mod content;
 pub mod controller;
+#[cfg(feature = "std")]
+pub mod convert;
+#[cfg(feature = "std")]
+pub mod csv;
+pub mod detect;
+#[cfg(feature = "std")]
+pub mod diff;
+pub mod edit;
+#[cfg(feature = "encoding")]
+pub mod encoding;
 pub mod error;
+pub mod extended;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "std")]
+pub mod format;
+pub mod frame;
+#[cfg(feature = "sm64")]
+pub mod games;
+#[cfg(feature = "std")]
+pub mod hexdump;
+#[cfg(feature = "http")]
+pub mod http;
+pub mod in_place;
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(feature = "std")]
+pub mod lint;
+#[cfg(feature = "std")]
+pub mod lua;
 pub mod m64;
-mod parser;
-#[cfg(test)]
+#[cfg(feature = "std")]
+pub mod merge;
+pub mod metadata;
+pub mod normalize;
+#[cfg(feature = "json")]
+pub mod overlay;
+pub mod padded_string;
+pub mod parser;
+#[cfg(feature = "std")]
+pub mod patch;
+#[cfg(feature = "std")]
+pub mod plugin;
+#[cfg(feature = "std")]
+pub mod project;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod region;
+#[cfg(feature = "std")]
+pub mod repair;
+#[cfg(feature = "std")]
+pub mod rom;
+#[cfg(feature = "std")]
+pub mod save;
+#[cfg(feature = "std")]
+pub mod script;
+#[cfg(feature = "json")]
+pub mod sidecar;
+#[cfg(feature = "signing")]
+pub mod signing;
+#[cfg(feature = "std")]
+pub mod srt;
+#[cfg(feature = "std")]
+pub mod st;
+#[cfg(feature = "std")]
+pub mod stats;
+#[cfg(feature = "std")]
+pub mod stick;
+pub mod storage;
+#[cfg(feature = "std")]
+pub mod summary;
+#[cfg(feature = "std")]
+pub mod svg;
+#[cfg(feature = "std")]
+pub mod tasd;
+#[cfg(all(test, feature = "std"))]
 mod tests;
+pub mod timing;
+pub mod tracked;
+pub mod trailer;
+#[cfg(feature = "std")]
+pub mod validate;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "watch")]
+pub mod watch;
 
-pub use m64::M64;
-pub use controller::Input;
+pub use controller::{Buttons, Input, RawInput};
+pub use frame::Frame;
+pub use m64::{LazyM64, M64};
+pub use padded_string::PaddedString;
@@ -0,0 +1,184 @@
+//! Checks a movie against the TASVideos submission requirements.
+use std::fmt;
+
+pub use crate::error::Severity;
+use crate::{
+    m64::{MovieStartType, M64},
+    plugin::Plugin,
+};
+
+/// The requirements checked by [`vault_check`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LintRule {
+    /// The movie starts from a savestate instead of power on.
+    SnapshotStart,
+    /// The movie starts from existing save data, which needs a justification.
+    SaveStart,
+    /// The rerecord count is 0.
+    NoRerecords,
+    /// The author field is empty.
+    NoAuthor,
+    /// The movie has no inputs.
+    NoInputs,
+    /// No controller is plugged in.
+    NoControllers,
+    /// The `input_frames` header field doesn't match the stored inputs.
+    InputFramesMismatch,
+    /// The movie ends with idle frames, which don't count towards the time but are still played back.
+    TrailingIdleFrames,
+    /// A plugin isn't known, so its sync behaviour is unknown.
+    UnknownPlugin,
+    /// The `fps` header field doesn't match the VI rate of the ROM region, which is a sign of a hand edited header.
+    RegionFpsMismatch,
+}
+
+impl LintRule {
+    /// Returns how serious a violation of the rule is.
+    pub fn severity(self) -> Severity {
+        match self {
+            LintRule::SnapshotStart | LintRule::NoAuthor | LintRule::NoInputs => Severity::Error,
+            LintRule::SaveStart
+            | LintRule::NoRerecords
+            | LintRule::NoControllers
+            | LintRule::InputFramesMismatch
+            | LintRule::RegionFpsMismatch => Severity::Warning,
+            LintRule::TrailingIdleFrames | LintRule::UnknownPlugin => Severity::Info,
+        }
+    }
+
+    /// Returns the stable code of the rule, which never changes between versions.
+    pub fn code(self) -> &'static str {
+        match self {
+            LintRule::SnapshotStart => "L001",
+            LintRule::SaveStart => "L002",
+            LintRule::NoRerecords => "L003",
+            LintRule::NoAuthor => "L004",
+            LintRule::NoInputs => "L005",
+            LintRule::NoControllers => "L006",
+            LintRule::InputFramesMismatch => "L007",
+            LintRule::TrailingIdleFrames => "L008",
+            LintRule::UnknownPlugin => "L009",
+            LintRule::RegionFpsMismatch => "L010",
+        }
+    }
+}
+
+/// A rule violation found by [`vault_check`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Finding {
+    /// The rule that was violated.
+    pub rule: LintRule,
+    /// Details of the violation.
+    pub message: String,
+}
+
+impl Finding {
+    fn new<S>(rule: LintRule, message: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            rule,
+            message: message.into(),
+        }
+    }
+
+    /// Returns how serious the finding is.
+    pub fn severity(&self) -> Severity {
+        self.rule.severity()
+    }
+
+    /// Returns the stable code of the violated rule.
+    pub fn code(&self) -> &'static str {
+        self.rule.code()
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}: {}", self.severity(), self.message)
+    }
+}
+
+/// Checks the movie against the TASVideos submission requirements.
+/// - Returns every finding, ordered from the most serious.
+pub fn vault_check(m64: &M64) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    match m64.movie_start_type {
+        MovieStartType::SnapShot | MovieStartType::ExistingSnapshot => findings.push(Finding::new(
+            LintRule::SnapshotStart,
+            "movie starts from a savestate, submissions must start from power on",
+        )),
+        MovieStartType::Eeprom => findings.push(Finding::new(
+            LintRule::SaveStart,
+            "movie starts from existing save data, which must be justified in the submission",
+        )),
+        MovieStartType::PowerOn => (),
+    }
+    if m64.total_rerecords() == 0 {
+        findings.push(Finding::new(LintRule::NoRerecords, "rerecord count is 0"));
+    }
+    if m64.author.as_str().trim().is_empty() {
+        findings.push(Finding::new(LintRule::NoAuthor, "author is empty"));
+    }
+    if m64.inputs.is_empty() {
+        findings.push(Finding::new(LintRule::NoInputs, "movie has no inputs"));
+    }
+    if !m64
+        .controller_flags
+        .iter()
+        .any(|flags| flags.controller_present)
+    {
+        findings.push(Finding::new(
+            LintRule::NoControllers,
+            "no controller is plugged in",
+        ));
+    }
+    if m64.input_frames as usize != m64.inputs.len() {
+        findings.push(Finding::new(
+            LintRule::InputFramesMismatch,
+            format!(
+                "header has {} input samples, but the movie holds {}",
+                m64.input_frames,
+                m64.inputs.len()
+            ),
+        ));
+    }
+
+    if let Some(region) = m64.country_code().region() {
+        if m64.fps as u32 != region.vi_rate() {
+            findings.push(Finding::new(
+                LintRule::RegionFpsMismatch,
+                format!(
+                    "header fps is {}, but the ROM is {region:?} which runs at {}",
+                    m64.fps,
+                    region.vi_rate()
+                ),
+            ));
+        }
+    }
+
+    let frames = m64.frame_count();
+    let last_active = m64.idle_report().last_active_frame;
+    let trailing = frames - last_active.map_or(0, |frame| frame + 1);
+    if trailing > 0 && !m64.inputs.is_empty() {
+        findings.push(Finding::new(
+            LintRule::TrailingIdleFrames,
+            format!("movie ends with {trailing} idle frames"),
+        ));
+    }
+
+    let plugins = m64.plugins();
+    for plugin in [plugins.video, plugins.sound, plugins.input, plugins.rsp] {
+        if plugin.plugin == Plugin::Unknown {
+            findings.push(Finding::new(
+                LintRule::UnknownPlugin,
+                format!("plugin \"{}\" isn't known", plugin.name),
+            ));
+        }
+    }
+
+    findings.sort_by_key(|finding| std::cmp::Reverse(finding.severity()));
+    findings
+}
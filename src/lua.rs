@@ -0,0 +1,84 @@
+//! Exporting movies as Lua scripts for mupen64-rr-lua.
+//!
+//! The generated script holds a table of inputs for each frame, and sets them with `joypad.set` from an `emu.atinput` callback.
+//! - The script advances a frame on each callback, so it should be started on the frame the movie starts from.
+//! - Buttons use the `joypad.set` names, and only held buttons and non zero stick axes are written.
+use crate::{
+    controller::{Buttons, Input},
+    m64::M64,
+};
+
+/// The buttons with their `joypad.set` names.
+const JOYPAD_BUTTONS: [(Buttons, &str); 14] = [
+    (Buttons::RIGHT_DPAD, "right"),
+    (Buttons::LEFT_DPAD, "left"),
+    (Buttons::DOWN_DPAD, "down"),
+    (Buttons::UP_DPAD, "up"),
+    (Buttons::START, "start"),
+    (Buttons::Z, "Z"),
+    (Buttons::B, "B"),
+    (Buttons::A, "A"),
+    (Buttons::RIGHT_CBUTTON, "Cright"),
+    (Buttons::LEFT_CBUTTON, "Cleft"),
+    (Buttons::DOWN_CBUTTON, "Cdown"),
+    (Buttons::UP_CBUTTON, "Cup"),
+    (Buttons::RIGHT_SHOULDER, "R"),
+    (Buttons::LEFT_SHOULDER, "L"),
+];
+
+impl M64 {
+    /// Returns a Lua script that replays the inputs through `joypad.set`.
+    /// - See the [module documentation](crate::lua) for how the script works.
+    pub fn to_lua_joypad_script(&self) -> String {
+        let ports = self
+            .controller_ports()
+            .iter()
+            .map(|port| (port + 1).to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let mut script = String::new();
+        script.push_str(&format!("-- {}\n", self.rom_internal_name.as_str()));
+        script.push_str(&format!("local ports = {{ {ports} }}\n"));
+        script.push_str("local frames = {\n");
+        for frame in self.frames() {
+            let inputs = frame.iter().map(joypad_table).collect::<Vec<_>>();
+            script.push_str(&format!("  {{ {} }},\n", inputs.join(", ")));
+        }
+        script.push_str("}\n");
+        script.push_str(
+            "local frame = 0
+
+emu.atinput(function()
+  frame = frame + 1
+  local inputs = frames[frame]
+  if inputs == nil then
+    return
+  end
+  for i, port in ipairs(ports) do
+    joypad.set(port, inputs[i])
+  end
+end)
+",
+        );
+        script
+    }
+}
+
+/// Writes a single input as a Lua table for `joypad.set`.
+fn joypad_table(input: &Input) -> String {
+    let buttons = input.buttons();
+    let mut fields = JOYPAD_BUTTONS
+        .iter()
+        .filter(|(button, _)| buttons.contains(*button))
+        .map(|(_, name)| format!("{name} = true"))
+        .collect::<Vec<_>>();
+    if input.x_axis != 0 {
+        fields.push(format!("X = {}", input.x_axis));
+    }
+    if input.y_axis != 0 {
+        fields.push(format!("Y = {}", input.y_axis));
+    }
+
+    format!("{{{}}}", fields.join(", "))
+}
@@ -1,31 +1,54 @@
 //! Contains the M64 struct and other types used for the M64 file.
-use std::io::{self, Read, Write};
+use alloc::{borrow::ToOwned, vec::Vec};
+#[cfg(feature = "chrono")]
+use core::num::TryFromIntError;
+use core::ops::{Index, IndexMut};
+#[cfg(feature = "std")]
+use std::{
+    fs::File,
+    io::{self, Read, Write},
+    path::Path,
+};
 
-use arrayvec::ArrayString;
+#[cfg(feature = "chrono")]
 use chrono::{DateTime, LocalResult, TimeZone, Utc};
-use nom::{error::VerboseErrorKind, Finish};
+use nom::{error::ErrorKind, Finish};
 use strum_macros::FromRepr;
 
+#[cfg(feature = "bytemuck")]
+use crate::controller::raw_inputs_to_le_bytes;
 use crate::{
-    controller::{Flags, Input},
+    controller::{Flags, Input, RawInput},
+    detect::{detect_format, MovieFileFormat},
     error::*,
-    parser,
+    extended::ExtendedHeader,
+    frame::{Frame, FrameIndex},
+    padded_string::PaddedString,
+    parser::{self, ParseField},
+    storage::InputStorage,
+    trailer::MetadataBlock,
 };
 
 /// The M64 file.
 /// Follows the format described in [this document](https://tasvideos.org/EmulatorResources/Mupen/M64).
+/// - With the `serde` feature, the string fields are serialized with their NUL padding so the exact bytes are kept.
+/// - The inputs are stored in a `Vec<Input>` by default, see [`InputStorage`] for other containers.
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
-pub struct M64 {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct M64<S = Vec<Input>> {
     /// Identifies the movie-savestate relationship.
     /// Also used as the recording time in unix epoch format.
     pub uid: u32,
     /// Number of vertical interrupt frames.
     pub vi_frames: u32,
     /// Number of input samples for any controllers.
+    /// - Despite the name, this counts samples across all controllers, use [`M64::header_frame_count`] for the number of frames.
     pub input_frames: u32,
     /// Rerecord count.
+    /// - Holds the lower 32 bits if the extended header stores a larger count, see [`M64::total_rerecords`].
     pub rerecords: u32,
     /// Frames per second in vertical interrupt frames.
+    /// - Rounded to a whole number, see [`M64::vertical_rate`] for the actual rate.
     pub fps: u8,
     /// The number of controllers.
     pub controller_count: u8,
@@ -34,204 +57,239 @@ pub struct M64 {
     /// The controller flags.
     pub controller_flags: [Flags; 4],
     /// Internal name of the ROM used when recording, directly from the ROM.
-    pub rom_internal_name: ArrayString<32>,
+    pub rom_internal_name: PaddedString<32>,
     /// CRC32 of the ROM used when recording, directly from the ROM.
     pub rom_crc_32: u32,
     /// Country code of the ROM used when recording, directly from the ROM.
+    /// - Use [`M64::country_code`] for the typed value and its region.
     pub rom_country_code: u16,
     /// Name of the video plugin used when recording, direcltly from the plugin.
-    pub video_plugin: ArrayString<64>,
+    pub video_plugin: PaddedString<64>,
     /// Name of the sound plugin used when recording, directly from the plugin.
-    pub sound_plugin: ArrayString<64>,
+    pub sound_plugin: PaddedString<64>,
     /// Name of the input plugin used when recording, directly from the plugin.
-    pub input_plugin: ArrayString<64>,
+    pub input_plugin: PaddedString<64>,
     /// Name of the RSP plugin used when recording, directly from the plugin.
-    pub rsp_plugin: ArrayString<64>,
+    pub rsp_plugin: PaddedString<64>,
     /// Author(s) of the TAS.
-    pub author: ArrayString<222>,
+    pub author: PaddedString<222>,
     /// Description of the TAS.
-    pub description: ArrayString<256>,
+    pub description: PaddedString<256>,
+    /// The mupen64-rr-lua extended header, if the movie has one.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub extended: Option<ExtendedHeader>,
+    /// Keyed metadata stored after the input samples, see the [`trailer`](crate::trailer) module.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub metadata_block: MetadataBlock,
 
     /// The input samples.
-    pub inputs: Vec<Input>,
+    pub inputs: S,
 }
 
-impl M64 {
-    /// Creates an instance of `M64` from an array of bytes.
-    pub fn from_u8_array(data: &[u8]) -> Result<Self, M64ParseError> {
-        let parse_result = parser::m64_from_u8(data).finish();
-
-        match parse_result {
-            Ok(parse_result) => Ok(parse_result.1),
-            Err(err) => {
-                let mut context = None;
-                let mut nom = None;
-                // at least 1 error will exist
-                let input = err.errors.first().unwrap().0;
-
-                for err in &err.errors {
-                    match &err.1 {
-                        VerboseErrorKind::Context(c) => context = Some(c),
-                        VerboseErrorKind::Char(ch) => {
-                            unimplemented!("VerboseErrorKind::Char({}) is not handled", ch)
-                        }
-                        VerboseErrorKind::Nom(n) => nom = Some(n),
-                    }
-                }
+/// A movie that keeps the raw input samples, decoding an [`Input`] each time one is accessed.
+/// - Takes 4 bytes per sample instead of 18, for analyses that only look at some of the frames.
+/// - Access the inputs with [`M64::input`] and [`M64::iter_inputs`], or decode them all with [`M64::with_storage`].
+pub type LazyM64 = M64<Vec<RawInput>>;
+
+impl<S> M64<S>
+where
+    S: InputStorage,
+{
+    /// Creates an instance of `M64` from an array of bytes, keeping the inputs in `S`.
+    pub fn from_u8_array_with_storage(data: &[u8]) -> Result<Self, M64ParseError> {
+        Self::parse(data, false)
+    }
+
+    /// Parses the movie, requiring every reserved byte to be 0 if `strict` is set.
+    fn parse(data: &[u8], strict: bool) -> Result<Self, M64ParseError> {
+        let err = match parser::m64_from_u8(data, strict).finish() {
+            Ok(((), m64)) => return Ok(m64),
+            Err(err) => err,
+        };
+
+        Err(parse_error(data, err))
+    }
+
+    /// Writes the `M64` instance to a given writer.
+    #[cfg(feature = "std")]
+    pub fn write_m64<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        self.write_with(|bytes| writer.write_all(bytes))
+    }
+
+    /// Returns the `M64` instance as the bytes of a `.m64` file.
+    /// - The buffer is allocated once at its final size.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let sample_count = self.inputs.sample_count();
+        let block = self.metadata_block.to_bytes();
+        let mut bytes = Vec::with_capacity(0x400 + sample_count * 4 + block.len());
+
+        bytes.extend_from_slice(&self.raw_header());
+        #[cfg(feature = "bytemuck")]
+        if let Some(raw) = self.inputs.raw_samples() {
+            bytes.extend_from_slice(&raw_inputs_to_le_bytes(raw));
+            bytes.extend_from_slice(&block);
+            return bytes;
+        }
+        for index in 0..sample_count {
+            bytes.extend_from_slice(&self.inputs.raw_sample(index).unwrap().to_le_bytes());
+        }
+        bytes.extend_from_slice(&block);
+        bytes
+    }
+
+    /// Returns the 1024 byte header as it's written to the `.m64` file, without allocating.
+    pub fn raw_header(&self) -> [u8; 0x400] {
+        let mut header = [0; 0x400];
+        let mut put = |offset: usize, bytes: &[u8]| {
+            header[offset..offset + bytes.len()].copy_from_slice(bytes);
+        };
+
+        // reserved bytes are left as 0
+        put(0x000, b"M64\x1A");
+        put(0x004, &3u32.to_le_bytes());
+        put(0x008, &self.uid.to_le_bytes());
+        put(0x00C, &self.vi_frames.to_le_bytes());
+        put(0x010, &self.rerecords.to_le_bytes());
+        put(0x014, &[self.fps, self.controller_count]);
+        // a version of 0 marks a movie without an extended header, so none is written
+        if let Some(extended) = self.extended.filter(|extended| extended.version != 0) {
+            put(0x016, &[extended.version, extended.flags.to_u8()]);
+            put(0x024, &extended.data_bytes());
+        }
+        put(0x018, &self.input_frames.to_le_bytes());
+        put(0x01C, &(self.movie_start_type as u16).to_le_bytes());
+        put(0x020, &Flags::to_u32(&self.controller_flags).to_le_bytes());
+        put(0x0C4, self.rom_internal_name.as_padded_str().as_bytes());
+        put(0x0E4, &self.rom_crc_32.to_le_bytes());
+        put(0x0E8, &self.rom_country_code.to_le_bytes());
+        put(0x122, self.video_plugin.as_padded_str().as_bytes());
+        put(0x162, self.sound_plugin.as_padded_str().as_bytes());
+        put(0x1A2, self.input_plugin.as_padded_str().as_bytes());
+        put(0x1E2, self.rsp_plugin.as_padded_str().as_bytes());
+        put(0x222, self.author.as_padded_str().as_bytes());
+        put(0x300, self.description.as_padded_str().as_bytes());
+        header
+    }
 
-                let nom = nom.unwrap();
-
-                match context {
-                    Some(context) => match *context {
-                        "signature" => {
-                            let input = if input.len() >= 4 {
-                                input[0..4].to_owned()
-                            } else {
-                                input.to_owned()
-                            };
-                            Err(M64ParseError::InvalidSignature(input))
-                        }
-                        "version" => {
-                            if let nom::error::ErrorKind::Eof = nom {
-                                Err(M64ParseError::NotEnoughBytes {
-                                    field: FieldName::Version,
-                                    requires: 4 - input.len(),
-                                })
-                            } else {
-                                let input = u32::from_le_bytes(input[0..4].try_into().unwrap());
-                                Err(M64ParseError::InvalidVersion(input))
-                            }
-                        }
-                        "uid" => Err(M64ParseError::NotEnoughBytes {
-                            field: FieldName::Uid,
-                            requires: 4 - input.len(),
-                        }),
-                        "vi_frames" => Err(M64ParseError::NotEnoughBytes {
-                            field: FieldName::ViFrames,
-                            requires: 4 - input.len(),
-                        }),
-                        "input_frames" => Err(M64ParseError::NotEnoughBytes {
-                            field: FieldName::InputFrames,
-                            requires: 4 - input.len(),
-                        }),
-                        "rerecords" => Err(M64ParseError::NotEnoughBytes {
-                            field: FieldName::Rerecords,
-                            requires: 4 - input.len(),
-                        }),
-                        "fps" => Err(M64ParseError::NotEnoughBytes {
-                            field: FieldName::Fps,
-                            requires: 1,
-                        }),
-                        "controller_count" => Err(M64ParseError::NotEnoughBytes {
-                            field: FieldName::ControllerCount,
-                            requires: 1,
-                        }),
-                        "reserved_0x16" => Err(M64ParseError::ReservedNotZero(0x16)),
-                        "movie_start_type" => {
-                            if let nom::error::ErrorKind::Eof = nom {
-                                Err(M64ParseError::NotEnoughBytes {
-                                    field: FieldName::MovieStartType,
-                                    requires: 2 - input.len(),
-                                })
-                            } else {
-                                Err(M64ParseError::InvalidMovieStartType)
-                            }
-                        }
-                        "reserved_0x1E" => Err(M64ParseError::ReservedNotZero(0x1E)),
-                        "controller_flags" => Err(M64ParseError::NotEnoughBytes {
-                            field: FieldName::ControllerFlags,
-                            requires: 4 - input.len(),
-                        }),
-                        "reserved_0x24" => Err(M64ParseError::ReservedNotZero(0x24)),
-                        "rom_internal_name" => {
-                            if let nom::error::ErrorKind::MapRes = nom {
-                                Err(M64ParseError::InvalidString(FieldName::RomInternalName))
-                            } else {
-                                Err(M64ParseError::NotEnoughBytes {
-                                    field: FieldName::RomInternalName,
-                                    requires: 32 - input.len(),
-                                })
-                            }
-                        }
-                        "rom_crc_32" => Err(M64ParseError::NotEnoughBytes {
-                            field: FieldName::RomCrc32,
-                            requires: 4 - input.len(),
-                        }),
-                        "rom_country_code" => Err(M64ParseError::NotEnoughBytes {
-                            field: FieldName::RomCountryCode,
-                            requires: 2 - input.len(),
-                        }),
-                        "reserved_0xEA" => Err(M64ParseError::ReservedNotZero(0xEA)),
-                        "video_plugin" => {
-                            if let nom::error::ErrorKind::MapRes = nom {
-                                Err(M64ParseError::InvalidString(FieldName::VideoPlugin))
-                            } else {
-                                Err(M64ParseError::NotEnoughBytes {
-                                    field: FieldName::VideoPlugin,
-                                    requires: 64 - input.len(),
-                                })
-                            }
-                        }
-                        "sound_plugin" => {
-                            if let nom::error::ErrorKind::MapRes = nom {
-                                Err(M64ParseError::InvalidString(FieldName::SoundPlugin))
-                            } else {
-                                Err(M64ParseError::NotEnoughBytes {
-                                    field: FieldName::SoundPlugin,
-                                    requires: 64 - input.len(),
-                                })
-                            }
-                        }
-                        "input_plugin" => {
-                            if let nom::error::ErrorKind::MapRes = nom {
-                                Err(M64ParseError::InvalidString(FieldName::InputPlugin))
-                            } else {
-                                Err(M64ParseError::NotEnoughBytes {
-                                    field: FieldName::InputPlugin,
-                                    requires: 64 - input.len(),
-                                })
-                            }
-                        }
-                        "rsp_plugin" => {
-                            if let nom::error::ErrorKind::MapRes = nom {
-                                Err(M64ParseError::InvalidString(FieldName::RspPlugin))
-                            } else {
-                                Err(M64ParseError::NotEnoughBytes {
-                                    field: FieldName::RspPlugin,
-                                    requires: 64 - input.len(),
-                                })
-                            }
-                        }
-                        "author" => {
-                            if let nom::error::ErrorKind::MapRes = nom {
-                                Err(M64ParseError::InvalidString(FieldName::Author))
-                            } else {
-                                Err(M64ParseError::NotEnoughBytes {
-                                    field: FieldName::Author,
-                                    requires: 222 - input.len(),
-                                })
-                            }
-                        }
-                        "description" => {
-                            if let nom::error::ErrorKind::MapRes = nom {
-                                Err(M64ParseError::InvalidString(FieldName::Description))
-                            } else {
-                                Err(M64ParseError::NotEnoughBytes {
-                                    field: FieldName::Description,
-                                    requires: 256 - input.len(),
-                                })
-                            }
-                        }
-                        "eof" => Err(M64ParseError::InputNot4BytesAligned(input.len())),
-                        _ => unimplemented!("context: {}\n{:?}", context, nom),
-                    },
-                    None => unimplemented!("No context found for m64 parser error"),
+    /// Passes the bytes of the `.m64` file to `write` in order.
+    /// - The inputs are passed in chunks of up to 4 KiB rather than one sample at a time.
+    /// - With the `bytemuck` feature, inputs kept as [`RawInput`]s are passed all at once.
+    pub(crate) fn write_with<F, E>(&self, mut write: F) -> Result<(), E>
+    where
+        F: FnMut(&[u8]) -> Result<(), E>,
+    {
+        write(&self.raw_header())?;
+
+        // inputs
+        match self.inputs.raw_samples() {
+            #[cfg(feature = "bytemuck")]
+            Some(raw) => write(&raw_inputs_to_le_bytes(raw))?,
+            _ => {
+                let mut chunk = [0; 0x1000];
+                let sample_count = self.inputs.sample_count();
+                for start in (0..sample_count).step_by(chunk.len() / 4) {
+                    let end = (start + chunk.len() / 4).min(sample_count);
+                    for (index, bytes) in (start..end).zip(chunk.chunks_exact_mut(4)) {
+                        bytes
+                            .copy_from_slice(&self.inputs.raw_sample(index).unwrap().to_le_bytes());
+                    }
+                    write(&chunk[..(end - start) * 4])?;
                 }
             }
         }
+
+        // metadata block
+        if !self.metadata_block.is_empty() {
+            write(&self.metadata_block.to_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the input sample at `index`, or `None` if it's out of range.
+    pub fn input(&self, index: usize) -> Option<Input> {
+        self.inputs.sample(index)
+    }
+
+    /// Replaces the input sample at `index`, returning the previous one, or `None` if it's out of range.
+    pub fn set_input(&mut self, index: usize, input: Input) -> Option<Input> {
+        self.inputs.replace_sample(index, input)
+    }
+
+    /// Returns an iterator over the input samples, decoding each one as it's reached.
+    pub fn iter_inputs(&self) -> impl Iterator<Item = Input> + '_ {
+        (0..self.inputs.sample_count()).map(|index| self.inputs.sample(index).unwrap())
+    }
+
+    /// Moves the inputs into another storage, keeping the header.
+    pub fn with_storage<T>(self) -> M64<T>
+    where
+        T: InputStorage,
+    {
+        let inputs = T::from_inputs(
+            (0..self.inputs.sample_count()).map(|index| self.inputs.sample(index).unwrap()),
+        );
+
+        M64 {
+            uid: self.uid,
+            vi_frames: self.vi_frames,
+            input_frames: self.input_frames,
+            rerecords: self.rerecords,
+            fps: self.fps,
+            controller_count: self.controller_count,
+            movie_start_type: self.movie_start_type,
+            controller_flags: self.controller_flags,
+            rom_internal_name: self.rom_internal_name,
+            rom_crc_32: self.rom_crc_32,
+            rom_country_code: self.rom_country_code,
+            video_plugin: self.video_plugin,
+            sound_plugin: self.sound_plugin,
+            input_plugin: self.input_plugin,
+            rsp_plugin: self.rsp_plugin,
+            author: self.author,
+            description: self.description,
+            extended: self.extended,
+            metadata_block: self.metadata_block,
+            inputs,
+        }
+    }
+}
+
+impl M64 {
+    /// Creates an instance of `M64` from an array of bytes.
+    pub fn from_u8_array(data: &[u8]) -> Result<Self, M64ParseError> {
+        Self::from_u8_array_with_storage(data)
+    }
+
+    /// Creates an instance of `M64` from an array of bytes, requiring every reserved byte to be 0.
+    /// - Movies with a mupen64-rr-lua [extended header](crate::extended) are rejected with [`M64ParseError::ReservedNotZero`].
+    pub fn from_u8_array_strict(data: &[u8]) -> Result<Self, M64ParseError> {
+        Self::parse(data, true)
+    }
+
+    /// Creates an instance of `M64` from the 1024 byte header and the input samples.
+    /// - The header fields are kept as they are, including `input_frames`, even if it doesn't match the inputs.
+    pub fn from_header_and_inputs<I>(header: &[u8; 0x400], inputs: I) -> Result<Self, M64ParseError>
+    where
+        I: IntoIterator<Item = Input>,
+    {
+        let m64 = Self::from_u8_array(header)?;
+        Ok(M64 {
+            inputs: inputs.into_iter().collect(),
+            ..m64
+        })
+    }
+
+    /// Creates a [`LazyM64`] from an array of bytes, which decodes the inputs only when they are accessed.
+    pub fn from_u8_array_lazy(data: &[u8]) -> Result<LazyM64, M64ParseError> {
+        LazyM64::from_u8_array_with_storage(data)
     }
 
     /// Creates an instance of `M64` from a given reader.
+    #[cfg(feature = "std")]
     pub fn read_m64<R>(mut reader: R) -> Result<Self, M64ParseError>
     where
         R: Read,
@@ -241,86 +299,438 @@ impl M64 {
         Self::from_u8_array(&data)
     }
 
-    /// Writes the `M64` instance to a given writer.
-    pub fn write_m64<W>(&self, writer: &mut W) -> io::Result<()>
+    /// Creates an instance of `M64` from the file at `path`.
+    /// - The file is read in one go, into a buffer sized from the file's metadata.
+    /// - Errors hold the path, and whether opening, reading or parsing the file failed.
+    #[cfg(feature = "std")]
+    pub fn from_path<P>(path: P) -> Result<Self, LoadError>
     where
-        W: Write,
+        P: AsRef<Path>,
     {
-        // signature
-        writer.write_all(b"M64\x1A")?;
-        // version number
-        writer.write_all(&3u32.to_le_bytes())?;
-        // uid
-        writer.write_all(&self.uid.to_le_bytes())?;
-        // vi frame count
-        writer.write_all(&self.vi_frames.to_le_bytes())?;
-        // rerecord count
-        writer.write_all(&self.rerecords.to_le_bytes())?;
-        // fps
-        writer.write_all(&self.fps.to_le_bytes())?;
-        // controller count
-        writer.write_all(&self.controller_count.to_le_bytes())?;
-        // reserved
-        writer.write_all(&[0; 2])?;
-        // input frame count
-        writer.write_all(&self.input_frames.to_le_bytes())?;
-        // movie start type
-        writer.write_all(&(self.movie_start_type as u16).to_le_bytes())?;
-        // reserved
-        writer.write_all(&[0; 2])?;
-        // controller flags
-        writer.write_all(&Flags::to_u32(&self.controller_flags).to_le_bytes())?;
-        // reserved
-        writer.write_all(&[0; 160])?;
-        // rom internal name
-        writer.write_all(self.rom_internal_name.as_bytes())?;
-        // rom crc 32
-        writer.write_all(&self.rom_crc_32.to_le_bytes())?;
-        // rom country code
-        writer.write_all(&self.rom_country_code.to_le_bytes())?;
-        // reserved
-        writer.write_all(&[0; 56])?;
-        // video plugin
-        writer.write_all(self.video_plugin.as_bytes())?;
-        // sound plugin
-        writer.write_all(self.sound_plugin.as_bytes())?;
-        // input plugin
-        writer.write_all(self.input_plugin.as_bytes())?;
-        // rsp plugin
-        writer.write_all(self.rsp_plugin.as_bytes())?;
-        // author
-        writer.write_all(self.author.as_bytes())?;
-        // description
-        writer.write_all(self.description.as_bytes())?;
+        let path = path.as_ref();
+        let mut file = File::open(path).map_err(|source| LoadError::Open {
+            path: path.to_owned(),
+            source,
+        })?;
 
-        // inputs
-        for input in &self.inputs {
-            writer.write_all(&u32::from(*input).to_le_bytes())?;
-        }
+        // the size is only a hint, reading still goes to the end of the file
+        let size = file
+            .metadata()
+            .map_or(0, |metadata| metadata.len() as usize);
+        let mut data = Vec::with_capacity(size);
+        file.read_to_end(&mut data)
+            .map_err(|source| LoadError::Read {
+                path: path.to_owned(),
+                source,
+            })?;
 
-        Ok(())
+        Self::from_u8_array(&data).map_err(|source| LoadError::Parse {
+            path: path.to_owned(),
+            source,
+        })
     }
 
     /// Returns the recording time of the movie in unix epoch format, from the M64 uid.
+    #[cfg(feature = "chrono")]
     pub fn recording_time(&self) -> LocalResult<DateTime<Utc>> {
         Utc.timestamp_opt(self.uid as i64, 0)
     }
+
+    /// Sets the recording time of the movie, updating the M64 uid.
+    /// - Returns an error if the time can't be stored, which is any time before 1970 or after 2106.
+    ///
+    /// # Savestates
+    /// The uid also identifies the movie, and savestates made while recording store it.
+    /// Changing it disassociates every savestate made for the movie so far, which Mupen64 then treats as belonging to another movie.
+    #[cfg(feature = "chrono")]
+    pub fn set_recording_time(&mut self, time: DateTime<Utc>) -> Result<(), TryFromIntError> {
+        self.uid = u32::try_from(time.timestamp())?;
+        Ok(())
+    }
+
+    /// Returns the recording time of the movie as a [`SystemTime`](std::time::SystemTime), from the M64 uid.
+    #[cfg(feature = "std")]
+    pub fn recording_system_time(&self) -> std::time::SystemTime {
+        std::time::UNIX_EPOCH + core::time::Duration::from_secs(self.uid as u64)
+    }
+
+    /// Returns the recording time of the movie as a [`time::OffsetDateTime`] in UTC, from the M64 uid.
+    #[cfg(feature = "time")]
+    pub fn recording_offset_date_time(&self) -> time::OffsetDateTime {
+        // any u32 is a valid timestamp
+        time::OffsetDateTime::from_unix_timestamp(self.uid as i64).unwrap()
+    }
+
+    /// Returns the number of input samples that make up a single frame.
+    /// - Inputs are stored interleaved, one sample per controller for each frame.
+    /// - A controller count of 0 is treated as 1 controller.
+    pub fn samples_per_frame(&self) -> usize {
+        (self.controller_count as usize).max(1)
+    }
+
+    /// Returns the number of input samples, across all controllers.
+    pub fn sample_count(&self) -> usize {
+        self.inputs.len()
+    }
+
+    /// Returns the number of logical frames, counting an incomplete last frame.
+    pub fn frame_count(&self) -> usize {
+        self.inputs.len().div_ceil(self.samples_per_frame())
+    }
+
+    /// Returns the number of logical frames the `input_frames` header field counts, including an incomplete last frame.
+    pub fn header_frame_count(&self) -> usize {
+        (self.input_frames as usize).div_ceil(self.samples_per_frame())
+    }
+
+    /// Returns the frame and controller of the input sample at `sample`, as `(frame, controller)`.
+    pub fn sample_to_frame(&self, sample: usize) -> (usize, usize) {
+        let stride = self.samples_per_frame();
+        (sample / stride, sample % stride)
+    }
+
+    /// Returns the controller ports the input samples of each frame belong to.
+    /// - Falls back to the first ports if the present controller flags don't match the controller count.
+    #[cfg(feature = "std")]
+    pub(crate) fn controller_ports(&self) -> Vec<usize> {
+        let present = (0..4)
+            .filter(|&port| self.controller_flags[port].controller_present)
+            .collect::<Vec<_>>();
+
+        if present.len() == self.samples_per_frame() {
+            present
+        } else {
+            (0..self.samples_per_frame()).collect()
+        }
+    }
+
+    /// Returns an iterator over the inputs of a single controller.
+    /// - `controller` is the index of the controller in the interleaved input data, starting from 0.
+    /// - Yields nothing if `controller` is out of range of the controller count.
+    pub fn inputs_for_controller(&self, controller: usize) -> impl Iterator<Item = Input> + '_ {
+        let stride = self.samples_per_frame();
+        let skip = if controller < stride {
+            controller
+        } else {
+            self.inputs.len()
+        };
+
+        self.inputs.iter().skip(skip).step_by(stride).copied()
+    }
+
+    /// Returns the inputs split up for each controller.
+    pub fn controllers(&self) -> Vec<Vec<Input>> {
+        (0..self.samples_per_frame())
+            .map(|controller| self.inputs_for_controller(controller).collect())
+            .collect()
+    }
+
+    /// Returns an iterator over the logical frames of the movie.
+    /// - If the number of input samples isn't a multiple of the controller count, the last frame holds fewer inputs.
+    pub fn frames(&self) -> impl Iterator<Item = Frame> + '_ {
+        self.inputs
+            .chunks(self.samples_per_frame())
+            .map(|inputs| Frame::from_inputs(inputs).unwrap())
+    }
+
+    /// Returns an iterator over the logical frames of the movie, along with their frame index.
+    pub fn enumerate_frames(&self) -> impl Iterator<Item = (usize, Frame)> + '_ {
+        self.frames().enumerate()
+    }
+
+    /// Returns an iterator over all overlapping windows of `size` frames.
+    /// - Each window is the input samples of `size` consecutive frames, use `chunks(m64.samples_per_frame())` on it to split it into frames.
+    /// - Yields nothing if the movie is shorter than `size` frames.
+    ///
+    /// # Panics
+    /// Panics if `size` is 0.
+    pub fn windows(&self, size: usize) -> impl Iterator<Item = &[Input]> + '_ {
+        assert!(size != 0, "window size must be non-zero");
+
+        let stride = self.samples_per_frame();
+        self.inputs.windows(size * stride).step_by(stride)
+    }
+
+    /// Returns the estimated number of input frames per second.
+    /// - Estimated from the header with `VI rate * input frames / vi frames`, since games don't always poll for input every VI.
+    /// - Uses the actual VI rate of the region, see [`M64::vertical_rate`].
+    /// - Falls back to the VI rate if there are no VI frames.
+    pub fn input_frames_per_second(&self) -> f64 {
+        let rate = self.vertical_rate().exact();
+        if self.vi_frames == 0 {
+            return rate;
+        }
+
+        let frames = self.input_frames as f64 / self.samples_per_frame() as f64;
+        rate * frames / self.vi_frames as f64
+    }
+
+    /// Returns an iterator over the frames grouped into one second chunks.
+    /// - The chunk size is [`M64::input_frames_per_second`] rounded to the nearest frame.
+    /// - Each chunk is the input samples of those frames, and the last chunk may be shorter.
+    pub fn chunks_per_second(&self) -> impl Iterator<Item = &[Input]> + '_ {
+        // rounds without `f64::round`, which needs std, as the rate is never negative
+        let frames_per_second = ((self.input_frames_per_second() + 0.5) as usize).max(1);
+
+        self.inputs
+            .chunks(frames_per_second * self.samples_per_frame())
+    }
+
+    /// Returns an iterator over the indices of frames that trigger a reset.
+    /// - A frame triggers a reset if any of its samples have both reserved bits set, see [`Input::is_reset`].
+    pub fn reset_frames(&self) -> impl Iterator<Item = usize> + '_ {
+        self.enumerate_frames()
+            .filter(|(_, frame)| frame.iter().any(Input::is_reset))
+            .map(|(i, _)| i)
+    }
+
+    /// Returns if any frame triggers a reset.
+    pub fn has_resets(&self) -> bool {
+        self.inputs.iter().any(Input::is_reset)
+    }
+
+    /// Returns a digest of the input samples only, ignoring the rest of the movie.
+    /// - The digest is the 64-bit FNV-1a hash of the raw input samples in little endian,
+    ///   and is stable across versions of this crate and platforms.
+    pub fn input_hash(&self) -> u64 {
+        const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+        self.inputs
+            .iter()
+            .flat_map(|input| u32::from(*input).to_le_bytes())
+            .fold(FNV_OFFSET_BASIS, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+            })
+    }
+
+    /// Returns an iterator over runs of consecutive identical input samples, as `(input, length)` pairs.
+    /// - Runs are over the raw interleaved samples, so for movies with multiple controllers,
+    ///   adjacent samples belong to different controllers.
+    pub fn runs(&self) -> impl Iterator<Item = (Input, usize)> + '_ {
+        let mut inputs = self.inputs.iter().copied().peekable();
+
+        core::iter::from_fn(move || {
+            let input = inputs.next()?;
+            let mut length = 1;
+            while inputs.next_if_eq(&input).is_some() {
+                length += 1;
+            }
+
+            Some((input, length))
+        })
+    }
+
+    /// Appends a frame to the end of the inputs, and increases `input_frames` accordingly.
+    /// - Returns the frame back if it doesn't hold an input for every controller.
+    pub fn push_frame(&mut self, frame: Frame) -> Result<(), Frame> {
+        if frame.len() != self.samples_per_frame() {
+            return Err(frame);
+        }
+
+        self.extend(frame.iter().copied());
+        Ok(())
+    }
+
+    /// Returns the inputs at the given frame index, or `None` if out of range.
+    /// - `m64.get(frame)` returns the inputs of every controller for that frame.
+    /// - `m64.get((frame, controller))` returns the input of a single controller.
+    pub fn get<I: FrameIndex>(&self, index: I) -> Option<&I::Output> {
+        index.get(self)
+    }
+
+    /// Returns the mutable inputs at the given frame index, or `None` if out of range.
+    /// - Works the same way as [`M64::get`].
+    pub fn get_mut<I: FrameIndex>(&mut self, index: I) -> Option<&mut I::Output> {
+        index.get_mut(self)
+    }
+}
+
+impl Default for M64 {
+    /// Creates an empty power on movie for a single controller running at 60 fps.
+    fn default() -> Self {
+        let mut controller_flags = Flags::from_u32(0);
+        controller_flags[0].controller_present = true;
+
+        M64 {
+            uid: 0,
+            vi_frames: 0,
+            input_frames: 0,
+            rerecords: 0,
+            fps: 60,
+            controller_count: 1,
+            movie_start_type: MovieStartType::default(),
+            controller_flags,
+            rom_internal_name: PaddedString::zeroed(),
+            rom_crc_32: 0,
+            rom_country_code: 0,
+            video_plugin: PaddedString::zeroed(),
+            sound_plugin: PaddedString::zeroed(),
+            input_plugin: PaddedString::zeroed(),
+            rsp_plugin: PaddedString::zeroed(),
+            author: PaddedString::zeroed(),
+            description: PaddedString::zeroed(),
+            extended: None,
+            metadata_block: MetadataBlock::default(),
+            inputs: Vec::new(),
+        }
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for M64 {
+    /// Generates a structurally valid movie.
+    /// - The first `controller_count` controllers are present, between 1 and 4 of them.
+    /// - The inputs hold whole frames, and `input_frames` matches them.
+    /// - Strings are cut off to fit their fields.
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let controller_count = u.int_in_range(1..=4)?;
+        let mut controller_flags = Flags::from_u32(0);
+        for flags in &mut controller_flags[..controller_count as usize] {
+            *flags = Flags {
+                controller_present: true,
+                ..u.arbitrary()?
+            };
+        }
+
+        let mut inputs = Vec::<Input>::arbitrary(u)?;
+        inputs.truncate(inputs.len() - inputs.len() % controller_count as usize);
+
+        Ok(M64 {
+            uid: u.arbitrary()?,
+            vi_frames: u.arbitrary()?,
+            input_frames: inputs.len() as u32,
+            rerecords: u.arbitrary()?,
+            fps: *u.choose(&[50, 60])?,
+            controller_count,
+            movie_start_type: u.arbitrary()?,
+            controller_flags,
+            rom_internal_name: PaddedString::truncated(u.arbitrary()?).0,
+            rom_crc_32: u.arbitrary()?,
+            rom_country_code: u.arbitrary()?,
+            video_plugin: PaddedString::truncated(u.arbitrary()?).0,
+            sound_plugin: PaddedString::truncated(u.arbitrary()?).0,
+            input_plugin: PaddedString::truncated(u.arbitrary()?).0,
+            rsp_plugin: PaddedString::truncated(u.arbitrary()?).0,
+            author: PaddedString::truncated(u.arbitrary()?).0,
+            description: PaddedString::truncated(u.arbitrary()?).0,
+            extended: None,
+            metadata_block: MetadataBlock::default(),
+            inputs,
+        })
+    }
+}
+
+impl Extend<Input> for M64 {
+    /// Appends input samples, and increases `input_frames` by the number of samples added.
+    fn extend<T: IntoIterator<Item = Input>>(&mut self, iter: T) {
+        let len = self.inputs.len();
+        self.inputs.extend(iter);
+
+        let added = (self.inputs.len() - len) as u32;
+        self.input_frames = self.input_frames.saturating_add(added);
+    }
+}
+
+impl FromIterator<Input> for M64 {
+    /// Creates a movie with the [default](M64::default) header from the input samples.
+    fn from_iter<T: IntoIterator<Item = Input>>(iter: T) -> Self {
+        let mut m64 = M64::default();
+        m64.extend(iter);
+        m64
+    }
+}
+
+/// Creates a movie using the header of `header` and the input samples from `inputs`.
+/// - The inputs of `header` are discarded, and `input_frames` is set to the number of samples collected.
+pub fn collect_into_m64<I>(header: M64, inputs: I) -> M64
+where
+    I: IntoIterator<Item = Input>,
+{
+    let mut m64 = M64 {
+        input_frames: 0,
+        inputs: Vec::new(),
+        ..header
+    };
+    m64.extend(inputs);
+    m64
+}
+
+impl<I: FrameIndex> Index<I> for M64 {
+    type Output = I::Output;
+
+    fn index(&self, index: I) -> &Self::Output {
+        self.get(index).expect("frame index out of range")
+    }
+}
+
+impl<I: FrameIndex> IndexMut<I> for M64 {
+    fn index_mut(&mut self, index: I) -> &mut Self::Output {
+        self.get_mut(index).expect("frame index out of range")
+    }
 }
 
 /// All possible movie start types.
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, FromRepr)]
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Default, FromRepr)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum MovieStartType {
     /// Movie begins from snapshot.
     /// - The snapshot will be loaded from an external file with the movie filename with the `st` extension.
     SnapShot = 1,
     /// Movie begins from power on.
+    #[default]
     PowerOn = 2,
     /// Movie begins from EEPROM.
     Eeprom = 4,
+    /// Movie begins from a savestate that already existed before recording, used by Mupen64-rr-lua.
+    /// - Played back the same way as [`MovieStartType::SnapShot`], loading the `st` file with the movie filename,
+    ///   but the savestate wasn't made by starting the recording, so it can belong to another movie.
+    ExistingSnapshot = 8,
 }
 
-impl Default for MovieStartType {
-    fn default() -> Self {
-        MovieStartType::PowerOn
+impl MovieStartType {
+    /// Returns if the movie begins from a savestate, which is either [`MovieStartType::SnapShot`] or [`MovieStartType::ExistingSnapshot`].
+    pub fn is_snapshot(self) -> bool {
+        matches!(
+            self,
+            MovieStartType::SnapShot | MovieStartType::ExistingSnapshot
+        )
+    }
+}
+
+/// Maps an error of the parsers into a [`M64ParseError`], where `data` is the whole input given to the parser.
+pub(crate) fn parse_error(data: &[u8], err: parser::ParseError<'_>) -> M64ParseError {
+    let input = err.input;
+    let eof = err.kind == ErrorKind::Eof;
+    let unknown = |context: Option<&'static str>| M64ParseError::Unknown {
+        context,
+        offset: data.len() - input.len(),
+    };
+
+    match err.field {
+        Some(ParseField::Signature) => match detect_format(data).format {
+            MovieFileFormat::M64 | MovieFileFormat::Unknown => {
+                M64ParseError::InvalidSignature(input[..input.len().min(4)].to_owned())
+            }
+            format => M64ParseError::WrongFormat(format),
+        },
+        Some(ParseField::Header(FieldName::Version)) if !eof => {
+            M64ParseError::InvalidVersion(u32::from_le_bytes(input[0..4].try_into().unwrap()))
+        }
+        Some(ParseField::Header(FieldName::MovieStartType)) if !eof => {
+            M64ParseError::InvalidMovieStartType
+        }
+        Some(ParseField::Header(field)) if err.kind == ErrorKind::MapRes => {
+            M64ParseError::InvalidString(field)
+        }
+        Some(ParseField::Header(field)) => M64ParseError::NotEnoughBytes {
+            field,
+            requires: field.size() - input.len(),
+        },
+        Some(ParseField::Reserved(offset)) => M64ParseError::ReservedNotZero(offset),
+        Some(ParseField::Eof) => M64ParseError::InputNot4BytesAligned(input.len()),
+        Some(ParseField::Input) => unknown(Some("input")),
+        None => unknown(None),
     }
 }
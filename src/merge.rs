@@ -0,0 +1,148 @@
+//! Three-way merging of movies edited from a common ancestor.
+//!
+//! The input samples of both movies are compared against the ancestor, and the edits are combined:
+//! - Edits of only one movie are applied as they are.
+//! - Edits that overlap, or insert at the same sample, are applied once if both movies made the same change,
+//!   and are reported as conflicts otherwise.
+use std::{collections::VecDeque, ops::Range};
+
+use crate::{
+    controller::Input,
+    m64::M64,
+    patch::{input_edits, InputEdit},
+};
+
+/// Overlapping edits of both movies that don't agree.
+/// - Ranges are in input samples.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MergeConflict {
+    /// The samples of the ancestor that both movies changed.
+    pub base: Range<usize>,
+    /// The samples of the merged movie, which hold our version of the change.
+    pub merged: Range<usize>,
+    /// Our version of the samples.
+    pub ours: Vec<Input>,
+    /// Their version of the samples.
+    pub theirs: Vec<Input>,
+}
+
+/// The result of a three-way merge.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Merge {
+    /// The merged movie.
+    /// - Conflicting changes hold our version.
+    pub m64: M64,
+    /// The conflicting changes, in increasing order.
+    pub conflicts: Vec<MergeConflict>,
+}
+
+impl Merge {
+    /// Returns if the movies merged without conflicts.
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+/// Merges the inputs of `ours` and `theirs`, which were both edited from `base`.
+/// - The header is taken from `ours`, with the rerecords of both edits added to the ancestor's,
+///   and `input_frames` set to the merged number of samples.
+/// - See the [module documentation](crate::merge) for how edits are combined.
+pub fn merge3(base: &M64, ours: &M64, theirs: &M64) -> Merge {
+    let mut our_edits = VecDeque::from(input_edits(&base.inputs, &ours.inputs));
+    let mut their_edits = VecDeque::from(input_edits(&base.inputs, &theirs.inputs));
+    let mut inputs = Vec::new();
+    let mut conflicts = Vec::new();
+    let mut next = 0;
+
+    loop {
+        let take_theirs = match (our_edits.front(), their_edits.front()) {
+            (Some(a), Some(b)) => b.range.start < a.range.start,
+            (Some(_), None) => false,
+            (None, Some(_)) => true,
+            (None, None) => break,
+        };
+
+        // gather every edit of either movie that overlaps the edits gathered so far
+        let (mut ours_in, mut theirs_in) = (Vec::new(), Vec::new());
+        if take_theirs {
+            theirs_in.push(their_edits.pop_front().unwrap());
+        } else {
+            ours_in.push(our_edits.pop_front().unwrap());
+        }
+        let mut range = ours_in.first().or(theirs_in.first()).unwrap().range.clone();
+        loop {
+            if let Some(edit) = our_edits
+                .front()
+                .filter(|edit| overlaps(&range, &edit.range))
+            {
+                range = range.start.min(edit.range.start)..range.end.max(edit.range.end);
+                ours_in.push(our_edits.pop_front().unwrap());
+            } else if let Some(edit) = their_edits
+                .front()
+                .filter(|edit| overlaps(&range, &edit.range))
+            {
+                range = range.start.min(edit.range.start)..range.end.max(edit.range.end);
+                theirs_in.push(their_edits.pop_front().unwrap());
+            } else {
+                break;
+            }
+        }
+
+        inputs.extend_from_slice(&base.inputs[next..range.start]);
+        next = range.end;
+        let ours_version = apply(&base.inputs, &range, &ours_in);
+        let theirs_version = apply(&base.inputs, &range, &theirs_in);
+
+        let start = inputs.len();
+        if ours_in.is_empty() {
+            inputs.extend(theirs_version);
+        } else if theirs_in.is_empty() || ours_version == theirs_version {
+            inputs.extend(ours_version);
+        } else {
+            inputs.extend_from_slice(&ours_version);
+            conflicts.push(MergeConflict {
+                base: range,
+                merged: start..inputs.len(),
+                ours: ours_version,
+                theirs: theirs_version,
+            });
+        }
+    }
+    inputs.extend_from_slice(&base.inputs[next..]);
+
+    let rerecords = ours.total_rerecords().saturating_add(
+        theirs
+            .total_rerecords()
+            .saturating_sub(base.total_rerecords()),
+    );
+    let mut m64 = M64 {
+        input_frames: inputs.len() as u32,
+        inputs,
+        ..ours.clone()
+    };
+    m64.set_total_rerecords(rerecords);
+
+    Merge { m64, conflicts }
+}
+
+/// Returns if two edit ranges of the ancestor touch the same samples, or insert at the same sample.
+/// - An insertion at the end of a range doesn't overlap it, as it's applied after the range.
+fn overlaps(a: &Range<usize>, b: &Range<usize>) -> bool {
+    a.start == b.start
+        || (a.start < b.end && b.start < a.end)
+        || (a.is_empty() && b.start < a.start && a.start < b.end)
+        || (b.is_empty() && a.start < b.start && b.start < a.end)
+}
+
+/// Applies edits that all lie within `range` to the samples of the ancestor in `range`.
+fn apply(base: &[Input], range: &Range<usize>, edits: &[InputEdit]) -> Vec<Input> {
+    let mut inputs = Vec::new();
+    let mut next = range.start;
+    for edit in edits {
+        inputs.extend_from_slice(&base[next..edit.range.start]);
+        inputs.extend_from_slice(&edit.inputs);
+        next = edit.range.end;
+    }
+    inputs.extend_from_slice(&base[next..range.end]);
+    inputs
+}
@@ -0,0 +1,140 @@
+//! The header of a movie without its inputs.
+use crate::{
+    controller::Flags,
+    extended::ExtendedHeader,
+    m64::{MovieStartType, M64},
+    padded_string::PaddedString,
+    trailer::MetadataBlock,
+};
+
+/// Every field of a movie except the inputs, see [`M64`] for what each field holds.
+/// - Created from a movie with `From`, and applied onto another one with [`M64Metadata::apply_to`].
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct M64Metadata {
+    /// Identifies the movie-savestate relationship.
+    pub uid: u32,
+    /// Number of vertical interrupt frames.
+    pub vi_frames: u32,
+    /// Number of input samples for any controllers.
+    pub input_frames: u32,
+    /// Rerecord count.
+    pub rerecords: u32,
+    /// Frames per second in vertical interrupt frames.
+    pub fps: u8,
+    /// The number of controllers.
+    pub controller_count: u8,
+    /// Movie start type.
+    pub movie_start_type: MovieStartType,
+    /// The controller flags.
+    pub controller_flags: [Flags; 4],
+    /// Internal name of the ROM used when recording.
+    pub rom_internal_name: PaddedString<32>,
+    /// CRC32 of the ROM used when recording.
+    pub rom_crc_32: u32,
+    /// Country code of the ROM used when recording.
+    pub rom_country_code: u16,
+    /// Name of the video plugin used when recording.
+    pub video_plugin: PaddedString<64>,
+    /// Name of the sound plugin used when recording.
+    pub sound_plugin: PaddedString<64>,
+    /// Name of the input plugin used when recording.
+    pub input_plugin: PaddedString<64>,
+    /// Name of the RSP plugin used when recording.
+    pub rsp_plugin: PaddedString<64>,
+    /// Author(s) of the TAS.
+    pub author: PaddedString<222>,
+    /// Description of the TAS.
+    pub description: PaddedString<256>,
+    /// The mupen64-rr-lua extended header, if the movie has one.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub extended: Option<ExtendedHeader>,
+    /// Keyed metadata stored after the input samples.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub metadata_block: MetadataBlock,
+}
+
+impl M64Metadata {
+    /// Overwrites every field of `m64` except the inputs.
+    /// - `input_frames` is overwritten too, so the counts can end up not matching the inputs of `m64`.
+    pub fn apply_to<S>(&self, m64: &mut M64<S>) {
+        m64.uid = self.uid;
+        m64.vi_frames = self.vi_frames;
+        m64.input_frames = self.input_frames;
+        m64.rerecords = self.rerecords;
+        m64.fps = self.fps;
+        m64.controller_count = self.controller_count;
+        m64.movie_start_type = self.movie_start_type;
+        m64.controller_flags = self.controller_flags;
+        m64.rom_internal_name = self.rom_internal_name;
+        m64.rom_crc_32 = self.rom_crc_32;
+        m64.rom_country_code = self.rom_country_code;
+        m64.video_plugin = self.video_plugin;
+        m64.sound_plugin = self.sound_plugin;
+        m64.input_plugin = self.input_plugin;
+        m64.rsp_plugin = self.rsp_plugin;
+        m64.author = self.author;
+        m64.description = self.description;
+        m64.extended = self.extended;
+        m64.metadata_block = self.metadata_block.clone();
+    }
+
+    /// Creates a movie with this metadata and the given inputs.
+    pub fn with_inputs<S>(&self, inputs: S) -> M64<S> {
+        M64 {
+            uid: self.uid,
+            vi_frames: self.vi_frames,
+            input_frames: self.input_frames,
+            rerecords: self.rerecords,
+            fps: self.fps,
+            controller_count: self.controller_count,
+            movie_start_type: self.movie_start_type,
+            controller_flags: self.controller_flags,
+            rom_internal_name: self.rom_internal_name,
+            rom_crc_32: self.rom_crc_32,
+            rom_country_code: self.rom_country_code,
+            video_plugin: self.video_plugin,
+            sound_plugin: self.sound_plugin,
+            input_plugin: self.input_plugin,
+            rsp_plugin: self.rsp_plugin,
+            author: self.author,
+            description: self.description,
+            extended: self.extended,
+            metadata_block: self.metadata_block.clone(),
+            inputs,
+        }
+    }
+}
+
+impl<S> From<&M64<S>> for M64Metadata {
+    fn from(m64: &M64<S>) -> Self {
+        M64Metadata {
+            uid: m64.uid,
+            vi_frames: m64.vi_frames,
+            input_frames: m64.input_frames,
+            rerecords: m64.rerecords,
+            fps: m64.fps,
+            controller_count: m64.controller_count,
+            movie_start_type: m64.movie_start_type,
+            controller_flags: m64.controller_flags,
+            rom_internal_name: m64.rom_internal_name,
+            rom_crc_32: m64.rom_crc_32,
+            rom_country_code: m64.rom_country_code,
+            video_plugin: m64.video_plugin,
+            sound_plugin: m64.sound_plugin,
+            input_plugin: m64.input_plugin,
+            rsp_plugin: m64.rsp_plugin,
+            author: m64.author,
+            description: m64.description,
+            extended: m64.extended,
+            metadata_block: m64.metadata_block.clone(),
+        }
+    }
+}
+
+impl<S> M64<S> {
+    /// Returns every field except the inputs.
+    pub fn metadata(&self) -> M64Metadata {
+        M64Metadata::from(self)
+    }
+}
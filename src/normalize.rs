@@ -0,0 +1,48 @@
+//! Bringing a movie into a canonical form.
+//!
+//! Movies that play back the same can still differ in bytes that have no effect,
+//! such as what follows the first NUL of a string field, or a lone reserved bit in an input sample.
+//! [`M64::normalize`] clears those, so the bytes of a normalized movie can be used to identify it.
+use alloc::vec::Vec;
+
+use crate::{controller::Buttons, m64::M64};
+
+impl M64 {
+    /// Brings the movie into its canonical form.
+    /// - Each string field is cut off at its first NUL and padded with NUL bytes to the full field size.
+    /// - Reserved bits are cleared from the input samples, except for samples with both set, which trigger a reset.
+    /// - `input_frames` is set to the number of input samples.
+    /// - The VI count and controller count are left as they are, as they can't be derived from the inputs.
+    pub fn normalize(&mut self) {
+        self.rom_internal_name.normalize();
+        self.video_plugin.normalize();
+        self.sound_plugin.normalize();
+        self.input_plugin.normalize();
+        self.rsp_plugin.normalize();
+        self.author.normalize();
+        self.description.normalize();
+
+        for input in &mut self.inputs {
+            if !input.is_reset() {
+                input.set_buttons(input.buttons() - Buttons::RESERVED);
+            }
+        }
+
+        self.input_frames = self.inputs.len() as u32;
+    }
+
+    /// Returns if the movie is already in its canonical form, see [`M64::normalize`].
+    pub fn is_normalized(&self) -> bool {
+        let mut normalized = self.clone();
+        normalized.normalize();
+        *self == normalized
+    }
+
+    /// Returns the bytes of the normalized movie, see [`M64::normalize`].
+    /// - Movies with the same canonical bytes are the same movie, so these can be hashed for content-addressed storage.
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        let mut normalized = self.clone();
+        normalized.normalize();
+        normalized.to_bytes()
+    }
+}
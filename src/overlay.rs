@@ -0,0 +1,73 @@
+//! Exporting inputs for input display overlays.
+//!
+//! The inputs are written as [JSON Lines](https://jsonlines.org/), one object per input frame, so overlays can stream them
+//! alongside the video of an encode.
+//!
+//! # Schema
+//! Each frame is an object with the following keys:
+//! - `frame`: index of the input frame.
+//! - `time_ms`: time from the start of the movie the frame starts at in milliseconds, see [`M64::frame_time`].
+//!   Always 0 for a movie with VI frames but no input frames, which has no input frame rate.
+//! - `controllers`: array with an object for each controller, in controller order, with:
+//!   - `buttons`: object with a boolean for each button, keyed by the names in [`OVERLAY_BUTTONS`].
+//!   - `stick`: object with the analog stick axes `x` and `y` from -128 to 127, where positive `y` is up.
+use std::io::{self, Write};
+
+use serde_json::{json, Map, Value};
+
+use crate::{
+    controller::{Buttons, Input},
+    m64::M64,
+};
+
+/// The buttons of each controller object, along with their key.
+pub const OVERLAY_BUTTONS: [(&str, Buttons); 14] = [
+    ("a", Buttons::A),
+    ("b", Buttons::B),
+    ("z", Buttons::Z),
+    ("start", Buttons::START),
+    ("l", Buttons::LEFT_SHOULDER),
+    ("r", Buttons::RIGHT_SHOULDER),
+    ("c_up", Buttons::UP_CBUTTON),
+    ("c_down", Buttons::DOWN_CBUTTON),
+    ("c_left", Buttons::LEFT_CBUTTON),
+    ("c_right", Buttons::RIGHT_CBUTTON),
+    ("dpad_up", Buttons::UP_DPAD),
+    ("dpad_down", Buttons::DOWN_DPAD),
+    ("dpad_left", Buttons::LEFT_DPAD),
+    ("dpad_right", Buttons::RIGHT_DPAD),
+];
+
+impl M64 {
+    /// Writes the inputs for input display overlays to the given writer.
+    /// - See the [module documentation](crate::overlay) for the schema.
+    pub fn write_overlay_json<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        for (index, frame) in self.enumerate_frames() {
+            let controllers = frame.iter().map(overlay_controller).collect::<Vec<_>>();
+            let line = json!({
+                "frame": index,
+                "time_ms": self.frame_time(index).as_millis() as u64,
+                "controllers": controllers,
+            });
+            writeln!(writer, "{line}")?;
+        }
+
+        Ok(())
+    }
+}
+
+fn overlay_controller(input: &Input) -> Value {
+    let pressed = input.buttons();
+    let buttons = OVERLAY_BUTTONS
+        .iter()
+        .map(|&(name, button)| (name.to_string(), Value::Bool(pressed.contains(button))))
+        .collect::<Map<_, _>>();
+
+    json!({
+        "buttons": buttons,
+        "stick": { "x": input.x_axis, "y": input.y_axis },
+    })
+}
@@ -0,0 +1,134 @@
+//! Fixed-size string fields, which are padded with NUL bytes to their full size in the file.
+use core::{fmt, ops::Deref};
+
+use arrayvec::{ArrayString, CapacityError};
+
+/// A string field of `N` bytes, padded with NUL bytes.
+/// - [`PaddedString::as_str`], `Display` and `Deref` give the string without the trailing NULs.
+/// - The full `N` bytes are always kept, including anything after the first NUL, so the field is written back as it was read.
+/// - With the `serde` feature, the string is serialized with its NUL padding, and padded again when deserialized.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "ArrayString<N>", into = "ArrayString<N>")
+)]
+pub struct PaddedString<const N: usize>(ArrayString<N>);
+
+impl<const N: usize> PaddedString<N> {
+    /// Creates a string of `N` NUL bytes.
+    pub fn zeroed() -> Self {
+        PaddedString(ArrayString::zero_filled())
+    }
+
+    /// Creates a padded string, cutting off strings too long for the field at a character boundary.
+    /// - The second value is `true` if the string was cut off.
+    pub fn truncated(s: &str) -> (Self, bool) {
+        let mut end = s.len().min(N);
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        (
+            Self::from(ArrayString::from(&s[..end]).unwrap()),
+            end < s.len(),
+        )
+    }
+
+    /// Returns the string without the trailing NUL bytes.
+    pub fn as_str(&self) -> &str {
+        self.0.trim_end_matches('\0')
+    }
+
+    /// Returns the full `N` bytes of the field, including the NUL padding.
+    pub fn as_padded_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Clears the string, leaving only the NUL padding.
+    pub fn clear(&mut self) {
+        *self = Self::zeroed();
+    }
+
+    /// Cuts off the string at its first NUL, and pads it with NUL bytes again.
+    /// - Returns if the string was changed.
+    pub fn normalize(&mut self) -> bool {
+        let before = *self;
+        if let Some(end) = self.0.find('\0') {
+            self.0.truncate(end);
+        }
+        *self = Self::from(self.0);
+        *self != before
+    }
+}
+
+impl<const N: usize> Default for PaddedString<N> {
+    /// Same as [`PaddedString::zeroed`].
+    fn default() -> Self {
+        Self::zeroed()
+    }
+}
+
+impl<const N: usize> From<ArrayString<N>> for PaddedString<N> {
+    /// Pads the string with NUL bytes to the full field size.
+    fn from(mut s: ArrayString<N>) -> Self {
+        while !s.is_full() {
+            s.push('\0');
+        }
+        PaddedString(s)
+    }
+}
+
+impl<const N: usize> From<PaddedString<N>> for ArrayString<N> {
+    /// Returns the full field, including the NUL padding.
+    fn from(s: PaddedString<N>) -> Self {
+        s.0
+    }
+}
+
+impl<'a, const N: usize> TryFrom<&'a str> for PaddedString<N> {
+    type Error = CapacityError<&'a str>;
+
+    /// Creates a padded string, failing if the string is too long for the field.
+    fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        ArrayString::from(s).map(Self::from)
+    }
+}
+
+impl<const N: usize> Deref for PaddedString<N> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<const N: usize> AsRef<str> for PaddedString<N> {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<const N: usize> PartialEq<str> for PaddedString<N> {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl<const N: usize> PartialEq<&str> for PaddedString<N> {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl<const N: usize> fmt::Display for PaddedString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl<const N: usize> fmt::Debug for PaddedString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
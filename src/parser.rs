@@ -1,43 +1,156 @@
-use arrayvec::ArrayString;
+//! The nom parsers the movie is parsed with, for embedding movies in other formats.
+//!
+//! Each parser takes the bytes at the start of its part of the file, and returns the rest along with the parsed value.
+//! Errors are [`ParseError`]s, which hold the offset and field that failed without allocating,
+//! and which [`M64::from_u8_array`] maps into a [`M64ParseError`](crate::error::M64ParseError).
 use nom::{
-    bytes::complete::*, combinator::*, error::*, multi::*, number::complete::*, sequence::*,
-    IResult,
+    bytes::complete::*,
+    combinator::*,
+    error::{ErrorKind, FromExternalError},
+    number::complete::*,
+    sequence::*,
+    IResult, Parser,
 };
 
-use crate::{controller::*, m64::*};
+use crate::{
+    controller::*,
+    error::FieldName,
+    extended::{ExtendedHeader, EXTENDED_DATA_SIZE},
+    m64::*,
+    metadata::M64Metadata,
+    padded_string::PaddedString,
+    storage::InputStorage,
+    trailer::MetadataBlock,
+};
+
+/// The result of the parsers.
+pub type ParseResult<'a, T> = IResult<&'a [u8], T, ParseError<'a>>;
+
+/// The error of the parsers.
+/// - It's built from the innermost parser that failed, and only borrows the input, so failing never allocates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseError<'a> {
+    /// The input the innermost parser failed on.
+    pub input: &'a [u8],
+    /// What the innermost parser failed with, such as [`ErrorKind::Eof`] if there weren't enough bytes.
+    pub kind: ErrorKind,
+    /// The part of the file that failed, or `None` if it failed outside of any field.
+    pub field: Option<ParseField>,
+}
+
+/// The part of the file a parser failed on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ParseField {
+    /// The file signature.
+    Signature,
+    /// A header field.
+    Header(FieldName),
+    /// Reserved bytes at the offset, which must be 0.
+    Reserved(usize),
+    /// An input sample.
+    Input,
+    /// Bytes after the input samples that don't make up a whole sample.
+    Eof,
+}
+
+impl<'a> nom::error::ParseError<&'a [u8]> for ParseError<'a> {
+    fn from_error_kind(input: &'a [u8], kind: ErrorKind) -> Self {
+        ParseError {
+            input,
+            kind,
+            field: None,
+        }
+    }
+
+    fn append(_: &'a [u8], _: ErrorKind, other: Self) -> Self {
+        other
+    }
+}
+
+impl<'a, E> FromExternalError<&'a [u8], E> for ParseError<'a> {
+    fn from_external_error(input: &'a [u8], kind: ErrorKind, _: E) -> Self {
+        nom::error::ParseError::from_error_kind(input, kind)
+    }
+}
+
+/// Size of the header in bytes, which the input samples follow.
+pub const HEADER_SIZE: usize = 0x400;
+
+/// Parses a string field of `S` bytes, keeping its NUL padding.
+pub fn string_field<'a, const S: usize>() -> impl FnMut(&'a [u8]) -> ParseResult<'a, PaddedString<S>>
+{
+    let utf8_parse = map_res(take(S), core::str::from_utf8);
 
-fn array_string<'a, const S: usize>(
-) -> impl FnMut(&'a [u8]) -> IResult<&'a [u8], ArrayString<S>, VerboseError<&'a [u8]>> {
-    let utf8_parse = map_res(take(S), std::str::from_utf8);
+    map(utf8_parse, |s| PaddedString::try_from(s).unwrap())
+}
+
+/// Parses the file signature, `M64\x1A`.
+pub fn signature(data: &[u8]) -> ParseResult<'_, &[u8]> {
+    field(ParseField::Signature, tag([0x4D, 0x36, 0x34, 0x1A]))(data)
+}
+
+/// Parses the version number, which must be 3.
+pub fn version(data: &[u8]) -> ParseResult<'_, u32> {
+    field(
+        ParseField::Header(FieldName::Version),
+        verify(le_u32, |version| *version == 3),
+    )(data)
+}
+
+/// Parses the 1024 byte header, from the signature to the description.
+/// - The [extended header](crate::extended) of mupen64-rr-lua is read from the reserved bytes it's stored in.
+pub fn header(data: &[u8]) -> ParseResult<'_, M64Metadata> {
+    header_with(data, false)
+}
 
-    map(utf8_parse, |s| ArrayString::<S>::from(s).unwrap())
+/// Parses the 1024 byte header the same as [`header`], but requires every reserved byte to be 0.
+/// - Movies with an [extended header](crate::extended) are rejected.
+pub fn strict_header(data: &[u8]) -> ParseResult<'_, M64Metadata> {
+    header_with(data, true)
 }
 
-pub fn m64_from_u8(data: &[u8]) -> IResult<(), M64, VerboseError<&[u8]>> {
+fn header_with(data: &[u8], strict: bool) -> ParseResult<'_, M64Metadata> {
     // defining parsers
-    let signature = tag([0x4D, 0x36, 0x34, 0x1A]);
     let movie_start_type = map_opt(le_u16, |value| MovieStartType::from_repr(value as usize));
     let controller_flags = map_opt(le_u32, |b| Some(Flags::from_u32(b)));
-    let input = map_opt(le_u32, |i: u32| Some(Input::from(i)));
-    let version_verify = verify(le_u32, |version| *version == 3);
     let reserved_check = |bytes: usize| verify(take(bytes), |v: &[u8]| v.iter().all(|&b| b == 0));
+    // the extended version must be set for the extended flags to be
+    let extended_version = verify(take(2usize), |v: &[u8]| {
+        if strict {
+            v == [0, 0]
+        } else {
+            v[0] != 0 || v[1] == 0
+        }
+    });
 
     // general header data
+    let (data, (_, _, uid, vi_frames, rerecords, fps, controller_count, extended_version)) =
+        tuple((
+            signature,
+            version,
+            field(ParseField::Header(FieldName::Uid), le_u32),
+            field(ParseField::Header(FieldName::ViFrames), le_u32),
+            field(ParseField::Header(FieldName::Rerecords), le_u32),
+            field(ParseField::Header(FieldName::Fps), u8),
+            field(ParseField::Header(FieldName::ControllerCount), u8),
+            field(ParseField::Reserved(0x16), extended_version),
+        ))(data)?;
+    let extended_version: [u8; 2] = extended_version.try_into().unwrap();
+    // the extended data is only read if there is an extended header
+    let extended_data = if extended_version[0] == 0 {
+        0
+    } else {
+        EXTENDED_DATA_SIZE
+    };
+
     let (
         data,
         (
-            _,
-            _,
-            uid,
-            vi_frames,
-            rerecords,
-            fps,
-            controller_count,
-            _,
             input_frames,
             movie_start_type,
             _,
             controller_flags,
+            extended_data,
             _,
             rom_internal_name,
             rom_crc_32,
@@ -49,47 +162,68 @@ pub fn m64_from_u8(data: &[u8]) -> IResult<(), M64, VerboseError<&[u8]>> {
             rsp_plugin,
         ),
     ) = tuple((
-        context("signature", signature),
-        context("version", version_verify),
-        context("uid", le_u32),
-        context("vi_frames", le_u32),
-        context("rerecords", le_u32),
-        context("fps", u8),
-        context("controller_count", u8),
-        context("reserved_0x16", reserved_check(2)),
-        context("input_frames", le_u32),
-        context("movie_start_type", movie_start_type),
-        context("reserved_0x1E", reserved_check(2)),
-        context("controller_flags", controller_flags),
-        context("reserved_0x24", reserved_check(160)),
-        context("rom_internal_name", array_string::<32>()),
-        context("rom_crc_32", le_u32),
-        context("rom_country_code", le_u16),
-        context("reserved_0xEA", reserved_check(56)),
-        context("video_plugin", array_string::<64>()),
-        context("sound_plugin", array_string::<64>()),
-        context("input_plugin", array_string::<64>()),
-        context("rsp_plugin", array_string::<64>()),
+        field(ParseField::Header(FieldName::InputFrames), le_u32),
+        field(
+            ParseField::Header(FieldName::MovieStartType),
+            movie_start_type,
+        ),
+        field(ParseField::Reserved(0x1E), reserved_check(2)),
+        field(
+            ParseField::Header(FieldName::ControllerFlags),
+            controller_flags,
+        ),
+        field(ParseField::Reserved(0x24), take(extended_data)),
+        field(
+            ParseField::Reserved(0x24),
+            reserved_check(160 - extended_data),
+        ),
+        field(
+            ParseField::Header(FieldName::RomInternalName),
+            string_field::<32>(),
+        ),
+        field(ParseField::Header(FieldName::RomCrc32), le_u32),
+        field(ParseField::Header(FieldName::RomCountryCode), le_u16),
+        field(ParseField::Reserved(0xEA), reserved_check(56)),
+        field(
+            ParseField::Header(FieldName::VideoPlugin),
+            string_field::<64>(),
+        ),
+        field(
+            ParseField::Header(FieldName::SoundPlugin),
+            string_field::<64>(),
+        ),
+        field(
+            ParseField::Header(FieldName::InputPlugin),
+            string_field::<64>(),
+        ),
+        field(
+            ParseField::Header(FieldName::RspPlugin),
+            string_field::<64>(),
+        ),
     ))(data)?;
+    let extended = extended_data
+        .try_into()
+        .ok()
+        .and_then(|data| ExtendedHeader::from_bytes(extended_version, data));
 
     // TAS author info
     let (data, (author, description)) = tuple((
-        context("author", array_string::<222>()),
-        context("description", array_string::<256>()),
+        field(ParseField::Header(FieldName::Author), string_field::<222>()),
+        field(
+            ParseField::Header(FieldName::Description),
+            string_field::<256>(),
+        ),
     ))(data)?;
 
-    // getting input data
-    let (_, (inputs, _)) = tuple((many0(input), context("eof", eof)))(data)?;
-
     Ok((
-        (),
-        M64 {
+        data,
+        M64Metadata {
             uid,
             vi_frames,
+            input_frames,
             rerecords,
             fps,
             controller_count,
-            input_frames,
             movie_start_type,
             controller_flags,
             rom_internal_name,
@@ -101,7 +235,51 @@ pub fn m64_from_u8(data: &[u8]) -> IResult<(), M64, VerboseError<&[u8]>> {
             rsp_plugin,
             author,
             description,
-            inputs,
+            extended,
+            metadata_block: MetadataBlock::default(),
         },
     ))
 }
+
+/// Parses a single 4 byte input sample.
+pub fn input(data: &[u8]) -> ParseResult<'_, Input> {
+    field(ParseField::Input, map(le_u32, Input::from))(data)
+}
+
+/// Parses a whole movie, which must take up all of `data`.
+/// - `strict` parses the header with [`strict_header`] instead of [`header`], and reads a [`MetadataBlock`] as input samples.
+pub(crate) fn m64_from_u8<S>(data: &[u8], strict: bool) -> IResult<(), M64<S>, ParseError<'_>>
+where
+    S: InputStorage,
+{
+    let (data, mut header) = header_with(data, strict)?;
+    let data = match MetadataBlock::split_from_inputs(data, header.input_frames) {
+        Some((data, block)) if !strict => {
+            header.metadata_block = block;
+            data
+        }
+        _ => data,
+    };
+
+    // getting input data, every sample is an independent 4 byte word
+    let (data, rest) = data.split_at(data.len() - data.len() % 4);
+    field(ParseField::Eof, eof)(rest)?;
+    let inputs = S::from_le_bytes(data, header.input_frames);
+
+    Ok(((), header.with_inputs(inputs)))
+}
+
+/// Sets the field of the errors of `parser` that don't have one yet.
+fn field<'a, O, F>(field: ParseField, mut parser: F) -> impl FnMut(&'a [u8]) -> ParseResult<'a, O>
+where
+    F: Parser<&'a [u8], O, ParseError<'a>>,
+{
+    move |input| {
+        parser.parse(input).map_err(|err| {
+            err.map(|err| ParseError {
+                field: err.field.or(Some(field)),
+                ..err
+            })
+        })
+    }
+}
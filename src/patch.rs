@@ -0,0 +1,256 @@
+//! Compact binary patches between two movies.
+//!
+//! A patch holds the header bytes that changed and the edits to the input samples,
+//! so sharing a new revision of a movie only takes the size of what changed.
+//!
+//! # Patch file
+//! All values are little endian.
+//! | Offset | Size | Description                                    |
+//! |--------|------|------------------------------------------------|
+//! | 0x00   | 4    | Signature `M64P`                               |
+//! | 0x04   | 1    | Version, currently 1                           |
+//! | 0x05   | 4    | CRC32 of the movie the patch applies to        |
+//! | 0x09   | 4    | CRC32 of the patched movie                     |
+//! | 0x0D   | 2    | Number of header changes                       |
+//! | ...    | ...  | Header changes                                 |
+//! | ...    | 4    | Number of input edits                          |
+//! | ...    | ...  | Input edits                                    |
+//!
+//! Each header change is a 2 byte offset into the header, a 2 byte length and the new bytes.
+//!
+//! Each input edit is the 4 byte index of the first sample, the 4 byte number of samples removed there,
+//! the 4 byte number of samples inserted in their place and the inserted samples as stored in the `.m64` file.
+//! Sample indices refer to the original movie and edits are in increasing order.
+use std::ops::Range;
+
+use crate::{
+    controller::{decode_inputs, encode_inputs, Input},
+    diff::{diff, DiffOp},
+    error::PatchError,
+    m64::M64,
+    parser::HEADER_SIZE,
+};
+
+/// Patch format version written by this crate.
+pub const PATCH_VERSION: u8 = 1;
+
+const SIGNATURE: &[u8; 4] = b"M64P";
+
+/// Changes that turn one movie into another.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Patch {
+    /// CRC32 of the movie the patch applies to.
+    pub source_crc32: u32,
+    /// CRC32 of the movie after applying the patch.
+    pub target_crc32: u32,
+    /// Changed header bytes.
+    pub header: Vec<HeaderChange>,
+    /// Input sample edits, in increasing order.
+    pub inputs: Vec<InputEdit>,
+}
+
+/// A run of changed header bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeaderChange {
+    /// Offset into the header.
+    pub offset: u16,
+    /// The new bytes.
+    pub bytes: Vec<u8>,
+}
+
+/// Replaces a range of input samples of the original movie.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputEdit {
+    /// Samples of the original movie that are removed.
+    pub range: Range<usize>,
+    /// Samples inserted in their place.
+    pub inputs: Vec<Input>,
+}
+
+impl M64 {
+    /// Creates a patch that turns `self` into `other`.
+    pub fn create_patch(&self, other: &M64) -> Patch {
+        let source = self.to_bytes();
+        let target = other.to_bytes();
+
+        let mut header = Vec::new();
+        let mut offset = 0;
+        while offset < HEADER_SIZE {
+            if source[offset] == target[offset] {
+                offset += 1;
+                continue;
+            }
+            let start = offset;
+            while offset < HEADER_SIZE && source[offset] != target[offset] {
+                offset += 1;
+            }
+            header.push(HeaderChange {
+                offset: start as u16,
+                bytes: target[start..offset].to_vec(),
+            });
+        }
+
+        let inputs = input_edits(&self.inputs, &other.inputs);
+
+        Patch {
+            source_crc32: crc32fast::hash(&source),
+            target_crc32: crc32fast::hash(&target),
+            header,
+            inputs,
+        }
+    }
+
+    /// Applies `patch`, returning the patched movie.
+    /// - The movie has to be the one the patch was created from.
+    pub fn apply_patch(&self, patch: &Patch) -> Result<M64, PatchError> {
+        let source = self.to_bytes();
+        if crc32fast::hash(&source) != patch.source_crc32 {
+            return Err(PatchError::SourceMismatch);
+        }
+
+        let mut bytes = source[..HEADER_SIZE].to_vec();
+        for change in &patch.header {
+            let start = change.offset as usize;
+            bytes
+                .get_mut(start..start + change.bytes.len())
+                .ok_or(PatchError::InvalidEdit)?
+                .copy_from_slice(&change.bytes);
+        }
+
+        let mut inputs = Vec::with_capacity(self.inputs.len());
+        let mut next = 0;
+        for edit in &patch.inputs {
+            if edit.range.start < next
+                || edit.range.start > edit.range.end
+                || edit.range.end > self.inputs.len()
+            {
+                return Err(PatchError::InvalidEdit);
+            }
+            inputs.extend_from_slice(&self.inputs[next..edit.range.start]);
+            inputs.extend_from_slice(&edit.inputs);
+            next = edit.range.end;
+        }
+        inputs.extend_from_slice(&self.inputs[next..]);
+        bytes.extend(encode_inputs(&inputs));
+
+        if crc32fast::hash(&bytes) != patch.target_crc32 {
+            return Err(PatchError::TargetMismatch);
+        }
+        Ok(M64::from_u8_array(&bytes)?)
+    }
+}
+
+impl Patch {
+    /// Returns the patch file bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = SIGNATURE.to_vec();
+        bytes.push(PATCH_VERSION);
+        bytes.extend(self.source_crc32.to_le_bytes());
+        bytes.extend(self.target_crc32.to_le_bytes());
+
+        bytes.extend((self.header.len() as u16).to_le_bytes());
+        for change in &self.header {
+            bytes.extend(change.offset.to_le_bytes());
+            bytes.extend((change.bytes.len() as u16).to_le_bytes());
+            bytes.extend_from_slice(&change.bytes);
+        }
+
+        bytes.extend((self.inputs.len() as u32).to_le_bytes());
+        for edit in &self.inputs {
+            bytes.extend((edit.range.start as u32).to_le_bytes());
+            bytes.extend((edit.range.len() as u32).to_le_bytes());
+            bytes.extend((edit.inputs.len() as u32).to_le_bytes());
+            bytes.extend(encode_inputs(&edit.inputs));
+        }
+
+        bytes
+    }
+
+    /// Parses a patch file.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, PatchError> {
+        let mut reader = Reader(bytes);
+        if reader.take(4)? != SIGNATURE {
+            return Err(PatchError::InvalidSignature);
+        }
+        let version = reader.take(1)?[0];
+        if version != PATCH_VERSION {
+            return Err(PatchError::UnsupportedVersion(version));
+        }
+        let source_crc32 = reader.u32()?;
+        let target_crc32 = reader.u32()?;
+
+        let header = (0..reader.u16()?)
+            .map(|_| {
+                let offset = reader.u16()?;
+                let len = reader.u16()? as usize;
+                Ok(HeaderChange {
+                    offset,
+                    bytes: reader.take(len)?.to_vec(),
+                })
+            })
+            .collect::<Result<_, PatchError>>()?;
+
+        let inputs = (0..reader.u32()?)
+            .map(|_| {
+                let start = reader.u32()? as usize;
+                let removed = reader.u32()? as usize;
+                let inserted = reader.u32()? as usize;
+                let samples = inserted.checked_mul(4).ok_or(PatchError::UnexpectedEof)?;
+                Ok(InputEdit {
+                    range: start..start + removed,
+                    inputs: decode_inputs(reader.take(samples)?),
+                })
+            })
+            .collect::<Result<_, PatchError>>()?;
+
+        if !reader.0.is_empty() {
+            return Err(PatchError::TrailingData(reader.0.len()));
+        }
+
+        Ok(Self {
+            source_crc32,
+            target_crc32,
+            header,
+            inputs,
+        })
+    }
+}
+
+/// Returns the edits that turn the input samples `a` into `b`, in increasing order.
+pub(crate) fn input_edits(a: &[Input], b: &[Input]) -> Vec<InputEdit> {
+    diff(0, a, b)
+        .into_iter()
+        .map(|op| {
+            let (range, inserted) = match op {
+                DiffOp::Insert { at, other, .. } => (at..at, other),
+                DiffOp::Delete { range, .. } => (range, 0..0),
+                DiffOp::Replace { range, other, .. } => (range, other),
+            };
+            InputEdit {
+                range,
+                inputs: b[inserted].to_vec(),
+            }
+        })
+        .collect()
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], PatchError> {
+        if self.0.len() < len {
+            return Err(PatchError::UnexpectedEof);
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn u16(&mut self) -> Result<u16, PatchError> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, PatchError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+}
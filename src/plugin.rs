@@ -0,0 +1,158 @@
+//! Normalizing plugin names into known plugin identities.
+//!
+//! Plugin names are free form strings reported by each plugin, such as `Jabo's Direct3D8 1.6` or `glN64 v0.4.1`.
+//! They are matched case insensitively against the start of the names in [`KNOWN_PLUGINS`],
+//! and the version is taken from the first word after it that starts with a digit, with an optional leading `v`.
+use crate::m64::M64;
+
+/// The kinds of plugins a movie records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PluginKind {
+    /// Graphics plugin.
+    Video,
+    /// Audio plugin.
+    Sound,
+    /// Controller plugin.
+    Input,
+    /// Signal processor plugin.
+    Rsp,
+}
+
+/// Plugins known by this crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Plugin {
+    /// Jabo's Direct3D8.
+    JaboDirect3D8,
+    /// Jabo's Direct3D6.
+    JaboDirect3D6,
+    /// Glide64 Mk2.
+    Glide64Mk2,
+    /// Glide64.
+    Glide64,
+    /// GLideN64.
+    GlideN64,
+    /// glN64.
+    GlN64,
+    /// Rice's Video Plugin.
+    Rice,
+    /// angrylion's RDP.
+    Angrylion,
+    /// Jabo's DirectSound.
+    JaboDirectSound,
+    /// Azimer's Audio.
+    AziAudio,
+    /// No Sound.
+    NoSound,
+    /// TAS Input Plugin.
+    TasInput,
+    /// N-Rage's Direct-Input.
+    NRage,
+    /// RSP emulation Plugin.
+    RspEmulation,
+    /// Zilmar's RSP.
+    ZilmarRsp,
+    /// A plugin that isn't known.
+    Unknown,
+}
+
+impl Plugin {
+    /// Returns the kind of the plugin, or `None` if it's unknown.
+    pub fn kind(self) -> Option<PluginKind> {
+        let kind = match self {
+            Plugin::JaboDirect3D8
+            | Plugin::JaboDirect3D6
+            | Plugin::Glide64Mk2
+            | Plugin::Glide64
+            | Plugin::GlideN64
+            | Plugin::GlN64
+            | Plugin::Rice
+            | Plugin::Angrylion => PluginKind::Video,
+            Plugin::JaboDirectSound | Plugin::AziAudio | Plugin::NoSound => PluginKind::Sound,
+            Plugin::TasInput | Plugin::NRage => PluginKind::Input,
+            Plugin::RspEmulation | Plugin::ZilmarRsp => PluginKind::Rsp,
+            Plugin::Unknown => return None,
+        };
+        Some(kind)
+    }
+}
+
+/// Name prefixes of the known plugins, in the order they are matched.
+pub const KNOWN_PLUGINS: [(&str, Plugin); 15] = [
+    ("Jabo's Direct3D8", Plugin::JaboDirect3D8),
+    ("Jabo's Direct3D6", Plugin::JaboDirect3D6),
+    ("Glide64mk2", Plugin::Glide64Mk2),
+    ("Glide64", Plugin::Glide64),
+    ("GLideN64", Plugin::GlideN64),
+    ("glN64", Plugin::GlN64),
+    ("Rice", Plugin::Rice),
+    ("angrylion", Plugin::Angrylion),
+    ("Jabo's DirectSound", Plugin::JaboDirectSound),
+    ("Azimer", Plugin::AziAudio),
+    ("No Sound", Plugin::NoSound),
+    ("TAS Input Plugin", Plugin::TasInput),
+    ("N-Rage", Plugin::NRage),
+    ("RSP emulation Plugin", Plugin::RspEmulation),
+    ("Zilmar", Plugin::ZilmarRsp),
+];
+
+/// The identity of a plugin from its name.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PluginIdentity {
+    /// The plugin, or [`Plugin::Unknown`] if the name isn't known.
+    pub plugin: Plugin,
+    /// The version, if the name holds one.
+    pub version: Option<String>,
+    /// The name without padding.
+    pub name: String,
+}
+
+impl PluginIdentity {
+    /// Identifies a plugin from its name.
+    /// - See the [module documentation](crate::plugin) for how names are matched.
+    pub fn from_name(name: &str) -> Self {
+        let name = name.trim_end_matches('\0').trim();
+        let known = KNOWN_PLUGINS.iter().find(|(prefix, _)| {
+            name.get(..prefix.len())
+                .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+        });
+
+        let rest = known.map_or(name, |(prefix, _)| &name[prefix.len()..]);
+        let version = rest.split_whitespace().find_map(|word| {
+            let version = word.strip_prefix(['v', 'V']).unwrap_or(word);
+            version
+                .starts_with(|ch: char| ch.is_ascii_digit())
+                .then(|| version.to_string())
+        });
+
+        PluginIdentity {
+            plugin: known.map_or(Plugin::Unknown, |(_, plugin)| *plugin),
+            version,
+            name: name.to_string(),
+        }
+    }
+}
+
+/// The identities of the plugins used by a movie.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Plugins {
+    /// Graphics plugin.
+    pub video: PluginIdentity,
+    /// Audio plugin.
+    pub sound: PluginIdentity,
+    /// Controller plugin.
+    pub input: PluginIdentity,
+    /// Signal processor plugin.
+    pub rsp: PluginIdentity,
+}
+
+impl M64 {
+    /// Returns the identities of the plugins used when recording.
+    pub fn plugins(&self) -> Plugins {
+        Plugins {
+            video: PluginIdentity::from_name(&self.video_plugin),
+            sound: PluginIdentity::from_name(&self.sound_plugin),
+            input: PluginIdentity::from_name(&self.input_plugin),
+            rsp: PluginIdentity::from_name(&self.rsp_plugin),
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//! Projects holding multiple branches of inputs for the same movie, like the branches of TAStudio.
+use std::collections::BTreeMap;
+
+use crate::{controller::Input, diff::DiffOp, error::ProjectError, m64::M64};
+
+/// Name of the branch a project starts with.
+pub const DEFAULT_BRANCH: &str = "main";
+
+/// Multiple branches of inputs sharing a common header.
+#[derive(Debug, Clone, PartialEq)]
+pub struct M64Project {
+    header: M64,
+    branches: BTreeMap<String, Vec<Input>>,
+    current: String,
+}
+
+impl M64Project {
+    /// Creates a project from a movie, whose inputs become the [`DEFAULT_BRANCH`].
+    pub fn new(mut m64: M64) -> Self {
+        let inputs = std::mem::take(&mut m64.inputs);
+        Self {
+            header: m64,
+            branches: BTreeMap::from([(DEFAULT_BRANCH.to_string(), inputs)]),
+            current: DEFAULT_BRANCH.to_string(),
+        }
+    }
+
+    /// Returns the header shared by every branch.
+    /// - Its inputs are always empty, and `input_frames` is set for each branch when exporting.
+    pub fn header(&self) -> &M64 {
+        &self.header
+    }
+
+    /// Returns the header shared by every branch for editing.
+    /// - Inputs added to the header are ignored, use [`M64Project::inputs_mut`] instead.
+    pub fn header_mut(&mut self) -> &mut M64 {
+        &mut self.header
+    }
+
+    /// Returns the name of the current branch.
+    pub fn current_branch(&self) -> &str {
+        &self.current
+    }
+
+    /// Returns the names of every branch in alphabetical order.
+    pub fn branch_names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.branches.keys().map(String::as_str)
+    }
+
+    /// Returns the inputs of the current branch.
+    pub fn inputs(&self) -> &[Input] {
+        &self.branches[&self.current]
+    }
+
+    /// Returns the inputs of the current branch for editing.
+    pub fn inputs_mut(&mut self) -> &mut Vec<Input> {
+        self.branches.get_mut(&self.current).unwrap()
+    }
+
+    /// Returns the inputs of a branch.
+    pub fn branch(&self, name: &str) -> Result<&[Input], ProjectError> {
+        self.branches
+            .get(name)
+            .map(Vec::as_slice)
+            .ok_or_else(|| ProjectError::NoSuchBranch(name.to_string()))
+    }
+
+    /// Creates a branch holding a copy of the current branch's inputs, without switching to it.
+    pub fn create_branch(&mut self, name: &str) -> Result<(), ProjectError> {
+        if self.branches.contains_key(name) {
+            return Err(ProjectError::BranchExists(name.to_string()));
+        }
+        let inputs = self.inputs().to_vec();
+        self.branches.insert(name.to_string(), inputs);
+        Ok(())
+    }
+
+    /// Makes another branch the current branch.
+    pub fn switch_branch(&mut self, name: &str) -> Result<(), ProjectError> {
+        self.branch(name)?;
+        self.current = name.to_string();
+        Ok(())
+    }
+
+    /// Deletes a branch, returning its inputs.
+    /// - The current branch can't be deleted.
+    pub fn delete_branch(&mut self, name: &str) -> Result<Vec<Input>, ProjectError> {
+        if name == self.current {
+            return Err(ProjectError::CurrentBranch(name.to_string()));
+        }
+        self.branches
+            .remove(name)
+            .ok_or_else(|| ProjectError::NoSuchBranch(name.to_string()))
+    }
+
+    /// Returns the edits that turn the inputs of branch `from` into the inputs of branch `to`, see [`M64::diff_inputs`].
+    pub fn diff_branches(&self, from: &str, to: &str) -> Result<Vec<DiffOp>, ProjectError> {
+        Ok(self.export(from)?.diff_inputs(&self.export(to)?))
+    }
+
+    /// Returns a branch as a standalone movie, with `input_frames` set to its number of input samples.
+    pub fn export(&self, name: &str) -> Result<M64, ProjectError> {
+        let inputs = self.branch(name)?.to_vec();
+        Ok(M64 {
+            input_frames: inputs.len() as u32,
+            inputs,
+            ..self.header.clone()
+        })
+    }
+
+    /// Returns the current branch as a standalone movie, see [`M64Project::export`].
+    pub fn export_current(&self) -> M64 {
+        self.export(&self.current).unwrap()
+    }
+}
@@ -0,0 +1,377 @@
+//! Python bindings, built as the `mupen64_movie_parser` module.
+//!
+//! The module is built as an extension with [maturin](https://www.maturin.rs/),
+//! by enabling the `python` and `pyo3/extension-module` features.
+//! ```python
+//! from mupen64_movie_parser import M64
+//!
+//! m64 = M64.read("movie.m64")
+//! print(m64.author, m64.rerecords)
+//! for input in m64.inputs[:10]:
+//!     print(input.buttons, input.x, input.y)
+//! ```
+use std::fs;
+
+use pyo3::{
+    exceptions::{PyIndexError, PyValueError},
+    prelude::*,
+    types::{PyBytes, PyDict},
+};
+
+use crate::{
+    controller::{Buttons, Input},
+    diff::DiffOp,
+    m64::{MovieStartType, M64},
+    padded_string::PaddedString,
+};
+
+/// A single input sample.
+#[pyclass(name = "Input", eq, frozen)]
+#[derive(Clone, PartialEq)]
+pub struct PyInput(Input);
+
+#[pymethods]
+impl PyInput {
+    /// Creates an input from the raw value stored in the file.
+    #[new]
+    #[pyo3(signature = (raw = 0))]
+    fn new(raw: u32) -> Self {
+        Self(Input::from(raw))
+    }
+
+    /// The raw value stored in the file.
+    #[getter]
+    fn raw(&self) -> u32 {
+        self.0.into()
+    }
+
+    /// Names of the pressed buttons.
+    #[getter]
+    fn buttons(&self) -> Vec<&'static str> {
+        self.0
+            .buttons()
+            .iter_names()
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    /// Analog stick X axis.
+    #[getter]
+    fn x(&self) -> i8 {
+        self.0.x_axis
+    }
+
+    /// Analog stick Y axis.
+    #[getter]
+    fn y(&self) -> i8 {
+        self.0.y_axis
+    }
+
+    /// Returns `True` if the button with the given name is pressed.
+    fn pressed(&self, button: &str) -> PyResult<bool> {
+        let button = Buttons::from_name(button)
+            .ok_or_else(|| PyValueError::new_err(format!("unknown button {button}")))?;
+        Ok(self.0.buttons().contains(button))
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Input(buttons={:?}, x={}, y={})",
+            self.buttons(),
+            self.0.x_axis,
+            self.0.y_axis
+        )
+    }
+}
+
+/// A Mupen64-rerecording movie.
+#[pyclass(name = "M64")]
+pub struct PyM64(M64);
+
+#[pymethods]
+impl PyM64 {
+    /// Creates an empty movie.
+    #[new]
+    fn new() -> Self {
+        Self(M64::default())
+    }
+
+    /// Parses a movie from bytes.
+    #[staticmethod]
+    fn from_bytes(data: &[u8]) -> PyResult<Self> {
+        M64::from_u8_array(data)
+            .map(Self)
+            .map_err(|err| PyValueError::new_err(err.to_string()))
+    }
+
+    /// Reads a movie from a file.
+    #[staticmethod]
+    fn read(path: &str) -> PyResult<Self> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+
+    /// Returns the movie as bytes.
+    fn to_bytes<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        let mut bytes = Vec::new();
+        self.0.write_m64(&mut bytes)?;
+        Ok(PyBytes::new(py, &bytes))
+    }
+
+    /// Writes the movie to a file.
+    fn write(&self, path: &str) -> PyResult<()> {
+        let mut bytes = Vec::new();
+        self.0.write_m64(&mut bytes)?;
+        Ok(fs::write(path, bytes)?)
+    }
+
+    #[getter]
+    fn uid(&self) -> u32 {
+        self.0.uid
+    }
+
+    #[setter]
+    fn set_uid(&mut self, uid: u32) {
+        self.0.uid = uid;
+    }
+
+    #[getter]
+    fn vi_frames(&self) -> u32 {
+        self.0.vi_frames
+    }
+
+    #[setter]
+    fn set_vi_frames(&mut self, vi_frames: u32) {
+        self.0.vi_frames = vi_frames;
+    }
+
+    #[getter]
+    fn input_frames(&self) -> u32 {
+        self.0.input_frames
+    }
+
+    #[setter]
+    fn set_input_frames(&mut self, input_frames: u32) {
+        self.0.input_frames = input_frames;
+    }
+
+    #[getter]
+    fn rerecords(&self) -> u32 {
+        self.0.rerecords
+    }
+
+    #[setter]
+    fn set_rerecords(&mut self, rerecords: u32) {
+        self.0.rerecords = rerecords;
+    }
+
+    #[getter]
+    fn fps(&self) -> u8 {
+        self.0.fps
+    }
+
+    #[setter]
+    fn set_fps(&mut self, fps: u8) {
+        self.0.fps = fps;
+    }
+
+    #[getter]
+    fn controller_count(&self) -> u8 {
+        self.0.controller_count
+    }
+
+    /// Movie start type, one of `"snapshot"`, `"power_on"`, `"eeprom"` or `"existing_snapshot"`.
+    #[getter]
+    fn movie_start_type(&self) -> &'static str {
+        match self.0.movie_start_type {
+            MovieStartType::SnapShot => "snapshot",
+            MovieStartType::PowerOn => "power_on",
+            MovieStartType::Eeprom => "eeprom",
+            MovieStartType::ExistingSnapshot => "existing_snapshot",
+        }
+    }
+
+    #[getter]
+    fn rom_internal_name(&self) -> &str {
+        self.0.rom_internal_name.as_str()
+    }
+
+    #[setter]
+    fn set_rom_internal_name(&mut self, name: &str) -> PyResult<()> {
+        self.0.rom_internal_name = padded(name)?;
+        Ok(())
+    }
+
+    #[getter]
+    fn rom_crc_32(&self) -> u32 {
+        self.0.rom_crc_32
+    }
+
+    #[getter]
+    fn rom_country_code(&self) -> u16 {
+        self.0.rom_country_code
+    }
+
+    #[getter]
+    fn video_plugin(&self) -> &str {
+        self.0.video_plugin.as_str()
+    }
+
+    #[getter]
+    fn sound_plugin(&self) -> &str {
+        self.0.sound_plugin.as_str()
+    }
+
+    #[getter]
+    fn input_plugin(&self) -> &str {
+        self.0.input_plugin.as_str()
+    }
+
+    #[getter]
+    fn rsp_plugin(&self) -> &str {
+        self.0.rsp_plugin.as_str()
+    }
+
+    #[getter]
+    fn author(&self) -> &str {
+        self.0.author.as_str()
+    }
+
+    #[setter]
+    fn set_author(&mut self, author: &str) -> PyResult<()> {
+        self.0.author = padded(author)?;
+        Ok(())
+    }
+
+    #[getter]
+    fn description(&self) -> &str {
+        self.0.description.as_str()
+    }
+
+    #[setter]
+    fn set_description(&mut self, description: &str) -> PyResult<()> {
+        self.0.description = padded(description)?;
+        Ok(())
+    }
+
+    /// All input samples, interleaved between controllers.
+    #[getter]
+    fn inputs(&self) -> Vec<PyInput> {
+        self.0.inputs.iter().copied().map(PyInput).collect()
+    }
+
+    /// The input samples of a single controller.
+    fn controller_inputs(&self, controller: usize) -> Vec<PyInput> {
+        self.0
+            .inputs_for_controller(controller)
+            .map(PyInput)
+            .collect()
+    }
+
+    /// Replaces the input sample at the given index.
+    fn set_input(&mut self, index: usize, input: &PyInput) -> PyResult<()> {
+        let sample = self
+            .0
+            .inputs
+            .get_mut(index)
+            .ok_or_else(|| PyIndexError::new_err("input index out of range"))?;
+        *sample = input.0;
+        Ok(())
+    }
+
+    /// Appends an input sample, increasing `input_frames` by 1.
+    fn push_input(&mut self, input: &PyInput) {
+        self.0.extend([input.0]);
+    }
+
+    /// Returns the input statistics of each controller as dictionaries.
+    fn stats<'py>(&self, py: Python<'py>) -> PyResult<Vec<Bound<'py, PyDict>>> {
+        self.0
+            .stats()
+            .into_iter()
+            .map(|stats| {
+                let buttons = PyDict::new(py);
+                for button in &stats.buttons {
+                    let Some((name, _)) = button.button.iter_names().next() else {
+                        continue;
+                    };
+                    let button_stats = PyDict::new(py);
+                    button_stats.set_item("presses", button.presses)?;
+                    button_stats.set_item("held_frames", button.held_frames)?;
+                    buttons.set_item(name, button_stats)?;
+                }
+
+                let dict = PyDict::new(py);
+                dict.set_item("frames", stats.frames)?;
+                dict.set_item("total_presses", stats.total_presses)?;
+                dict.set_item("presses_per_second", stats.presses_per_second)?;
+                dict.set_item("stick_histogram", stats.stick_histogram)?;
+                dict.set_item("buttons", buttons)?;
+                Ok(dict)
+            })
+            .collect()
+    }
+
+    /// Returns the edits that turn the inputs of this movie into the inputs of `other`.
+    /// - Each edit is a tuple of the kind, the controller, the frame range of this movie and the frame range of `other`,
+    ///   with ranges as `(start, end)` tuples.
+    #[allow(clippy::type_complexity)]
+    fn diff(&self, other: &PyM64) -> Vec<(&'static str, usize, (usize, usize), (usize, usize))> {
+        self.0
+            .diff_inputs(&other.0)
+            .into_iter()
+            .map(|op| match op {
+                DiffOp::Insert {
+                    controller,
+                    at,
+                    other,
+                } => ("insert", controller, (at, at), (other.start, other.end)),
+                DiffOp::Delete { controller, range } => (
+                    "delete",
+                    controller,
+                    (range.start, range.end),
+                    (range.start, range.start),
+                ),
+                DiffOp::Replace {
+                    controller,
+                    range,
+                    other,
+                } => (
+                    "replace",
+                    controller,
+                    (range.start, range.end),
+                    (other.start, other.end),
+                ),
+            })
+            .collect()
+    }
+
+    fn __len__(&self) -> usize {
+        self.0.inputs.len()
+    }
+
+    fn __getitem__(&self, index: usize) -> PyResult<PyInput> {
+        self.0
+            .inputs
+            .get(index)
+            .copied()
+            .map(PyInput)
+            .ok_or_else(|| PyIndexError::new_err("input index out of range"))
+    }
+}
+
+fn padded<const N: usize>(s: &str) -> PyResult<PaddedString<N>> {
+    match PaddedString::truncated(s) {
+        (_, true) => Err(PyValueError::new_err(format!(
+            "string is longer than {N} bytes"
+        ))),
+        (s, false) => Ok(s),
+    }
+}
+
+/// The `mupen64_movie_parser` Python module.
+#[pymodule]
+fn mupen64_movie_parser(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyM64>()?;
+    m.add_class::<PyInput>()?;
+    Ok(())
+}
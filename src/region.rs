@@ -0,0 +1,256 @@
+//! ROM country codes and the video regions they belong to.
+use alloc::{format, string::String};
+use core::time::Duration;
+
+use crate::m64::M64;
+
+/// The video standard of a region.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Region {
+    /// NTSC, used in North America and Japan.
+    Ntsc,
+    /// PAL, used in most of Europe and Australia.
+    Pal,
+    /// PAL-M, used in Brazil.
+    Mpal,
+}
+
+impl Region {
+    /// Returns the nominal number of VIs per second.
+    pub fn vi_rate(self) -> u32 {
+        match self {
+            Region::Ntsc | Region::Mpal => 60,
+            Region::Pal => 50,
+        }
+    }
+
+    /// Returns the actual number of VIs per second, such as 59.94 for NTSC.
+    /// - Derived from the VI clock and the length of a field set up by the standard video modes of each region.
+    pub fn vi_rate_exact(self) -> f64 {
+        // (VI clock in Hz, line length in VI clocks, lines per field)
+        let (clock, line, lines) = match self {
+            Region::Ntsc => (48_681_812.0, 3094.0, 262.5),
+            Region::Pal => (49_656_530.0, 3178.0, 312.5),
+            Region::Mpal => (48_628_316.0, 3091.0, 262.5),
+        };
+        clock / (line * lines)
+    }
+}
+
+/// The VI rate of a movie.
+/// - The header only stores the rate rounded to a whole number in the `fps` byte, such as 60 for the 59.94 VIs per second of NTSC.
+///   Timing calculations should use [`VerticalRate::exact`] instead, as the stored rate is off by about 0.1%.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VerticalRate {
+    stored: u8,
+    region: Region,
+}
+
+impl VerticalRate {
+    /// Creates the VI rate from the stored `fps` byte and the region the movie runs in.
+    pub fn new(stored: u8, region: Region) -> Self {
+        VerticalRate { stored, region }
+    }
+
+    /// Returns the `fps` byte stored in the header.
+    pub fn stored(self) -> u8 {
+        self.stored
+    }
+
+    /// Returns the region the rate comes from.
+    pub fn region(self) -> Region {
+        self.region
+    }
+
+    /// Returns the nominal number of VIs per second of the region, see [`Region::vi_rate`].
+    pub fn nominal(self) -> u32 {
+        self.region.vi_rate()
+    }
+
+    /// Returns the actual number of VIs per second of the region, see [`Region::vi_rate_exact`].
+    pub fn exact(self) -> f64 {
+        self.region.vi_rate_exact()
+    }
+
+    /// Returns if the stored `fps` byte is the nominal rate of the region.
+    pub fn is_consistent(self) -> bool {
+        self.stored as u32 == self.nominal()
+    }
+}
+
+/// The country code of a ROM.
+/// - Converts to and from the raw value with `From`, keeping unknown values as [`CountryCode::Unknown`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CountryCode {
+    /// `7`, beta ROMs.
+    Beta,
+    /// `A`, Asia.
+    Asia,
+    /// `B`, Brazil.
+    Brazil,
+    /// `C`, China.
+    China,
+    /// `D`, Germany.
+    Germany,
+    /// `E`, North America.
+    NorthAmerica,
+    /// `F`, France.
+    France,
+    /// `G`, Gateway 64 in NTSC regions.
+    GatewayNtsc,
+    /// `H`, Netherlands.
+    Netherlands,
+    /// `I`, Italy.
+    Italy,
+    /// `J`, Japan.
+    Japan,
+    /// `K`, Korea.
+    Korea,
+    /// `L`, Gateway 64 in PAL regions.
+    GatewayPal,
+    /// `N`, Canada.
+    Canada,
+    /// `P`, Europe.
+    Europe,
+    /// `S`, Spain.
+    Spain,
+    /// `U`, Australia.
+    Australia,
+    /// `W`, Scandinavia.
+    Scandinavia,
+    /// `X`, `Y` or `Z`, other European releases, holding the raw value.
+    OtherEurope(u16),
+    /// Any other value.
+    Unknown(u16),
+}
+
+impl CountryCode {
+    /// Returns the video region of the country, or `None` if the country code is unknown.
+    pub fn region(self) -> Option<Region> {
+        match self {
+            CountryCode::Beta
+            | CountryCode::Asia
+            | CountryCode::NorthAmerica
+            | CountryCode::GatewayNtsc
+            | CountryCode::Japan
+            | CountryCode::Korea
+            | CountryCode::Canada => Some(Region::Ntsc),
+            CountryCode::Brazil => Some(Region::Mpal),
+            CountryCode::China
+            | CountryCode::Germany
+            | CountryCode::France
+            | CountryCode::Netherlands
+            | CountryCode::Italy
+            | CountryCode::GatewayPal
+            | CountryCode::Europe
+            | CountryCode::Spain
+            | CountryCode::Australia
+            | CountryCode::Scandinavia
+            | CountryCode::OtherEurope(_) => Some(Region::Pal),
+            CountryCode::Unknown(_) => None,
+        }
+    }
+
+    /// Returns the nominal number of VIs per second, or `None` if the country code is unknown.
+    pub fn vi_rate(self) -> Option<u32> {
+        self.region().map(Region::vi_rate)
+    }
+}
+
+impl From<u16> for CountryCode {
+    fn from(value: u16) -> Self {
+        match value {
+            0x37 => CountryCode::Beta,
+            0x41 => CountryCode::Asia,
+            0x42 => CountryCode::Brazil,
+            0x43 => CountryCode::China,
+            0x44 => CountryCode::Germany,
+            0x45 => CountryCode::NorthAmerica,
+            0x46 => CountryCode::France,
+            0x47 => CountryCode::GatewayNtsc,
+            0x48 => CountryCode::Netherlands,
+            0x49 => CountryCode::Italy,
+            0x4A => CountryCode::Japan,
+            0x4B => CountryCode::Korea,
+            0x4C => CountryCode::GatewayPal,
+            0x4E => CountryCode::Canada,
+            0x50 => CountryCode::Europe,
+            0x53 => CountryCode::Spain,
+            0x55 => CountryCode::Australia,
+            0x57 => CountryCode::Scandinavia,
+            0x58..=0x5A => CountryCode::OtherEurope(value),
+            _ => CountryCode::Unknown(value),
+        }
+    }
+}
+
+impl From<CountryCode> for u16 {
+    fn from(value: CountryCode) -> Self {
+        match value {
+            CountryCode::Beta => 0x37,
+            CountryCode::Asia => 0x41,
+            CountryCode::Brazil => 0x42,
+            CountryCode::China => 0x43,
+            CountryCode::Germany => 0x44,
+            CountryCode::NorthAmerica => 0x45,
+            CountryCode::France => 0x46,
+            CountryCode::GatewayNtsc => 0x47,
+            CountryCode::Netherlands => 0x48,
+            CountryCode::Italy => 0x49,
+            CountryCode::Japan => 0x4A,
+            CountryCode::Korea => 0x4B,
+            CountryCode::GatewayPal => 0x4C,
+            CountryCode::Canada => 0x4E,
+            CountryCode::Europe => 0x50,
+            CountryCode::Spain => 0x53,
+            CountryCode::Australia => 0x55,
+            CountryCode::Scandinavia => 0x57,
+            CountryCode::OtherEurope(value) | CountryCode::Unknown(value) => value,
+        }
+    }
+}
+
+impl M64 {
+    /// Returns the country code of the ROM used when recording.
+    pub fn country_code(&self) -> CountryCode {
+        self.rom_country_code.into()
+    }
+
+    /// Returns the video region of the movie.
+    /// - Taken from the ROM country code, falling back to the `fps` byte if the country code is unknown.
+    pub fn region(&self) -> Region {
+        self.country_code().region().unwrap_or(if self.fps == 50 {
+            Region::Pal
+        } else {
+            Region::Ntsc
+        })
+    }
+
+    /// Returns the VI rate of the movie, with both the stored `fps` byte and the actual rate of its [region](M64::region).
+    pub fn vertical_rate(&self) -> VerticalRate {
+        VerticalRate::new(self.fps, self.region())
+    }
+
+    /// Returns the length of the movie, from the VI count and the actual VI rate of its [region](M64::region).
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.vi_frames as f64 / self.vertical_rate().exact())
+    }
+
+    /// Returns the length of the movie formatted like TASVideos does, as `H:MM:SS.ss`.
+    pub fn length_string(&self) -> String {
+        let duration = self.duration();
+        let centis = (duration.as_millis() + 5) / 10;
+        format!(
+            "{}:{:02}:{:02}.{:02}",
+            centis / 360_000,
+            centis / 6000 % 60,
+            centis / 100 % 60,
+            centis % 100
+        )
+    }
+
+    /// Sets the country code of the ROM used when recording.
+    pub fn set_country_code(&mut self, country_code: CountryCode) {
+        self.rom_country_code = country_code.into();
+    }
+}
@@ -0,0 +1,120 @@
+//! Mechanical fixes for the inconsistencies [`M64::validate`] finds.
+//!
+//! Only issues with a single obvious fix are repaired, anything that needs a decision,
+//! such as which controllers the inputs belong to, is left to the caller.
+use crate::{
+    controller::{Buttons, Input},
+    error::FieldName,
+    m64::M64,
+};
+
+/// Which repairs [`M64::repair`] makes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RepairOptions {
+    /// Complete an incomplete last frame with neutral inputs.
+    pub pad_incomplete_frame: bool,
+    /// Set `input_frames` to the number of input samples.
+    pub recount_input_frames: bool,
+    /// Clear single reserved bits from the input samples, keeping the resets which have both set.
+    pub clear_reserved_bits: bool,
+    /// Cut off each string field at its first NUL, and pad it with NUL bytes.
+    pub normalize_strings: bool,
+}
+
+impl Default for RepairOptions {
+    /// Makes every repair.
+    fn default() -> Self {
+        RepairOptions {
+            pad_incomplete_frame: true,
+            recount_input_frames: true,
+            clear_reserved_bits: true,
+            normalize_strings: true,
+        }
+    }
+}
+
+/// A change made by [`M64::repair`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Repair {
+    /// Neutral input samples were appended to complete the last frame.
+    PaddedIncompleteFrame {
+        /// The number of samples appended.
+        samples: usize,
+    },
+    /// `input_frames` was set to the number of input samples.
+    RecountedInputFrames {
+        /// The previous value.
+        from: u32,
+        /// The new value.
+        to: u32,
+    },
+    /// Single reserved bits were cleared.
+    ClearedReservedBits {
+        /// The number of samples changed.
+        samples: usize,
+    },
+    /// A string field had bytes after its first NUL, which were cleared.
+    NormalizedString(FieldName),
+}
+
+impl M64 {
+    /// Repairs the inconsistencies that have a single obvious fix, and returns what was changed.
+    /// - Returns nothing if the movie needed no repairs.
+    pub fn repair(&mut self, options: RepairOptions) -> Vec<Repair> {
+        let mut repairs = Vec::new();
+
+        if options.pad_incomplete_frame {
+            let stride = self.samples_per_frame();
+            let samples = (stride - self.inputs.len() % stride) % stride;
+            if samples > 0 {
+                self.inputs
+                    .extend(std::iter::repeat_n(Input::default(), samples));
+                repairs.push(Repair::PaddedIncompleteFrame { samples });
+            }
+        }
+
+        if options.recount_input_frames && self.input_frames as usize != self.inputs.len() {
+            let to = self.inputs.len() as u32;
+            repairs.push(Repair::RecountedInputFrames {
+                from: self.input_frames,
+                to,
+            });
+            self.input_frames = to;
+        }
+
+        if options.clear_reserved_bits {
+            let mut samples = 0;
+            for input in &mut self.inputs {
+                if input.reserved_1 != input.reserved_2 {
+                    input.set_buttons(input.buttons() - Buttons::RESERVED);
+                    samples += 1;
+                }
+            }
+            if samples > 0 {
+                repairs.push(Repair::ClearedReservedBits { samples });
+            }
+        }
+
+        if options.normalize_strings {
+            let mut changed = [
+                (
+                    FieldName::RomInternalName,
+                    self.rom_internal_name.normalize(),
+                ),
+                (FieldName::VideoPlugin, self.video_plugin.normalize()),
+                (FieldName::SoundPlugin, self.sound_plugin.normalize()),
+                (FieldName::InputPlugin, self.input_plugin.normalize()),
+                (FieldName::RspPlugin, self.rsp_plugin.normalize()),
+                (FieldName::Author, self.author.normalize()),
+                (FieldName::Description, self.description.normalize()),
+            ]
+            .into_iter()
+            .filter(|(_, changed)| *changed)
+            .map(|(field, _)| Repair::NormalizedString(field))
+            .collect();
+            repairs.append(&mut changed);
+        }
+
+        repairs
+    }
+}
@@ -0,0 +1,186 @@
+//! Reading the header of N64 ROMs.
+//!
+//! ROMs are dumped in one of three byte orders, which are told apart by the magic number at the start:
+//! - `.z64`: big endian, the native byte order.
+//! - `.v64`: every pair of bytes swapped.
+//! - `.n64`: little endian, every 4 bytes reversed.
+//!
+//! The header is read after converting to big endian, with the following fields:
+//! - `0x00`: the magic number `80 37 12 40`.
+//! - `0x10`: CRC1, big endian `u32`.
+//! - `0x14`: CRC2, big endian `u32`.
+//! - `0x20`: internal name, 20 bytes padded with spaces.
+//! - `0x3E`: country code.
+use std::fmt;
+
+use crate::{error::RomError, m64::M64, region::CountryCode};
+
+/// Magic number at the start of a big endian ROM.
+pub const ROM_MAGIC: [u8; 4] = [0x80, 0x37, 0x12, 0x40];
+/// Size of the ROM header in bytes.
+pub const ROM_HEADER_SIZE: usize = 0x40;
+
+/// The byte order of a ROM dump.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ByteOrder {
+    /// Big endian, usually with the `.z64` extension.
+    BigEndian,
+    /// Bytes swapped in pairs, usually with the `.v64` extension.
+    ByteSwapped,
+    /// Little endian, usually with the `.n64` extension.
+    LittleEndian,
+}
+
+impl ByteOrder {
+    /// Detects the byte order from the magic number at the start of a ROM.
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        match data.get(..4)? {
+            [0x80, 0x37, 0x12, 0x40] => Some(ByteOrder::BigEndian),
+            [0x37, 0x80, 0x40, 0x12] => Some(ByteOrder::ByteSwapped),
+            [0x40, 0x12, 0x37, 0x80] => Some(ByteOrder::LittleEndian),
+            _ => None,
+        }
+    }
+
+    /// Converts data in this byte order to big endian in place.
+    /// - Trailing bytes that don't fill a whole word are left as is.
+    pub fn to_big_endian(self, data: &mut [u8]) {
+        match self {
+            ByteOrder::BigEndian => (),
+            ByteOrder::ByteSwapped => data.chunks_exact_mut(2).for_each(|word| word.swap(0, 1)),
+            ByteOrder::LittleEndian => data.chunks_exact_mut(4).for_each(<[u8]>::reverse),
+        }
+    }
+}
+
+/// Returns a copy of the ROM converted to big endian.
+pub fn normalize_rom(rom: &[u8]) -> Result<Vec<u8>, RomError> {
+    let byte_order = ByteOrder::detect(rom).ok_or(RomError::InvalidMagic)?;
+    let mut rom = rom.to_vec();
+    byte_order.to_big_endian(&mut rom);
+    Ok(rom)
+}
+
+/// The header of a N64 ROM.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RomHeader {
+    /// Internal name, without the padding.
+    pub internal_name: String,
+    /// First checksum of the ROM.
+    pub crc1: u32,
+    /// Second checksum of the ROM.
+    pub crc2: u32,
+    /// Country code, such as `b'E'` for North America.
+    pub country_code: u8,
+}
+
+impl RomHeader {
+    /// Reads the header from the start of a ROM in any byte order.
+    pub fn from_u8_array(data: &[u8]) -> Result<Self, RomError> {
+        let mut header: [u8; ROM_HEADER_SIZE] = data
+            .get(..ROM_HEADER_SIZE)
+            .ok_or(RomError::TooShort)?
+            .try_into()
+            .unwrap();
+        ByteOrder::detect(&header)
+            .ok_or(RomError::InvalidMagic)?
+            .to_big_endian(&mut header);
+
+        let be_u32 =
+            |offset: usize| u32::from_be_bytes(header[offset..offset + 4].try_into().unwrap());
+        let internal_name = String::from_utf8_lossy(&header[0x20..0x34])
+            .trim_end_matches([' ', '\0'])
+            .to_string();
+
+        Ok(RomHeader {
+            internal_name,
+            crc1: be_u32(0x10),
+            crc2: be_u32(0x14),
+            country_code: header[0x3E],
+        })
+    }
+
+    /// Returns the typed country code.
+    pub fn country(&self) -> CountryCode {
+        u16::from(self.country_code).into()
+    }
+}
+
+/// A difference between a movie's ROM fields and a ROM.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RomMismatch {
+    /// The internal names differ.
+    InternalName {
+        /// Internal name stored in the movie.
+        movie: String,
+        /// Internal name of the ROM.
+        rom: String,
+    },
+    /// The CRCs differ, in the byte order stored in the movie.
+    Crc32 {
+        /// CRC stored in the movie.
+        movie: u32,
+        /// CRC1 of the ROM.
+        rom: u32,
+    },
+    /// The country codes differ.
+    CountryCode {
+        /// Country code stored in the movie.
+        movie: u16,
+        /// Country code of the ROM.
+        rom: u16,
+    },
+}
+
+impl fmt::Display for RomMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RomMismatch::InternalName { movie, rom } => write!(
+                f,
+                "movie was recorded on \"{movie}\", but the ROM is \"{rom}\""
+            ),
+            RomMismatch::Crc32 { movie, rom } => write!(
+                f,
+                "movie was recorded on a ROM with CRC {movie:08X}, but the ROM has CRC {rom:08X}"
+            ),
+            RomMismatch::CountryCode { movie, rom } => write!(
+                f,
+                "movie was recorded on a ROM with country code {movie:#04X}, but the ROM has country code {rom:#04X}"
+            ),
+        }
+    }
+}
+
+impl M64 {
+    /// Compares the ROM fields of the movie against a ROM.
+    /// - Returns every mismatch found, so an empty list means the movie was recorded on this ROM.
+    /// - Fails if the ROM header can't be read.
+    pub fn verify_rom(&self, rom: &[u8]) -> Result<Vec<RomMismatch>, RomError> {
+        let header = RomHeader::from_u8_array(rom)?;
+        let mut mismatches = Vec::new();
+
+        let movie_name = self.rom_internal_name.trim_end_matches([' ', '\0']);
+        if movie_name != header.internal_name {
+            mismatches.push(RomMismatch::InternalName {
+                movie: movie_name.to_string(),
+                rom: header.internal_name.clone(),
+            });
+        }
+        // the movie stores CRC1 as read in little endian
+        let rom_crc = header.crc1.swap_bytes();
+        if self.rom_crc_32 != rom_crc {
+            mismatches.push(RomMismatch::Crc32 {
+                movie: self.rom_crc_32,
+                rom: rom_crc,
+            });
+        }
+        if self.rom_country_code != u16::from(header.country_code) {
+            mismatches.push(RomMismatch::CountryCode {
+                movie: self.rom_country_code,
+                rom: header.country_code.into(),
+            });
+        }
+
+        Ok(mismatches)
+    }
+}
@@ -0,0 +1,152 @@
+//! Locating and validating the save files of movies starting from EEPROM.
+//!
+//! Movies with [`MovieStartType::Eeprom`] start from existing save data, which is stored next to the movie
+//! with the same file name and the extension of the save type, such as `movie.eep` for `movie.m64`.
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::m64::{MovieStartType, M64};
+
+/// The kinds of save data a game can use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SaveType {
+    /// EEPROM, either 4 or 16 kbit.
+    Eeprom,
+    /// Battery backed SRAM.
+    Sram,
+    /// Flash RAM.
+    FlashRam,
+    /// Controller mem paks.
+    Mempak,
+}
+
+impl SaveType {
+    /// All save types.
+    pub const ALL: [SaveType; 4] = [
+        SaveType::Eeprom,
+        SaveType::Sram,
+        SaveType::FlashRam,
+        SaveType::Mempak,
+    ];
+
+    /// Returns the file extension of the save type.
+    pub fn extension(self) -> &'static str {
+        match self {
+            SaveType::Eeprom => "eep",
+            SaveType::Sram => "sra",
+            SaveType::FlashRam => "fla",
+            SaveType::Mempak => "mpk",
+        }
+    }
+
+    /// Returns the valid sizes of the save type in bytes.
+    /// - Mem pak files hold either a single pak or all 4.
+    pub fn valid_sizes(self) -> &'static [usize] {
+        match self {
+            SaveType::Eeprom => &[0x200, 0x800],
+            SaveType::Sram => &[0x8000],
+            SaveType::FlashRam => &[0x20000],
+            SaveType::Mempak => &[0x8000, 0x20000],
+        }
+    }
+}
+
+/// A save file found next to a movie.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SaveFile {
+    /// The save type, from the extension.
+    pub save_type: SaveType,
+    /// Path of the file.
+    pub path: PathBuf,
+    /// Size of the file in bytes.
+    pub size: usize,
+}
+
+/// A problem with the save files of a movie.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SaveWarning {
+    /// The movie doesn't start from EEPROM, so its save files aren't used.
+    NotEepromMovie,
+    /// The movie starts from EEPROM, but no save file was found.
+    Missing,
+    /// The save file has a size that isn't valid for its save type.
+    InvalidSize(SaveFile),
+    /// The save file only holds `0x00` or `0xFF` bytes, so it's likely an unused save.
+    Blank(SaveFile),
+}
+
+/// The save files found for a movie, and any problems with them.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct SaveReport {
+    /// Save files found next to the movie.
+    pub files: Vec<SaveFile>,
+    /// Problems found.
+    pub warnings: Vec<SaveWarning>,
+}
+
+impl SaveReport {
+    /// Returns `true` if no problems were found.
+    pub fn is_ok(&self) -> bool {
+        self.warnings.is_empty()
+    }
+}
+
+/// Returns the path of the save file of the given type for the movie stored at `movie_path`.
+pub fn save_path<P>(movie_path: P, save_type: SaveType) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    movie_path.as_ref().with_extension(save_type.extension())
+}
+
+/// Checks save data of the given type, returning a warning if it's not valid.
+pub fn validate_save(save_file: SaveFile, data: &[u8]) -> Option<SaveWarning> {
+    if !save_file.save_type.valid_sizes().contains(&save_file.size) {
+        Some(SaveWarning::InvalidSize(save_file))
+    } else if data.iter().all(|&b| b == 0x00) || data.iter().all(|&b| b == 0xFF) {
+        Some(SaveWarning::Blank(save_file))
+    } else {
+        None
+    }
+}
+
+impl M64 {
+    /// Locates and validates the save files of the movie stored at `movie_path`.
+    /// - See the [module documentation](crate::save) for where the save files are looked for.
+    pub fn check_save_files<P>(&self, movie_path: P) -> io::Result<SaveReport>
+    where
+        P: AsRef<Path>,
+    {
+        let mut report = SaveReport::default();
+        if self.movie_start_type != MovieStartType::Eeprom {
+            report.warnings.push(SaveWarning::NotEepromMovie);
+        }
+
+        for save_type in SaveType::ALL {
+            let path = save_path(&movie_path, save_type);
+            let data = match fs::read(&path) {
+                Ok(data) => data,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(err),
+            };
+
+            let save_file = SaveFile {
+                save_type,
+                path,
+                size: data.len(),
+            };
+            if let Some(warning) = validate_save(save_file.clone(), &data) {
+                report.warnings.push(warning);
+            }
+            report.files.push(save_file);
+        }
+
+        if self.movie_start_type == MovieStartType::Eeprom && report.files.is_empty() {
+            report.warnings.push(SaveWarning::Missing);
+        }
+
+        Ok(report)
+    }
+}
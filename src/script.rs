@@ -0,0 +1,115 @@
+//! A human writable text format for authoring inputs.
+//!
+//! Each line holds a frame or an inclusive frame range, followed by the input held on those frames:
+//! ```text
+//! # jump and dive
+//! 120-180: A+Z x=60 y=0
+//! 181: B
+//! 200-210: y=-127
+//! ```
+//! - Frames start from 0, and frames that aren't written are neutral.
+//! - A line can't reach more than [`MAX_INPUT_GAP`] frames past the last frame of the lines before it.
+//! - Buttons are joined by `+`, using the same names as the constants of [`Buttons`].
+//! - `x` and `y` set the analog stick axes, and default to 0.
+//! - Everything after a `#` is a comment.
+//! - Later lines overwrite the frames of earlier lines.
+use crate::{
+    controller::{Buttons, Input},
+    csv::MAX_INPUT_GAP,
+    error::ScriptError,
+};
+
+/// Compiles a script into an input for each frame.
+/// - See the [module documentation](crate::script) for the format.
+pub fn parse_script(script: &str) -> Result<Vec<Input>, ScriptError> {
+    let mut inputs = Vec::new();
+
+    for (i, line) in script.lines().enumerate() {
+        let line = line.split_once('#').map_or(line, |(line, _)| line).trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let (start, end, input) = parse_line(line).ok_or(ScriptError::InvalidLine(i + 1))?;
+        if inputs.len() <= end {
+            if end - inputs.len() >= MAX_INPUT_GAP {
+                return Err(ScriptError::InputGap(i + 1));
+            }
+            inputs.resize(end + 1, Input::default());
+        }
+        inputs[start..=end].fill(input);
+    }
+
+    Ok(inputs)
+}
+
+/// Writes inputs as a script, with a line for each run of identical inputs.
+/// - Neutral runs are left out, except at the end so the number of frames is kept.
+pub fn format_script(inputs: &[Input]) -> String {
+    let mut script = String::new();
+    let mut start = 0;
+
+    for run in inputs.chunk_by(|a, b| a == b) {
+        let end = start + run.len() - 1;
+        let input = run[0];
+        if !input.is_neutral() || end + 1 == inputs.len() {
+            if start == end {
+                script.push_str(&format!("{start}:"));
+            } else {
+                script.push_str(&format!("{start}-{end}:"));
+            }
+
+            let buttons = input
+                .buttons()
+                .iter_names()
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>()
+                .join("+");
+            if !buttons.is_empty() {
+                script.push_str(&format!(" {buttons}"));
+            }
+            if input.x_axis != 0 {
+                script.push_str(&format!(" x={}", input.x_axis));
+            }
+            if input.y_axis != 0 {
+                script.push_str(&format!(" y={}", input.y_axis));
+            }
+            script.push('\n');
+        }
+        start = end + 1;
+    }
+
+    script
+}
+
+/// Parses a single line without the comment, returning the inclusive frame range and the input.
+fn parse_line(line: &str) -> Option<(usize, usize, Input)> {
+    let (frames, body) = line.split_once(':')?;
+    let (start, end) = match frames.split_once('-') {
+        Some((start, end)) => (start.trim().parse().ok()?, end.trim().parse().ok()?),
+        None => {
+            let frame = frames.trim().parse().ok()?;
+            (frame, frame)
+        }
+    };
+    if start > end {
+        return None;
+    }
+
+    let mut input = Input::default();
+    let mut buttons = Buttons::empty();
+    for token in body.split_whitespace() {
+        if let Some(x) = token.strip_prefix("x=") {
+            input.x_axis = x.parse().ok()?;
+        } else if let Some(y) = token.strip_prefix("y=") {
+            input.y_axis = y.parse().ok()?;
+        } else {
+            for name in token.split('+').filter(|name| !name.is_empty()) {
+                buttons |= Buttons::from_name(name)?;
+            }
+        }
+    }
+
+    input.set_buttons(buttons);
+    Some((start, end, input))
+}
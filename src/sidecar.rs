@@ -0,0 +1,325 @@
+//! Sidecar files holding annotations and metadata that don't fit in a movie.
+//!
+//! The sidecar of `movie.m64` is stored next to it as `movie.m64.json`.
+//!
+//! # Schema
+//! The sidecar is a single object with the following keys:
+//! - `version`: the format version, currently [`SIDECAR_VERSION`].
+//! - `uid`: the [`uid`](M64::uid) of the movie the sidecar belongs to.
+//! - `authors`: array of author names, which can be any length unlike the [`author`](M64::author) field.
+//! - `urls`: array of related URLs, such as submission pages or encodes.
+//! - `comments`: array of objects with the input frame range `start` and `end` (exclusive), and the `text` shown on them.
+//! - `bookmarks`: array of objects with an input `frame`, its `label` and an optional `note`.
+//!   The label was called `name` before, which is still read.
+//! - `metadata`: object of any other string values.
+//!
+//! Every key other than `version` and `uid` may be left out when empty.
+use std::{
+    collections::BTreeMap,
+    fs,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use serde_json::{json, Map, Value};
+
+use crate::{error::SidecarError, m64::M64, srt::Annotation};
+
+/// Sidecar format version written by this crate.
+pub const SIDECAR_VERSION: u64 = 1;
+
+/// A labelled input frame.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Bookmark {
+    /// The input frame.
+    pub frame: usize,
+    /// Label of the bookmark.
+    pub label: String,
+    /// Longer notes on the frame.
+    pub note: Option<String>,
+}
+
+impl Bookmark {
+    /// Creates a bookmark without a note.
+    pub fn new<S>(frame: usize, label: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            frame,
+            label: label.into(),
+            note: None,
+        }
+    }
+}
+
+/// Annotations and extended metadata of a movie.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Sidecar {
+    /// Author names.
+    pub authors: Vec<String>,
+    /// Related URLs.
+    pub urls: Vec<String>,
+    /// Comments on ranges of input frames, which can also be used as [subtitles](M64::to_srt).
+    pub comments: Vec<Annotation>,
+    /// Bookmarked input frames, ordered by frame when added through [`Sidecar::add_bookmark`].
+    pub bookmarks: Vec<Bookmark>,
+    /// Any other metadata.
+    pub metadata: BTreeMap<String, String>,
+}
+
+/// Returns the path of the sidecar file for the given movie path.
+pub fn sidecar_path<P>(movie_path: P) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    let mut path = movie_path.as_ref().as_os_str().to_owned();
+    path.push(".json");
+    path.into()
+}
+
+impl Sidecar {
+    /// Returns the sidecar as JSON, for the movie with the given UID.
+    /// - See the [module documentation](crate::sidecar) for the schema.
+    pub fn to_json(&self, uid: u32) -> String {
+        let comments = self
+            .comments
+            .iter()
+            .map(|comment| {
+                json!({
+                    "start": comment.frames.start,
+                    "end": comment.frames.end,
+                    "text": comment.text,
+                })
+            })
+            .collect::<Vec<_>>();
+        let bookmarks = self
+            .bookmarks
+            .iter()
+            .map(|bookmark| {
+                let mut value = json!({ "frame": bookmark.frame, "label": bookmark.label });
+                if let Some(note) = &bookmark.note {
+                    value["note"] = note.as_str().into();
+                }
+                value
+            })
+            .collect::<Vec<_>>();
+
+        let value = json!({
+            "version": SIDECAR_VERSION,
+            "uid": uid,
+            "authors": self.authors,
+            "urls": self.urls,
+            "comments": comments,
+            "bookmarks": bookmarks,
+            "metadata": self.metadata,
+        });
+        serde_json::to_string_pretty(&value).unwrap()
+    }
+
+    /// Parses a sidecar from JSON, returning it along with the UID of the movie it belongs to.
+    /// - See the [module documentation](crate::sidecar) for the schema.
+    pub fn from_json(json: &str) -> Result<(Self, u32), SidecarError> {
+        let value = serde_json::from_str::<Value>(json)?;
+        let object = value
+            .as_object()
+            .ok_or(SidecarError::InvalidField("sidecar"))?;
+
+        match object.get("version").and_then(Value::as_u64) {
+            Some(SIDECAR_VERSION) => (),
+            Some(version) => return Err(SidecarError::UnsupportedVersion(version)),
+            None => return Err(SidecarError::InvalidField("version")),
+        }
+        let uid = object
+            .get("uid")
+            .and_then(Value::as_u64)
+            .and_then(|uid| u32::try_from(uid).ok())
+            .ok_or(SidecarError::InvalidField("uid"))?;
+
+        let strings = |key| {
+            array(object, key)?
+                .iter()
+                .map(|value| value.as_str().map(str::to_string))
+                .collect::<Option<Vec<_>>>()
+                .ok_or(SidecarError::InvalidField(key))
+        };
+        let authors = strings("authors")?;
+        let urls = strings("urls")?;
+
+        let comments = array(object, "comments")?
+            .iter()
+            .map(|comment| {
+                let start = usize_field(comment, "start")?;
+                let end = usize_field(comment, "end")?;
+                let text = comment.get("text")?.as_str()?;
+                Some(Annotation::new(start..end, text))
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or(SidecarError::InvalidField("comments"))?;
+
+        let bookmarks = array(object, "bookmarks")?
+            .iter()
+            .map(|bookmark| {
+                let label = bookmark.get("label").or_else(|| bookmark.get("name"))?;
+                let note = match bookmark.get("note") {
+                    Some(note) => Some(note.as_str()?.to_string()),
+                    None => None,
+                };
+                Some(Bookmark {
+                    frame: usize_field(bookmark, "frame")?,
+                    label: label.as_str()?.to_string(),
+                    note,
+                })
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or(SidecarError::InvalidField("bookmarks"))?;
+
+        let metadata = match object.get("metadata") {
+            Some(metadata) => metadata
+                .as_object()
+                .and_then(|metadata| {
+                    metadata
+                        .iter()
+                        .map(|(key, value)| Some((key.clone(), value.as_str()?.to_string())))
+                        .collect::<Option<BTreeMap<_, _>>>()
+                })
+                .ok_or(SidecarError::InvalidField("metadata"))?,
+            None => BTreeMap::new(),
+        };
+
+        let sidecar = Sidecar {
+            authors,
+            urls,
+            comments,
+            bookmarks,
+            metadata,
+        };
+        Ok((sidecar, uid))
+    }
+}
+
+impl Sidecar {
+    /// Adds a bookmark, keeping the bookmarks ordered by frame.
+    /// - Bookmarks on the same frame keep the order they were added in.
+    pub fn add_bookmark(&mut self, bookmark: Bookmark) {
+        let index = self
+            .bookmarks
+            .partition_point(|existing| existing.frame <= bookmark.frame);
+        self.bookmarks.insert(index, bookmark);
+    }
+
+    /// Returns the first bookmark with the given label.
+    pub fn bookmark(&self, label: &str) -> Option<&Bookmark> {
+        self.bookmarks
+            .iter()
+            .find(|bookmark| bookmark.label == label)
+    }
+
+    /// Returns the bookmarks within the range of frames.
+    pub fn bookmarks_in(&self, frames: Range<usize>) -> impl Iterator<Item = &Bookmark> + '_ {
+        self.bookmarks
+            .iter()
+            .filter(move |bookmark| frames.contains(&bookmark.frame))
+    }
+
+    /// Removes every bookmark with the given label, returning how many were removed.
+    pub fn remove_bookmarks(&mut self, label: &str) -> usize {
+        let len = self.bookmarks.len();
+        self.bookmarks.retain(|bookmark| bookmark.label != label);
+        len - self.bookmarks.len()
+    }
+
+    /// Moves bookmarks and comments to follow `count` frames being inserted before frame `at`.
+    pub fn insert_frames(&mut self, at: usize, count: usize) {
+        let shift = |frame: &mut usize| {
+            if *frame >= at {
+                *frame += count;
+            }
+        };
+        for bookmark in &mut self.bookmarks {
+            shift(&mut bookmark.frame);
+        }
+        for comment in &mut self.comments {
+            // a comment that spans the insertion grows to cover the inserted frames
+            if comment.frames.start > at {
+                comment.frames.start += count;
+            }
+            shift(&mut comment.frames.end);
+        }
+    }
+
+    /// Moves bookmarks and comments to follow the range of frames being deleted.
+    /// - Bookmarks on deleted frames are removed and returned, and comments only on deleted frames are removed.
+    pub fn delete_frames(&mut self, frames: Range<usize>) -> Vec<Bookmark> {
+        let shift = |frame: usize| {
+            if frame >= frames.end {
+                frame - frames.len()
+            } else {
+                frame.min(frames.start)
+            }
+        };
+
+        let (deleted, kept) = self
+            .bookmarks
+            .drain(..)
+            .partition(|bookmark| frames.contains(&bookmark.frame));
+        self.bookmarks = kept;
+        for bookmark in &mut self.bookmarks {
+            bookmark.frame = shift(bookmark.frame);
+        }
+
+        for comment in &mut self.comments {
+            comment.frames = shift(comment.frames.start)..shift(comment.frames.end);
+        }
+        self.comments.retain(|comment| !comment.frames.is_empty());
+
+        deleted
+    }
+}
+
+impl M64 {
+    /// Loads the sidecar of the movie stored at `movie_path`.
+    /// - Fails with [`SidecarError::UidMismatch`] if the sidecar belongs to a different movie.
+    pub fn load_sidecar<P>(&self, movie_path: P) -> Result<Sidecar, SidecarError>
+    where
+        P: AsRef<Path>,
+    {
+        let json = fs::read_to_string(sidecar_path(movie_path))?;
+        let (sidecar, uid) = Sidecar::from_json(&json)?;
+        if uid != self.uid {
+            return Err(SidecarError::UidMismatch {
+                expected: self.uid,
+                found: uid,
+            });
+        }
+
+        Ok(sidecar)
+    }
+
+    /// Saves the sidecar for the movie stored at `movie_path`.
+    pub fn save_sidecar<P>(&self, movie_path: P, sidecar: &Sidecar) -> Result<(), SidecarError>
+    where
+        P: AsRef<Path>,
+    {
+        fs::write(sidecar_path(movie_path), sidecar.to_json(self.uid))?;
+        Ok(())
+    }
+}
+
+/// Returns the array at the given key, or an empty slice if it's missing.
+fn array<'a>(
+    object: &'a Map<String, Value>,
+    key: &'static str,
+) -> Result<&'a [Value], SidecarError> {
+    match object.get(key) {
+        Some(value) => value
+            .as_array()
+            .map(Vec::as_slice)
+            .ok_or(SidecarError::InvalidField(key)),
+        None => Ok(&[]),
+    }
+}
+
+fn usize_field(value: &Value, key: &str) -> Option<usize> {
+    usize::try_from(value.get(key)?.as_u64()?).ok()
+}
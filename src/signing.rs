@@ -0,0 +1,115 @@
+//! Signing movies with ed25519 for tamper evidence.
+//!
+//! The signature covers the bytes the movie is written as, which is the header and the inputs, see [`M64::to_bytes`].
+//! It's detached from the movie and stored next to it in a `.sig` sidecar, such as `movie.m64.sig` for `movie.m64`.
+//!
+//! # Signature file
+//! | Offset | Size | Description                          |
+//! |--------|------|--------------------------------------|
+//! | 0x00   | 4    | Signature `M64S`                     |
+//! | 0x04   | 32   | ed25519 public key of the signer     |
+//! | 0x24   | 64   | ed25519 signature of the movie bytes |
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+pub use ed25519_dalek::{Signature, SigningKey, VerifyingKey};
+use ed25519_dalek::{Signer, Verifier};
+
+use crate::{error::SignatureError, m64::M64};
+
+/// File signature of signature files.
+const SIGNATURE_MAGIC: &[u8; 4] = b"M64S";
+
+/// A detached signature of a movie, along with the key that made it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MovieSignature {
+    /// Public key of the signer.
+    pub key: VerifyingKey,
+    /// Signature of the movie bytes.
+    pub signature: Signature,
+}
+
+impl MovieSignature {
+    /// Size of a signature file in bytes.
+    pub const SIZE: usize = 100;
+
+    /// Returns the signature file bytes.
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0; Self::SIZE];
+        bytes[..4].copy_from_slice(SIGNATURE_MAGIC);
+        bytes[4..36].copy_from_slice(self.key.as_bytes());
+        bytes[36..].copy_from_slice(&self.signature.to_bytes());
+        bytes
+    }
+
+    /// Parses a signature file.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SignatureError> {
+        if bytes.len() != Self::SIZE || &bytes[..4] != SIGNATURE_MAGIC {
+            return Err(SignatureError::InvalidFile);
+        }
+
+        let key = VerifyingKey::from_bytes(bytes[4..36].try_into().unwrap())
+            .map_err(|_| SignatureError::InvalidKey)?;
+        let signature = Signature::from_bytes(bytes[36..].try_into().unwrap());
+        Ok(Self { key, signature })
+    }
+}
+
+/// Returns the path of the signature file for the movie at `movie`.
+pub fn signature_path<P>(movie: P) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    let mut path = movie.as_ref().as_os_str().to_owned();
+    path.push(".sig");
+    PathBuf::from(path)
+}
+
+impl M64 {
+    /// Signs the movie with `key`.
+    pub fn sign(&self, key: &SigningKey) -> MovieSignature {
+        MovieSignature {
+            key: key.verifying_key(),
+            signature: key.sign(&self.to_bytes()),
+        }
+    }
+
+    /// Verifies that `signature` was made for this movie by the key it holds.
+    /// - This only proves the movie wasn't changed since it was signed,
+    ///   compare [`MovieSignature::key`] against a trusted key to know who signed it.
+    pub fn verify_signature(&self, signature: &MovieSignature) -> Result<(), SignatureError> {
+        signature
+            .key
+            .verify(&self.to_bytes(), &signature.signature)
+            .map_err(|_| SignatureError::Mismatch)
+    }
+
+    /// Signs the movie at `path` with `key`, writing the signature next to it.
+    pub fn sign_file<P>(path: P, key: &SigningKey) -> io::Result<MovieSignature>
+    where
+        P: AsRef<Path>,
+    {
+        let signature = key.sign(&fs::read(&path)?);
+        let signature = MovieSignature {
+            key: key.verifying_key(),
+            signature,
+        };
+        fs::write(signature_path(path), signature.to_bytes())?;
+        Ok(signature)
+    }
+
+    /// Verifies the movie at `path` against the signature next to it, returning the signature if it matches.
+    pub fn verify_file<P>(path: P) -> Result<MovieSignature, SignatureError>
+    where
+        P: AsRef<Path>,
+    {
+        let signature = MovieSignature::from_bytes(&fs::read(signature_path(&path))?)?;
+        signature
+            .key
+            .verify(&fs::read(path)?, &signature.signature)
+            .map_err(|_| SignatureError::Mismatch)?;
+        Ok(signature)
+    }
+}
@@ -0,0 +1,73 @@
+//! Generating SRT subtitles from frame annotations.
+use std::{ops::Range, time::Duration};
+
+use crate::m64::M64;
+
+/// A piece of text shown over a range of input frames.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Annotation {
+    /// The input frames the text is shown on.
+    pub frames: Range<usize>,
+    /// The text to show.
+    pub text: String,
+}
+
+impl Annotation {
+    /// Creates a new annotation.
+    pub fn new<S>(frames: Range<usize>, text: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            frames,
+            text: text.into(),
+        }
+    }
+}
+
+impl M64 {
+    /// Returns the time from the start of the movie at which the given input frame starts.
+    /// - Uses [`M64::input_frames_per_second`], so the timing follows the movie's region and how often the game polls for input.
+    /// - Returns [`Duration::ZERO`] if the rate is 0, which it is for a movie with VI frames but no input frames.
+    pub fn frame_time(&self, frame: usize) -> Duration {
+        let rate = self.input_frames_per_second();
+        if rate <= 0.0 {
+            return Duration::ZERO;
+        }
+        Duration::from_secs_f64(frame as f64 / rate)
+    }
+
+    /// Returns an SRT subtitle file showing the given annotations.
+    /// - Entries are ordered by their starting frame, and empty frame ranges are skipped.
+    pub fn to_srt(&self, annotations: &[Annotation]) -> String {
+        let mut annotations = annotations
+            .iter()
+            .filter(|annotation| !annotation.frames.is_empty())
+            .collect::<Vec<_>>();
+        annotations.sort_by_key(|annotation| annotation.frames.start);
+
+        let mut srt = String::new();
+        for (i, annotation) in annotations.into_iter().enumerate() {
+            srt.push_str(&format!(
+                "{}\n{} --> {}\n{}\n\n",
+                i + 1,
+                srt_timestamp(self.frame_time(annotation.frames.start)),
+                srt_timestamp(self.frame_time(annotation.frames.end)),
+                annotation.text.trim_end()
+            ));
+        }
+        srt
+    }
+}
+
+/// Formats a duration as an SRT timestamp, `hh:mm:ss,mmm`.
+fn srt_timestamp(time: Duration) -> String {
+    let millis = time.as_millis();
+    format!(
+        "{:02}:{:02}:{:02},{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
@@ -0,0 +1,167 @@
+//! Reading the header of Mupen64-rerecording savestates (`.st`).
+//!
+//! A savestate is usually gzip compressed, and starts with the MD5 hash of the ROM written as 32 ASCII hex characters,
+//! followed by the emulator state.
+//! If a movie was active when the state was saved, the state ends with a movie block:
+//! - `u32` size of the rest of the block.
+//! - `u32` UID of the movie.
+//! - `u32` input sample the movie was at.
+//! - `u32` VI frame the movie was at.
+//! - `u32` number of input samples in the movie.
+//! - The input samples of the movie, plus one extra sample.
+//!
+//! The emulator state in between differs between emulator versions, so the movie block is found from the end of the state.
+use std::io::Read;
+
+use flate2::read::GzDecoder;
+
+use crate::{controller::Input, error::StError, m64::M64};
+
+const GZIP_MAGIC: [u8; 2] = [0x1F, 0x8B];
+const ROM_MD5_LENGTH: usize = 32;
+/// Size of the movie block fields before the input samples.
+const MOVIE_BLOCK_HEADER: usize = 16;
+
+/// The header of a savestate.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StHeader {
+    /// MD5 hash of the ROM, as lowercase hex.
+    pub rom_md5: String,
+    /// Information of the movie that was active when the state was saved.
+    pub movie: Option<StMovie>,
+}
+
+/// The movie block of a savestate.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StMovie {
+    /// UID of the movie.
+    pub uid: u32,
+    /// Input sample the movie was at.
+    pub current_sample: u32,
+    /// VI frame the movie was at.
+    pub current_vi: u32,
+    /// Number of input samples in the movie.
+    pub length_samples: u32,
+    /// The input samples of the movie, including the extra sample at the end.
+    pub inputs: Vec<Input>,
+}
+
+impl StHeader {
+    /// Reads the header of a savestate, decompressing it first if it's gzip compressed.
+    pub fn from_u8_array(data: &[u8]) -> Result<Self, StError> {
+        if data.starts_with(&GZIP_MAGIC) {
+            let mut decompressed = Vec::new();
+            GzDecoder::new(data).read_to_end(&mut decompressed)?;
+            return Self::from_decompressed(&decompressed);
+        }
+
+        Self::from_decompressed(data)
+    }
+
+    fn from_decompressed(data: &[u8]) -> Result<Self, StError> {
+        let rom_md5 = data.get(..ROM_MD5_LENGTH).ok_or(StError::UnexpectedEof)?;
+        if !rom_md5.iter().all(u8::is_ascii_hexdigit) {
+            return Err(StError::InvalidRomMd5);
+        }
+
+        Ok(StHeader {
+            rom_md5: String::from_utf8_lossy(rom_md5).to_ascii_lowercase(),
+            movie: find_movie_block(&data[ROM_MD5_LENGTH..]),
+        })
+    }
+}
+
+/// Finds the movie block at the end of the state, by looking for a sample count that matches the block size.
+fn find_movie_block(data: &[u8]) -> Option<StMovie> {
+    let u32_at = |offset: usize| {
+        let bytes = data.get(offset..offset + 4)?;
+        Some(u32::from_le_bytes(bytes.try_into().unwrap()))
+    };
+
+    let max_samples = data.len().checked_sub(MOVIE_BLOCK_HEADER + 8)? / 4;
+    (0..max_samples).find_map(|samples| {
+        let size = MOVIE_BLOCK_HEADER + (samples + 1) * 4;
+        let start = data.len() - size;
+        if u32_at(start - 4)? as usize != size || u32_at(start + 12)? as usize != samples {
+            return None;
+        }
+
+        let inputs = data[start + MOVIE_BLOCK_HEADER..]
+            .chunks_exact(4)
+            .map(|b| Input::from(u32::from_le_bytes(b.try_into().unwrap())))
+            .collect();
+        Some(StMovie {
+            uid: u32_at(start)?,
+            current_sample: u32_at(start + 4)?,
+            current_vi: u32_at(start + 8)?,
+            length_samples: samples as u32,
+            inputs,
+        })
+    })
+}
+
+/// A mismatch between a movie and a savestate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SavestateMismatch {
+    /// The movie doesn't start from a snapshot, so it doesn't use a savestate.
+    NotSnapshotMovie,
+    /// The savestate wasn't saved with a movie active.
+    NoMovie,
+    /// The UID of the savestate's movie doesn't match the movie.
+    Uid {
+        /// UID of the movie.
+        movie: u32,
+        /// UID stored in the savestate.
+        savestate: u32,
+    },
+    /// The savestate was saved past the end of the movie.
+    SampleOutOfRange {
+        /// Input sample the savestate was saved at.
+        current_sample: u32,
+        /// Number of input samples in the movie.
+        input_frames: u32,
+    },
+    /// The inputs stored in the savestate differ from the movie, starting from this sample.
+    Inputs {
+        /// Index of the first differing input sample.
+        sample: usize,
+    },
+}
+
+impl M64 {
+    /// Checks that the savestate belongs to this movie.
+    /// - Returns every mismatch found, so an empty list means the savestate matches.
+    /// - The inputs are compared up to the sample the savestate was saved at.
+    pub fn verify_savestate(&self, header: &StHeader) -> Vec<SavestateMismatch> {
+        let mut mismatches = Vec::new();
+        if !self.movie_start_type.is_snapshot() {
+            mismatches.push(SavestateMismatch::NotSnapshotMovie);
+        }
+
+        let Some(movie) = &header.movie else {
+            mismatches.push(SavestateMismatch::NoMovie);
+            return mismatches;
+        };
+
+        if movie.uid != self.uid {
+            mismatches.push(SavestateMismatch::Uid {
+                movie: self.uid,
+                savestate: movie.uid,
+            });
+        }
+        if movie.current_sample > self.input_frames {
+            mismatches.push(SavestateMismatch::SampleOutOfRange {
+                current_sample: movie.current_sample,
+                input_frames: self.input_frames,
+            });
+        }
+
+        let compared = (movie.current_sample as usize).min(movie.inputs.len());
+        let differing = (0..compared).find(|&i| self.inputs.get(i) != movie.inputs.get(i));
+        if let Some(sample) = differing {
+            mismatches.push(SavestateMismatch::Inputs { sample });
+        }
+
+        mismatches
+    }
+}
@@ -0,0 +1,345 @@
+//! Statistics about the inputs of a movie, such as button press counts and stick usage.
+use std::{collections::BTreeMap, ops::RangeBounds};
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+#[cfg(feature = "rayon")]
+use crate::storage::PARALLEL_MIN_SAMPLES;
+use crate::{
+    controller::{Buttons, Input},
+    m64::M64,
+};
+
+/// Width of each bucket of the stick displacement histogram.
+pub const STICK_HISTOGRAM_BUCKET: usize = 8;
+
+/// Statistics of a single button.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct ButtonStats {
+    /// The button.
+    pub button: Buttons,
+    /// Number of times the button was pressed, counting a hold as a single press.
+    pub presses: usize,
+    /// Number of frames the button was held for.
+    pub held_frames: usize,
+    /// How many times the button was held for a given number of frames, keyed by the hold length.
+    pub hold_times: BTreeMap<usize, usize>,
+}
+
+impl ButtonStats {
+    /// Returns the longest hold in frames, or `None` if the button was never pressed.
+    pub fn longest_hold(&self) -> Option<usize> {
+        self.hold_times.keys().next_back().copied()
+    }
+
+    /// Returns the shortest hold in frames, or `None` if the button was never pressed.
+    pub fn shortest_hold(&self) -> Option<usize> {
+        self.hold_times.keys().next().copied()
+    }
+
+    /// Returns the average hold in frames, or `None` if the button was never pressed.
+    pub fn average_hold(&self) -> Option<f64> {
+        (self.presses > 0).then(|| self.held_frames as f64 / self.presses as f64)
+    }
+
+    /// Returns the most common hold length in frames, the shortest one if there are multiple.
+    /// - A hold length that repeats far more often than others can point to inputs that weren't made by hand.
+    pub fn most_common_hold(&self) -> Option<usize> {
+        self.hold_times
+            .iter()
+            .max_by(|(a_len, a_count), (b_len, b_count)| {
+                a_count.cmp(b_count).then(b_len.cmp(a_len))
+            })
+            .map(|(&len, _)| len)
+    }
+}
+
+/// Input statistics of a single controller.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct InputStats {
+    /// Number of frames.
+    pub frames: usize,
+    /// Statistics of each button, in the bit order of [`Buttons`].
+    /// - The reserved bits are left out, as they mark resets rather than buttons, see [`M64::reset_frames`].
+    pub buttons: Vec<ButtonStats>,
+    /// Total number of button presses, which is the sum of the presses in `buttons`.
+    pub total_presses: usize,
+    /// Average button presses per second.
+    pub presses_per_second: f64,
+    /// Number of frames for each stick displacement from the center,
+    /// with each bucket covering [`STICK_HISTOGRAM_BUCKET`] units.
+    pub stick_histogram: Vec<usize>,
+}
+
+impl InputStats {
+    /// Computes the statistics of the inputs of a single controller.
+    /// - `frames_per_second` is used for the presses per second.
+    pub fn from_inputs<I>(inputs: I, frames_per_second: f64) -> Self
+    where
+        I: IntoIterator<Item = Input>,
+    {
+        PartialStats::from_inputs(inputs).finish(frames_per_second)
+    }
+
+    /// Returns the statistics of a single button.
+    pub fn button(&self, button: Buttons) -> Option<&ButtonStats> {
+        self.buttons.iter().find(|stats| stats.button == button)
+    }
+}
+
+/// The difference in presses of a single button between two movies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ButtonDelta {
+    /// The button.
+    pub button: Buttons,
+    /// Presses in the first movie, summed over every controller.
+    pub presses: usize,
+    /// Presses in the second movie, summed over every controller.
+    pub other_presses: usize,
+}
+
+impl ButtonDelta {
+    /// Returns how many more presses the second movie has, negative if it has fewer.
+    pub fn delta(&self) -> i64 {
+        self.other_presses as i64 - self.presses as i64
+    }
+}
+
+/// Comparison of two movies, see [`compare`].
+/// - Differences are from the first movie to the second, so an improvement has a negative frame difference.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Comparison {
+    /// Number of frames in the first movie.
+    pub frames: usize,
+    /// Number of frames in the second movie.
+    pub other_frames: usize,
+    /// Difference in frames.
+    pub frame_difference: i64,
+    /// Difference in VIs, which is what the movie time is based on.
+    pub vi_difference: i64,
+    /// Difference in the whole rerecord counts, see [`M64::total_rerecords`], saturating at the bounds of `i64`.
+    pub rerecord_difference: i64,
+    /// Difference in total button presses.
+    pub total_presses_difference: i64,
+    /// Press counts of each button, in the bit order of [`Buttons`], leaving out buttons pressed in neither movie and the reserved bits.
+    pub buttons: Vec<ButtonDelta>,
+    /// The first frame where the inputs differ, or `None` if they're the same.
+    pub first_divergence: Option<usize>,
+}
+
+/// Compares the lengths, rerecords and button presses of two movies.
+pub fn compare(a: &M64, b: &M64) -> Comparison {
+    let (a_stats, b_stats) = (a.stats(), b.stats());
+    let presses = |stats: &[InputStats], button| {
+        stats
+            .iter()
+            .filter_map(|stats| stats.button(button))
+            .map(|stats| stats.presses)
+            .sum::<usize>()
+    };
+    let buttons = (0..16)
+        .map(|bit| Buttons::from_bits_retain(1 << bit))
+        .filter(|&button| !Buttons::RESERVED.contains(button))
+        .map(|button| ButtonDelta {
+            button,
+            presses: presses(&a_stats, button),
+            other_presses: presses(&b_stats, button),
+        })
+        .filter(|delta| delta.presses != 0 || delta.other_presses != 0)
+        .collect::<Vec<_>>();
+
+    let (frames, other_frames) = (a.frame_count(), b.frame_count());
+    let first_divergence = a
+        .first_divergence_with_context(b, 0)
+        .map(|divergence| divergence.frame);
+
+    Comparison {
+        frames,
+        other_frames,
+        frame_difference: other_frames as i64 - frames as i64,
+        vi_difference: b.vi_frames as i64 - a.vi_frames as i64,
+        rerecord_difference: saturate(b.total_rerecords() as i128 - a.total_rerecords() as i128),
+        total_presses_difference: buttons.iter().map(ButtonDelta::delta).sum(),
+        buttons,
+        first_divergence,
+    }
+}
+
+impl M64 {
+    /// Computes the input statistics of each controller.
+    pub fn stats(&self) -> Vec<InputStats> {
+        self.stats_in_range(..)
+    }
+
+    /// Computes the input statistics of each controller over a range of frames.
+    /// - Holds are cut off at the ends of the range, and the range is clamped to the frames of the movie.
+    /// - With the `rayon` feature, long movies are gone through in parallel.
+    pub fn stats_in_range<R>(&self, frames: R) -> Vec<InputStats>
+    where
+        R: RangeBounds<usize>,
+    {
+        let frames_per_second = self.input_frames_per_second();
+        let stride = self.samples_per_frame();
+        let inputs = &self.inputs[self.frame_range_to_samples(frames)];
+
+        (0..stride)
+            .map(|controller| {
+                controller_stats(inputs, controller, stride).finish(frames_per_second)
+            })
+            .collect()
+    }
+}
+
+/// Computes the statistics of the inputs of a single controller, out of the inputs of `stride` controllers.
+#[cfg(not(feature = "rayon"))]
+fn controller_stats(inputs: &[Input], controller: usize, stride: usize) -> PartialStats {
+    PartialStats::from_inputs(inputs.iter().skip(controller).step_by(stride).copied())
+}
+
+/// Computes the statistics of the inputs of a single controller, out of the inputs of `stride` controllers.
+/// - Chunks of the inputs are gone through in parallel, and merged in order.
+#[cfg(feature = "rayon")]
+fn controller_stats(inputs: &[Input], controller: usize, stride: usize) -> PartialStats {
+    let inputs = inputs
+        .iter()
+        .skip(controller)
+        .step_by(stride)
+        .copied()
+        .collect::<Vec<_>>();
+
+    inputs
+        .par_chunks(PARALLEL_MIN_SAMPLES)
+        .map(|chunk| PartialStats::from_inputs(chunk.iter().copied()))
+        .collect::<Vec<_>>()
+        .into_iter()
+        .fold(PartialStats::default(), PartialStats::merge)
+}
+
+/// Statistics of a run of frames, which can be merged with the statistics of the frames after it.
+#[derive(Default)]
+struct PartialStats {
+    frames: usize,
+    /// Length of every hold of each button in order, in the bit order of [`Buttons`].
+    holds: [Vec<usize>; 16],
+    /// The buttons held on the first frame, whose first hold can continue a hold of the frames before.
+    held_first: Buttons,
+    /// The buttons held on the last frame, whose last hold can continue into the frames after.
+    held_last: Buttons,
+    stick_histogram: Vec<usize>,
+}
+
+impl PartialStats {
+    fn from_inputs<I>(inputs: I) -> Self
+    where
+        I: IntoIterator<Item = Input>,
+    {
+        let mut stats = PartialStats::default();
+        let mut previous = Buttons::empty();
+
+        for input in inputs {
+            let pressed = input.buttons();
+            if stats.frames == 0 {
+                stats.held_first = pressed;
+            }
+            stats.frames += 1;
+
+            for (bit, holds) in stats.holds.iter_mut().enumerate() {
+                let button = Buttons::from_bits_retain(1 << bit);
+                if !pressed.contains(button) {
+                    continue;
+                }
+                match holds.last_mut() {
+                    Some(held) if previous.contains(button) => *held += 1,
+                    _ => holds.push(1),
+                }
+            }
+            previous = pressed;
+
+            let bucket = input.stick_polar().magnitude as usize / STICK_HISTOGRAM_BUCKET;
+            if stats.stick_histogram.len() <= bucket {
+                stats.stick_histogram.resize(bucket + 1, 0);
+            }
+            stats.stick_histogram[bucket] += 1;
+        }
+
+        stats.held_last = previous;
+        stats
+    }
+
+    /// Adds the statistics of the frames right after these, joining holds that carry over.
+    #[cfg(feature = "rayon")]
+    fn merge(mut self, other: PartialStats) -> Self {
+        if self.frames == 0 {
+            return other;
+        }
+        if other.frames == 0 {
+            return self;
+        }
+
+        let carried = self.held_last & other.held_first;
+        for (bit, (holds, other_holds)) in self.holds.iter_mut().zip(other.holds).enumerate() {
+            let mut other_holds = other_holds.into_iter();
+            if carried.contains(Buttons::from_bits_retain(1 << bit)) {
+                *holds.last_mut().unwrap() += other_holds.next().unwrap();
+            }
+            holds.extend(other_holds);
+        }
+
+        if self.stick_histogram.len() < other.stick_histogram.len() {
+            self.stick_histogram.resize(other.stick_histogram.len(), 0);
+        }
+        for (count, other_count) in self.stick_histogram.iter_mut().zip(other.stick_histogram) {
+            *count += other_count;
+        }
+
+        PartialStats {
+            frames: self.frames + other.frames,
+            held_last: other.held_last,
+            ..self
+        }
+    }
+
+    fn finish(self, frames_per_second: f64) -> InputStats {
+        let buttons = self
+            .holds
+            .into_iter()
+            .enumerate()
+            .map(|(bit, holds)| (Buttons::from_bits_retain(1 << bit), holds))
+            .filter(|(button, _)| !Buttons::RESERVED.contains(*button))
+            .map(|(button, holds)| {
+                let mut hold_times = BTreeMap::new();
+                for &held in &holds {
+                    *hold_times.entry(held).or_default() += 1;
+                }
+                ButtonStats {
+                    button,
+                    presses: holds.len(),
+                    held_frames: holds.iter().sum(),
+                    hold_times,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let total_presses = buttons.iter().map(|stats| stats.presses).sum();
+        let seconds = self.frames as f64 / frames_per_second;
+        let presses_per_second = if seconds > 0.0 {
+            total_presses as f64 / seconds
+        } else {
+            0.0
+        };
+
+        InputStats {
+            frames: self.frames,
+            buttons,
+            total_presses,
+            presses_per_second,
+            stick_histogram: self.stick_histogram,
+        }
+    }
+}
+
+/// Converts a difference to an `i64`, saturating at its bounds.
+fn saturate(difference: i128) -> i64 {
+    difference.clamp(i64::MIN.into(), i64::MAX.into()) as i64
+}
@@ -0,0 +1,135 @@
+//! Contains helpers for analog stick math, using polar coordinates and the N64 controller's octagonal gate.
+use std::f64::consts::{FRAC_PI_4, TAU};
+
+use crate::controller::Input;
+
+/// An analog stick position in polar coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct StickPolar {
+    /// Angle in radians, counter-clockwise from the positive X-axis (right), in range `[0, 2π)`.
+    pub angle: f64,
+    /// Distance from the center of the stick.
+    pub magnitude: f64,
+}
+
+impl StickPolar {
+    /// Creates a polar position from stick X and Y values.
+    pub fn from_xy(x: i8, y: i8) -> Self {
+        let (x, y) = (x as f64, y as f64);
+        StickPolar {
+            angle: y.atan2(x).rem_euclid(TAU),
+            magnitude: x.hypot(y),
+        }
+    }
+
+    /// Returns the position as cartesian coordinates, without rounding.
+    pub fn to_xy(self) -> (f64, f64) {
+        (
+            self.angle.cos() * self.magnitude,
+            self.angle.sin() * self.magnitude,
+        )
+    }
+}
+
+/// The octagonal gate of the N64 controller, which limits how far the analog stick can physically move.
+/// - The gate is an octagon with its corners on the cardinal and diagonal directions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OctagonGate {
+    /// Maximum value reachable on a single axis when holding a cardinal direction.
+    pub cardinal: i8,
+    /// Maximum value reachable on both axes when holding a diagonal direction.
+    pub diagonal: i8,
+}
+
+impl Default for OctagonGate {
+    /// Typical values for an original N64 controller.
+    fn default() -> Self {
+        OctagonGate {
+            cardinal: 80,
+            diagonal: 70,
+        }
+    }
+}
+
+impl OctagonGate {
+    /// Returns the corners of the gate in counter-clockwise order, starting from the right.
+    pub(crate) fn corners(&self) -> [(f64, f64); 8] {
+        let c = self.cardinal as f64;
+        let d = self.diagonal as f64;
+
+        [
+            (c, 0.0),
+            (d, d),
+            (0.0, c),
+            (-d, d),
+            (-c, 0.0),
+            (-d, -d),
+            (0.0, -c),
+            (d, -d),
+        ]
+    }
+
+    /// Returns the maximum magnitude reachable at the given angle in radians.
+    pub fn max_magnitude(&self, angle: f64) -> f64 {
+        let angle = angle.rem_euclid(TAU);
+        let corners = self.corners();
+        // each edge of the octagon covers 45 degrees
+        let edge = ((angle / FRAC_PI_4) as usize).min(7);
+        let (x1, y1) = corners[edge];
+        let (x2, y2) = corners[(edge + 1) % 8];
+        let (dx, dy) = (angle.cos(), angle.sin());
+
+        // intersection of the ray from the center and the edge
+        let denominator = dx * (y2 - y1) - dy * (x2 - x1);
+        if denominator == 0.0 {
+            return 0.0;
+        }
+        (x1 * (y2 - y1) - y1 * (x2 - x1)) / denominator
+    }
+
+    /// Returns if the stick position is reachable through the gate.
+    pub fn contains(&self, x: i8, y: i8) -> bool {
+        let polar = StickPolar::from_xy(x, y);
+        polar.magnitude <= self.max_magnitude(polar.angle) + 1e-9
+    }
+
+    /// Clamps the magnitude of the polar position to what is reachable through the gate.
+    pub fn clamp(&self, polar: StickPolar) -> StickPolar {
+        StickPolar {
+            angle: polar.angle.rem_euclid(TAU),
+            magnitude: polar.magnitude.clamp(0.0, self.max_magnitude(polar.angle)),
+        }
+    }
+
+    /// Snaps the polar position to the closest stick X and Y values reachable through the gate.
+    pub fn snap(&self, polar: StickPolar) -> (i8, i8) {
+        let (target_x, target_y) = self.clamp(polar).to_xy();
+        let distance = |x: i8, y: i8| (x as f64 - target_x).hypot(y as f64 - target_y);
+
+        let mut closest = (0, 0);
+        for x in (target_x.floor() as i32 - 1)..=(target_x.ceil() as i32 + 1) {
+            for y in (target_y.floor() as i32 - 1)..=(target_y.ceil() as i32 + 1) {
+                let (Ok(x), Ok(y)) = (i8::try_from(x), i8::try_from(y)) else {
+                    continue;
+                };
+                if self.contains(x, y) && distance(x, y) < distance(closest.0, closest.1) {
+                    closest = (x, y);
+                }
+            }
+        }
+
+        closest
+    }
+}
+
+impl Input {
+    /// Returns the analog stick position in polar coordinates.
+    pub fn stick_polar(&self) -> StickPolar {
+        StickPolar::from_xy(self.x_axis, self.y_axis)
+    }
+
+    /// Sets the analog stick to the closest position reachable through the gate for the given polar position.
+    pub fn set_stick_polar(&mut self, polar: StickPolar, gate: &OctagonGate) {
+        (self.x_axis, self.y_axis) = gate.snap(polar);
+    }
+}
@@ -0,0 +1,190 @@
+//! Containers for the input samples of a movie.
+//!
+//! [`M64`](crate::M64) is generic over the container its inputs are kept in, through the [`InputStorage`] trait.
+//! - `Vec<Input>` is the default, with every sample decoded up front.
+//! - `Vec<RawInput>` and `Box<[RawInput]>` keep the raw 4 byte samples and decode them on access,
+//!   taking less than a quarter of the memory.
+//!
+//! Other containers can be used by implementing the trait.
+use alloc::{boxed::Box, vec::Vec};
+
+use crate::controller::{Input, RawInput};
+
+/// A container of input samples in file order.
+pub trait InputStorage {
+    /// Creates the storage from input samples in order.
+    fn from_inputs<I>(inputs: I) -> Self
+    where
+        I: IntoIterator<Item = Input>,
+        Self: Sized;
+
+    /// Creates the storage from the input section of a file, which holds whole little endian samples.
+    /// - `input_frames` is the sample count from the header, which can be wrong and is only a hint for the capacity.
+    fn from_le_bytes(bytes: &[u8], input_frames: u32) -> Self
+    where
+        Self: Sized,
+    {
+        let _ = input_frames;
+        Self::from_inputs(bytes.chunks_exact(4).map(decode_sample))
+    }
+
+    /// Returns the number of input samples.
+    fn sample_count(&self) -> usize;
+
+    /// Returns the input sample at `index`, or `None` if it's out of range.
+    fn sample(&self, index: usize) -> Option<Input>;
+
+    /// Returns the raw value of the input sample at `index`, or `None` if it's out of range.
+    fn raw_sample(&self, index: usize) -> Option<u32> {
+        self.sample(index).map(u32::from)
+    }
+
+    /// Replaces the input sample at `index`, returning the previous one, or `None` if it's out of range.
+    fn replace_sample(&mut self, index: usize, input: Input) -> Option<Input>;
+
+    /// Returns every sample as a slice of raw samples, if the storage keeps them that way.
+    /// - Lets the samples be written in bulk instead of one at a time.
+    fn raw_samples(&self) -> Option<&[RawInput]> {
+        None
+    }
+}
+
+impl InputStorage for Vec<Input> {
+    fn from_inputs<I>(inputs: I) -> Self
+    where
+        I: IntoIterator<Item = Input>,
+    {
+        inputs.into_iter().collect()
+    }
+
+    fn from_le_bytes(bytes: &[u8], input_frames: u32) -> Self {
+        // pre-allocates for the header sample count, capped by the samples the data can hold in case the header is bogus
+        let mut inputs = Vec::with_capacity((input_frames as usize).min(bytes.len() / 4));
+        decode_inputs(bytes, &mut inputs);
+        inputs
+    }
+
+    fn sample_count(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    fn sample(&self, index: usize) -> Option<Input> {
+        self.as_slice().get(index).copied()
+    }
+
+    fn replace_sample(&mut self, index: usize, input: Input) -> Option<Input> {
+        self.get_mut(index)
+            .map(|sample| core::mem::replace(sample, input))
+    }
+}
+
+impl InputStorage for Vec<RawInput> {
+    fn from_inputs<I>(inputs: I) -> Self
+    where
+        I: IntoIterator<Item = Input>,
+    {
+        inputs.into_iter().map(RawInput::from).collect()
+    }
+
+    fn from_le_bytes(bytes: &[u8], _: u32) -> Self {
+        decode_raw_inputs(bytes)
+    }
+
+    fn sample_count(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    fn sample(&self, index: usize) -> Option<Input> {
+        self.as_slice().get(index).map(|&raw| raw.into())
+    }
+
+    fn raw_sample(&self, index: usize) -> Option<u32> {
+        self.as_slice().get(index).map(|raw| raw.0)
+    }
+
+    fn replace_sample(&mut self, index: usize, input: Input) -> Option<Input> {
+        self.get_mut(index)
+            .map(|sample| core::mem::replace(sample, input.into()).into())
+    }
+
+    fn raw_samples(&self) -> Option<&[RawInput]> {
+        Some(self)
+    }
+}
+
+impl InputStorage for Box<[RawInput]> {
+    fn from_inputs<I>(inputs: I) -> Self
+    where
+        I: IntoIterator<Item = Input>,
+    {
+        Vec::<RawInput>::from_inputs(inputs).into_boxed_slice()
+    }
+
+    fn from_le_bytes(bytes: &[u8], input_frames: u32) -> Self {
+        Vec::<RawInput>::from_le_bytes(bytes, input_frames).into_boxed_slice()
+    }
+
+    fn sample_count(&self) -> usize {
+        <[RawInput]>::len(self)
+    }
+
+    fn sample(&self, index: usize) -> Option<Input> {
+        <[RawInput]>::get(self, index).map(|&raw| raw.into())
+    }
+
+    fn raw_sample(&self, index: usize) -> Option<u32> {
+        <[RawInput]>::get(self, index).map(|raw| raw.0)
+    }
+
+    fn replace_sample(&mut self, index: usize, input: Input) -> Option<Input> {
+        <[RawInput]>::get_mut(self, index)
+            .map(|sample| core::mem::replace(sample, input.into()).into())
+    }
+
+    fn raw_samples(&self) -> Option<&[RawInput]> {
+        Some(self)
+    }
+}
+
+/// Decodes the aligned input section into `inputs`.
+#[cfg(not(feature = "rayon"))]
+fn decode_inputs(bytes: &[u8], inputs: &mut Vec<Input>) {
+    inputs.extend(bytes.chunks_exact(4).map(decode_sample));
+}
+
+/// Decodes the aligned input section into `inputs` in parallel, in chunks of at least [`PARALLEL_MIN_SAMPLES`] samples.
+#[cfg(feature = "rayon")]
+fn decode_inputs(bytes: &[u8], inputs: &mut Vec<Input>) {
+    use rayon::prelude::*;
+
+    inputs.par_extend(
+        bytes
+            .par_chunks_exact(4)
+            .with_min_len(PARALLEL_MIN_SAMPLES)
+            .map(decode_sample),
+    );
+}
+
+/// The fewest input samples handled by a single thread, so short movies aren't split up for nothing.
+#[cfg(feature = "rayon")]
+pub(crate) const PARALLEL_MIN_SAMPLES: usize = 0x4000;
+
+/// Decodes the aligned input section into raw samples.
+#[cfg(not(feature = "bytemuck"))]
+fn decode_raw_inputs(bytes: &[u8]) -> Vec<RawInput> {
+    bytes.chunks_exact(4).map(decode_raw_sample).collect()
+}
+
+/// Decodes the aligned input section into raw samples, copying the bytes in one go on little endian targets.
+#[cfg(feature = "bytemuck")]
+fn decode_raw_inputs(bytes: &[u8]) -> Vec<RawInput> {
+    crate::controller::raw_inputs_from_le_bytes(bytes).into_owned()
+}
+
+fn decode_sample(bytes: &[u8]) -> Input {
+    crate::controller::decode_sample(decode_raw_sample(bytes).0)
+}
+
+fn decode_raw_sample(bytes: &[u8]) -> RawInput {
+    RawInput(u32::from_le_bytes(bytes.try_into().unwrap()))
+}
@@ -0,0 +1,96 @@
+//! A human-readable summary of a movie, as shown by movie info tools.
+use std::fmt;
+
+use crate::m64::M64;
+
+/// A multi-line summary of a movie, created with [`M64::summary`].
+/// - Shows the ROM, region, length, rerecords, start type, Wii VC timing, resets, controller layout, author, description and plugins.
+/// - Every line ends with a newline, and the values are aligned after the field names.
+#[derive(Debug, Clone, Copy)]
+pub struct Summary<'a>(&'a M64);
+
+impl M64 {
+    /// Returns a human-readable summary of the movie, which is formatted with `Display`.
+    pub fn summary(&self) -> Summary<'_> {
+        Summary(self)
+    }
+}
+
+impl fmt::Display for Summary<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let m64 = self.0;
+        let mut line = |name: &str, value: &dyn fmt::Display| writeln!(f, "{name:<14}{value}");
+
+        line(
+            "ROM:",
+            &format_args!(
+                "{} (CRC32 {:08X})",
+                m64.rom_internal_name.as_str(),
+                m64.rom_crc_32
+            ),
+        )?;
+        line(
+            "Region:",
+            &format_args!(
+                "{:?}, {:?} at {} fps",
+                m64.country_code(),
+                m64.region(),
+                m64.fps
+            ),
+        )?;
+        line(
+            "Length:",
+            &format_args!("{} ({} VIs)", m64.length_string(), m64.vi_frames),
+        )?;
+        line(
+            "Input frames:",
+            &format_args!(
+                "{} ({} samples)",
+                m64.header_frame_count(),
+                m64.input_frames
+            ),
+        )?;
+        line("Rerecords:", &m64.total_rerecords())?;
+        line("Start type:", &format_args!("{:?}", m64.movie_start_type))?;
+        if m64.is_wii_vc() {
+            line("Timing:", &"Wii VC")?;
+        }
+        let mut resets = m64.reset_frames();
+        if let Some(first) = resets.next() {
+            line(
+                "Resets:",
+                &format_args!("{}, first on frame {first}", resets.count() + 1),
+            )?;
+        }
+
+        let mut present = m64
+            .controller_flags
+            .iter()
+            .enumerate()
+            .filter(|(_, flags)| flags.controller_present)
+            .peekable();
+        if present.peek().is_none() {
+            line("Controllers:", &"none")?;
+        }
+        for (port, flags) in present {
+            let pak = match (flags.has_mempak, flags.has_rumblepak) {
+                (true, true) => ", mempak and rumble pak",
+                (true, false) => ", mempak",
+                (false, true) => ", rumble pak",
+                (false, false) => "",
+            };
+            line(
+                &format!("Controller {}:", port + 1),
+                &format_args!("present{pak}"),
+            )?;
+        }
+
+        line("Author:", &m64.author.as_str())?;
+        line("Description:", &m64.description.as_str())?;
+        line("Video plugin:", &m64.video_plugin.as_str())?;
+        line("Sound plugin:", &m64.sound_plugin.as_str())?;
+        line("Input plugin:", &m64.input_plugin.as_str())?;
+        line("RSP plugin:", &m64.rsp_plugin.as_str())?;
+        line("UID:", &m64.uid)
+    }
+}
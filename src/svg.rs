@@ -0,0 +1,109 @@
+//! Rendering the analog stick path as an SVG image.
+//!
+//! The image is centered on the neutral stick position with one unit for each stick value, and positive Y pointing up.
+//! It holds the octagon gate, the axes, the path of the stick in frame order and a point for each distinct position.
+use std::{collections::HashMap, fmt::Write, ops::RangeBounds};
+
+use crate::{m64::M64, stick::OctagonGate};
+
+/// Options for rendering the analog stick path.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StickSvgOptions {
+    /// The gate drawn behind the path.
+    pub gate: OctagonGate,
+    /// Width and height of the image in pixels.
+    pub size: u32,
+    /// Colors the points by how many frames the stick was held there, from blue for the fewest to red for the most.
+    pub heatmap: bool,
+}
+
+impl Default for StickSvgOptions {
+    fn default() -> Self {
+        StickSvgOptions {
+            gate: OctagonGate::default(),
+            size: 512,
+            heatmap: false,
+        }
+    }
+}
+
+impl M64 {
+    /// Renders the analog stick path of a controller over a range of frames as an SVG image.
+    /// - The range is clamped to the frames of the movie.
+    /// - Only the gate and axes are drawn if `controller` is out of range of the controller count.
+    pub fn stick_svg<R>(&self, controller: usize, frames: R, options: &StickSvgOptions) -> String
+    where
+        R: RangeBounds<usize>,
+    {
+        let stride = self.samples_per_frame();
+        let samples = &self.inputs[self.frame_range_to_samples(frames)];
+        let skip = if controller < stride {
+            controller
+        } else {
+            samples.len()
+        };
+        let positions = samples
+            .iter()
+            .skip(skip)
+            .step_by(stride)
+            .map(|input| (input.x_axis as i32, -(input.y_axis as i32)))
+            .collect::<Vec<_>>();
+
+        let mut svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{0}" viewBox="-128 -128 256 256">"#,
+            options.size
+        );
+        svg.push_str(r#"<rect x="-128" y="-128" width="256" height="256" fill="white"/>"#);
+
+        let gate = options
+            .gate
+            .corners()
+            .iter()
+            .map(|(x, y)| format!("{x},{}", -y))
+            .collect::<Vec<_>>()
+            .join(" ");
+        write!(
+            svg,
+            r##"<polygon points="{gate}" fill="none" stroke="#888" stroke-width="1"/>"##
+        )
+        .unwrap();
+        svg.push_str(r##"<path d="M-128 0H128M0 -128V128" stroke="#ccc" stroke-width="0.5"/>"##);
+
+        if !positions.is_empty() {
+            let path = positions
+                .iter()
+                .map(|(x, y)| format!("{x} {y}"))
+                .collect::<Vec<_>>()
+                .join("L");
+            write!(
+                svg,
+                r##"<path d="M{path}" fill="none" stroke="#36c" stroke-width="0.5" stroke-opacity="0.6"/>"##
+            )
+            .unwrap();
+        }
+
+        // points in the order they're first reached, so the output is deterministic
+        let mut dwell = HashMap::new();
+        let mut points = Vec::new();
+        for &position in &positions {
+            *dwell.entry(position).or_insert_with(|| {
+                points.push(position);
+                0
+            }) += 1;
+        }
+        let max_dwell = dwell.values().copied().max().unwrap_or(1);
+
+        for (x, y) in points {
+            let color = if options.heatmap {
+                let heat = (dwell[&(x, y)] - 1) as f64 / (max_dwell - 1).max(1) as f64;
+                format!("hsl({:.0},100%,45%)", 240.0 * (1.0 - heat))
+            } else {
+                "#c33".to_string()
+            };
+            write!(svg, r#"<circle cx="{x}" cy="{y}" r="1.5" fill="{color}"/>"#).unwrap();
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
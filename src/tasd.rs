@@ -0,0 +1,259 @@
+//! Reading and writing the TASD (TAS Dump) interchange format.
+//!
+//! A TASD file starts with the signature `TASD`, a big endian `u16` version and the key length of 2,
+//! followed by packets that each consist of:
+//! - A 2 byte big endian key.
+//! - A byte holding how many bytes the payload length takes up, followed by the big endian payload length.
+//! - The payload.
+//!
+//! N64 inputs are stored in `INPUT_CHUNK` packets holding the port number followed by 4 bytes for each frame,
+//! which are the same bytes as the input samples of a `.m64` file.
+//! Packets that have no matching `.m64` field are skipped when reading.
+use std::io::{self, Write};
+
+use crate::{
+    controller::{Flags, Input},
+    error::TasdError,
+    m64::M64,
+    padded_string::PaddedString,
+};
+
+/// TASD format version written by this crate.
+pub const TASD_VERSION: u16 = 1;
+
+const SIGNATURE: &[u8; 4] = b"TASD";
+const KEY_LENGTH: u8 = 2;
+
+const CONSOLE_TYPE: u16 = 0x0001;
+const CONSOLE_REGION: u16 = 0x0002;
+const ROM_NAME: u16 = 0x0004;
+const ATTRIBUTION: u16 = 0x0005;
+const EMULATOR_NAME: u16 = 0x0007;
+const TOTAL_FRAMES: u16 = 0x000D;
+const RERECORDS: u16 = 0x000E;
+const PORT_CONTROLLER: u16 = 0x00F0;
+const INPUT_CHUNK: u16 = 0xFE01;
+const COMMENT: u16 = 0xFF01;
+
+const CONSOLE_N64: u8 = 0x03;
+const REGION_NTSC: u8 = 0x01;
+const REGION_PAL: u8 = 0x02;
+const ATTRIBUTION_AUTHOR: u8 = 0x01;
+const CONTROLLER_N64: u16 = 0x0301;
+const CONTROLLER_N64_RUMBLEPAK: u16 = 0x0302;
+const CONTROLLER_N64_MEMPAK: u16 = 0x0303;
+
+impl M64 {
+    /// Writes the movie in the TASD format to the given writer.
+    pub fn write_tasd<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: Write,
+    {
+        writer.write_all(SIGNATURE)?;
+        writer.write_all(&TASD_VERSION.to_be_bytes())?;
+        writer.write_all(&[KEY_LENGTH])?;
+
+        let region = if self.fps == 50 {
+            REGION_PAL
+        } else {
+            REGION_NTSC
+        };
+        write_packet(writer, CONSOLE_TYPE, &[CONSOLE_N64])?;
+        write_packet(writer, CONSOLE_REGION, &[region])?;
+        write_packet(writer, ROM_NAME, self.rom_internal_name.as_str().as_bytes())?;
+        let mut attribution = vec![ATTRIBUTION_AUTHOR];
+        attribution.extend_from_slice(self.author.as_str().as_bytes());
+        write_packet(writer, ATTRIBUTION, &attribution)?;
+        write_packet(writer, EMULATOR_NAME, b"Mupen64")?;
+        write_packet(
+            writer,
+            TOTAL_FRAMES,
+            &(self.frame_count() as u32).to_be_bytes(),
+        )?;
+        // TASD expects a 4 byte big endian count, all 8 bytes are only written if the upper 4 are needed
+        let rerecords = self.total_rerecords().to_be_bytes();
+        let width = if rerecords[..4] == [0; 4] { 4 } else { 8 };
+        write_packet(writer, RERECORDS, &rerecords[8 - width..])?;
+        write_packet(writer, COMMENT, self.description.as_str().as_bytes())?;
+
+        for (controller, port) in self.controller_ports().into_iter().enumerate() {
+            let flags = self.controller_flags[port];
+            let controller_type = if flags.has_mempak {
+                CONTROLLER_N64_MEMPAK
+            } else if flags.has_rumblepak {
+                CONTROLLER_N64_RUMBLEPAK
+            } else {
+                CONTROLLER_N64
+            };
+            let mut payload = vec![port as u8 + 1];
+            payload.extend_from_slice(&controller_type.to_be_bytes());
+            write_packet(writer, PORT_CONTROLLER, &payload)?;
+
+            let mut payload = vec![port as u8 + 1];
+            for input in self.inputs_for_controller(controller) {
+                payload.extend_from_slice(&u32::from(input).to_le_bytes());
+            }
+            write_packet(writer, INPUT_CHUNK, &payload)?;
+        }
+
+        Ok(())
+    }
+
+    /// Creates an instance of `M64` from TASD data.
+    /// - Header fields that TASD doesn't store are left at their [default](M64::default) values.
+    /// - Ports with fewer frames of inputs than the longest one are neutral on the frames they're missing.
+    pub fn from_tasd(data: &[u8]) -> Result<Self, TasdError> {
+        let header = data.get(..7).ok_or(TasdError::UnexpectedEof)?;
+        if &header[..4] != SIGNATURE {
+            return Err(TasdError::InvalidSignature);
+        }
+        if header[6] != KEY_LENGTH {
+            return Err(TasdError::UnsupportedKeyLength(header[6]));
+        }
+
+        let mut m64 = M64::default();
+        let mut controller_flags = Flags::from_u32(0);
+        // input streams for each port
+        let mut streams: [Vec<Input>; 4] = Default::default();
+        let mut data = &data[7..];
+
+        while !data.is_empty() {
+            let (key, payload, rest) = read_packet(data)?;
+            data = rest;
+
+            match key {
+                CONSOLE_TYPE if payload.first() != Some(&CONSOLE_N64) => {
+                    return Err(TasdError::UnsupportedConsole(
+                        payload.first().copied().unwrap_or_default(),
+                    ));
+                }
+                CONSOLE_REGION => {
+                    m64.fps = if payload.first() == Some(&REGION_PAL) {
+                        50
+                    } else {
+                        60
+                    };
+                }
+                ROM_NAME => m64.rom_internal_name = PaddedString::truncated(&lossy(payload)).0,
+                ATTRIBUTION if payload.first() == Some(&ATTRIBUTION_AUTHOR) => {
+                    m64.author = PaddedString::truncated(&lossy(&payload[1..])).0;
+                }
+                RERECORDS => m64.set_total_rerecords(be_uint(payload)),
+                COMMENT => m64.description = PaddedString::truncated(&lossy(payload)).0,
+                PORT_CONTROLLER => {
+                    let port = port_index(payload)?;
+                    let controller_type = be_uint(&payload[1..]) as u16;
+                    let flags = &mut controller_flags[port];
+                    flags.controller_present = true;
+                    flags.has_mempak = controller_type == CONTROLLER_N64_MEMPAK;
+                    flags.has_rumblepak = controller_type == CONTROLLER_N64_RUMBLEPAK;
+                }
+                INPUT_CHUNK => {
+                    let port = port_index(payload)?;
+                    controller_flags[port].controller_present = true;
+                    streams[port].extend(
+                        payload[1..]
+                            .chunks_exact(4)
+                            .map(|b| Input::from(u32::from_le_bytes(b.try_into().unwrap()))),
+                    );
+                }
+                _ => (),
+            }
+        }
+
+        let streams = streams
+            .into_iter()
+            .zip(controller_flags)
+            .filter(|(_, flags)| flags.controller_present)
+            .map(|(stream, _)| stream)
+            .collect::<Vec<_>>();
+        let frames = streams.iter().map(Vec::len).max().unwrap_or_default();
+        // every frame has a sample for each controller, so the controllers stay interleaved
+        let inputs = (0..frames).flat_map(|frame| {
+            streams
+                .iter()
+                .map(move |stream| stream.get(frame).copied().unwrap_or_default())
+        });
+
+        m64.controller_flags = controller_flags;
+        m64.controller_count = streams.len() as u8;
+        m64.extend(inputs);
+        Ok(m64)
+    }
+}
+
+/// Returns the keys of the packets that have no matching `.m64` field, in the order they appear.
+/// - The emulator name and frame count aren't included, as they're written again from the movie.
+pub(crate) fn skipped_packets(data: &[u8]) -> Result<Vec<u16>, TasdError> {
+    let mut data = data.get(7..).ok_or(TasdError::UnexpectedEof)?;
+    let mut skipped = Vec::new();
+
+    while !data.is_empty() {
+        let (key, payload, rest) = read_packet(data)?;
+        data = rest;
+
+        let mapped = match key {
+            ATTRIBUTION => payload.first() == Some(&ATTRIBUTION_AUTHOR),
+            CONSOLE_TYPE | CONSOLE_REGION | ROM_NAME | EMULATOR_NAME | TOTAL_FRAMES | RERECORDS
+            | COMMENT | PORT_CONTROLLER | INPUT_CHUNK => true,
+            _ => false,
+        };
+        if !mapped {
+            skipped.push(key);
+        }
+    }
+
+    Ok(skipped)
+}
+
+fn write_packet<W>(writer: &mut W, key: u16, payload: &[u8]) -> io::Result<()>
+where
+    W: Write,
+{
+    let len = (payload.len() as u64).to_be_bytes();
+    // smallest number of bytes that can hold the payload length
+    let len_bytes = (8 - len.iter().take_while(|&&b| b == 0).count()).max(1);
+
+    writer.write_all(&key.to_be_bytes())?;
+    writer.write_all(&[len_bytes as u8])?;
+    writer.write_all(&len[8 - len_bytes..])?;
+    writer.write_all(payload)
+}
+
+/// Reads a single packet, returning the key, payload and remaining data.
+fn read_packet(data: &[u8]) -> Result<(u16, &[u8], &[u8]), TasdError> {
+    let key = data.get(..2).ok_or(TasdError::UnexpectedEof)?;
+    let key = u16::from_be_bytes(key.try_into().unwrap());
+    let len_bytes = *data.get(2).ok_or(TasdError::UnexpectedEof)? as usize;
+    if len_bytes > 8 {
+        return Err(TasdError::InvalidPacketLength);
+    }
+
+    let len = data.get(3..3 + len_bytes).ok_or(TasdError::UnexpectedEof)?;
+    let len = usize::try_from(be_uint(len)).map_err(|_| TasdError::InvalidPacketLength)?;
+    let data = &data[3 + len_bytes..];
+    if data.len() < len {
+        return Err(TasdError::UnexpectedEof);
+    }
+
+    Ok((key, &data[..len], &data[len..]))
+}
+
+fn be_uint(bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .take(8)
+        .fold(0, |value, &b| (value << 8) | b as u64)
+}
+
+/// Returns the 0 based port index from the 1 based port number at the start of the payload.
+fn port_index(payload: &[u8]) -> Result<usize, TasdError> {
+    match payload.first() {
+        Some(&port @ 1..=4) => Ok(port as usize - 1),
+        _ => Err(TasdError::InvalidPort),
+    }
+}
+
+fn lossy(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
@@ -0,0 +1,22 @@
+use ::arbitrary::{Arbitrary, Unstructured};
+
+use crate::m64::M64;
+
+#[test]
+fn arbitrary_m64_round_trip() {
+    let data = (0..4096u32)
+        .map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8)
+        .collect::<Vec<_>>();
+    let mut u = Unstructured::new(&data);
+
+    while !u.is_empty() {
+        let m64 = M64::arbitrary(&mut u).unwrap();
+        assert!((1..=4).contains(&m64.controller_count));
+        assert_eq!(m64.input_frames as usize, m64.inputs.len());
+        assert_eq!(m64.inputs.len() % m64.samples_per_frame(), 0);
+
+        let mut written = Vec::new();
+        m64.write_m64(&mut written).unwrap();
+        assert_eq!(M64::from_u8_array(&written).unwrap(), m64);
+    }
+}
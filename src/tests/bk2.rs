@@ -0,0 +1,169 @@
+use std::io::{Cursor, Read};
+
+use zip::ZipArchive;
+
+use crate::{
+    convert::{Bk2File, Converted, TasdFile},
+    detect::{detect_format, Confidence, MovieFileFormat},
+    error::{FieldName, M64ParseError},
+    m64::M64,
+    padded_string::PaddedString,
+};
+
+fn read_entry(zip: &mut ZipArchive<Cursor<Vec<u8>>>, name: &str) -> String {
+    let mut content = String::new();
+    zip.by_name(name)
+        .unwrap()
+        .read_to_string(&mut content)
+        .unwrap();
+    content
+}
+
+#[test]
+fn write_bk2() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64").to_vec();
+    let m64 = M64::from_u8_array(&file).unwrap();
+    let mut bk2 = Cursor::new(Vec::new());
+    m64.write_bk2(&mut bk2).unwrap();
+
+    let mut zip = ZipArchive::new(Cursor::new(bk2.into_inner())).unwrap();
+    let header = read_entry(&mut zip, "Header.txt");
+    assert!(header.contains("Platform N64\n"));
+    assert!(header.contains("GameName SM64 Multiplayer\n"));
+    assert!(header.contains("rerecordCount 3377\n"));
+    assert!(header.contains("StartsFromSavestate True\n"));
+    assert!(!header.contains("PAL"));
+
+    let sync_settings = read_entry(&mut zip, "SyncSettings.json");
+    assert!(sync_settings.contains(
+        r#"[{"IsConnected":true,"PakType":1},{"IsConnected":true,"PakType":1},{"IsConnected":false"#
+    ));
+
+    let log = read_entry(&mut zip, "Input Log.txt");
+    let lines = log.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], "[Input]");
+    assert!(lines[1].starts_with("LogKey:#Reset|Power|#P1 X Axis|P1 Y Axis|P1 DPad U|"));
+    assert!(lines[1].contains("|#P2 X Axis|"));
+    assert_eq!(lines.len(), 834 + 3);
+    assert_eq!(*lines.last().unwrap(), "[/Input]");
+
+    let input = m64[(0, 0)];
+    let expected_start = format!("|..|{:>5},{:>5},", input.x_axis, input.y_axis);
+    assert!(lines[2].starts_with(&expected_start));
+    assert_eq!(lines[2].len(), 4 + (12 + 14 + 1) * 2);
+}
+
+#[test]
+fn bk2_round_trip() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64").to_vec();
+    let mut m64 = M64::from_u8_array(&file).unwrap();
+    // the input log only holds whole frames
+    m64.inputs.pop();
+    let mut bk2 = Cursor::new(Vec::new());
+    m64.write_bk2(&mut bk2).unwrap();
+
+    bk2.set_position(0);
+    let (imported, report) = M64::read_bk2(bk2).unwrap();
+    assert_eq!(imported.inputs, m64.inputs);
+    assert_eq!(imported.controller_count, 2);
+    assert_eq!(imported.controller_flags, m64.controller_flags);
+    assert_eq!(imported.rerecords, m64.rerecords);
+    assert_eq!(imported.rom_internal_name, m64.rom_internal_name);
+    assert_eq!(imported.author, m64.author);
+    assert_eq!(imported.movie_start_type, m64.movie_start_type);
+    assert_eq!(imported.input_frames, 1666);
+    assert_eq!(imported.vi_frames, 833);
+
+    assert!(report.truncated_fields.is_empty());
+    assert!(report.reset_frames.is_empty());
+    assert_eq!(
+        report.unmapped_header,
+        vec![
+            ("MovieVersion".to_string(), "BizHawk v2.0.0".to_string()),
+            ("Platform".to_string(), "N64".to_string()),
+            ("Core".to_string(), "Mupen64Plus".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn bk2_header_values() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64").to_vec();
+    let mut m64 = M64::from_u8_array(&file).unwrap();
+    m64.inputs.pop();
+    m64.fps = 50;
+    m64.author = PaddedString::truncated("someone\nrerecordCount 0\r\nPAL False").0;
+    m64.rom_internal_name = PaddedString::truncated("SM64\rAuthor nobody").0;
+    let mut bk2 = Cursor::new(Vec::new());
+    m64.write_bk2(&mut bk2).unwrap();
+
+    let mut zip = ZipArchive::new(Cursor::new(bk2.get_ref().clone())).unwrap();
+    let header = read_entry(&mut zip, "Header.txt");
+    assert!(header.contains("PAL True\n"));
+    assert!(header.contains("Author someone rerecordCount 0  PAL False\n"));
+    assert!(header.contains("GameName SM64 Author nobody\n"));
+    assert_eq!(header.lines().count(), 8);
+
+    bk2.set_position(0);
+    let (imported, _) = M64::read_bk2(bk2).unwrap();
+    assert_eq!(imported.fps, 50);
+    assert_eq!(imported.total_rerecords(), m64.total_rerecords());
+    assert_eq!(
+        imported.author.to_string(),
+        "someone rerecordCount 0  PAL False"
+    );
+    assert_eq!(imported.rom_internal_name.to_string(), "SM64 Author nobody");
+}
+
+#[test]
+fn detect_bk2() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64").to_vec();
+    let m64 = M64::from_u8_array(&file).unwrap();
+    let mut bk2 = Cursor::new(Vec::new());
+    m64.write_bk2(&mut bk2).unwrap();
+    let bk2 = bk2.into_inner();
+
+    let detected = detect_format(&bk2);
+    assert_eq!(detected.format, MovieFileFormat::Bk2);
+    assert_eq!(detected.confidence, Confidence::High);
+    assert!(matches!(
+        M64::from_u8_array(&bk2),
+        Err(M64ParseError::WrongFormat(MovieFileFormat::Bk2))
+    ));
+}
+
+#[test]
+fn convert_bk2() {
+    let mut m64 = M64::from_u8_array(include_bytes!("./m64s/bitfs_noreds2.m64")).unwrap();
+    m64.inputs.truncate(1666);
+    m64.input_frames = 1666;
+
+    let bk2 = Converted::<Bk2File>::try_from(&m64).unwrap();
+    assert!(!bk2.report.lossy_inputs);
+    assert!(bk2.report.lossy_fields.contains(&FieldName::Description));
+
+    let converted = Converted::<M64>::try_from(&bk2.movie).unwrap();
+    assert_eq!(converted.movie.inputs, m64.inputs);
+    assert!(converted
+        .report
+        .unmapped
+        .contains(&("Core".to_string(), "Mupen64Plus".to_string())));
+
+    let tasd = Converted::<TasdFile>::try_from(&bk2.movie).unwrap();
+    assert_eq!(
+        tasd.report.lossy_fields,
+        vec![FieldName::ViFrames, FieldName::MovieStartType]
+    );
+    assert!(tasd
+        .report
+        .unmapped
+        .contains(&("Platform".to_string(), "N64".to_string())));
+    let back = Converted::<Bk2File>::try_from(&tasd.movie).unwrap();
+    assert_eq!(
+        Converted::<M64>::try_from(&back.movie)
+            .unwrap()
+            .movie
+            .inputs,
+        m64.inputs
+    );
+}
@@ -0,0 +1,41 @@
+use std::borrow::Cow;
+
+use crate::{
+    controller::{raw_inputs_from_le_bytes, raw_inputs_to_le_bytes, RawInput},
+    m64::{LazyM64, M64},
+};
+
+#[test]
+fn bulk_raw_inputs() {
+    let file = include_bytes!("./m64s/120 star tas (2012).m64");
+    let m64 = M64::from_u8_array(file).unwrap();
+    let lazy = LazyM64::from_u8_array_with_storage(file).unwrap();
+    assert!(lazy
+        .inputs
+        .iter()
+        .zip(&m64.inputs)
+        .all(|(&raw, &input)| raw == RawInput::from(input)));
+
+    assert_eq!(lazy.to_bytes(), file);
+    let mut written = Vec::new();
+    lazy.write_m64(&mut written).unwrap();
+    assert_eq!(written, file);
+    assert_eq!(raw_inputs_to_le_bytes(&lazy.inputs), &file[0x400..]);
+
+    // aligned bytes are borrowed, with the trailing byte that isn't a whole sample left out
+    let mut samples = lazy.inputs.clone();
+    samples.push(RawInput(0x1234_5678));
+    let bytes = bytemuck::cast_slice::<RawInput, u8>(&samples);
+    let raw = raw_inputs_from_le_bytes(&bytes[..bytes.len() - 3]);
+    assert!(matches!(raw, Cow::Borrowed(_)));
+    assert_eq!(*raw, *lazy.inputs);
+
+    // misaligned bytes are copied
+    let raw = raw_inputs_from_le_bytes(&bytes[1..]);
+    assert!(matches!(raw, Cow::Owned(_)));
+    assert_eq!(raw.len(), samples.len() - 1);
+    assert_eq!(
+        raw[0].0,
+        u32::from_le_bytes(bytes[1..5].try_into().unwrap())
+    );
+}
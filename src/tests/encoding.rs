@@ -0,0 +1,56 @@
+use crate::{
+    encoding::{decode_text, TextEncoding},
+    error::{FieldName, M64ParseError},
+    m64::M64,
+};
+
+#[test]
+fn decode_field_encodings() {
+    let utf8 = decode_text(b"MKDasher\0\0\0");
+    assert_eq!(utf8.text, "MKDasher");
+    assert_eq!(utf8.encoding, TextEncoding::Utf8);
+
+    // "マリオ" in Shift-JIS
+    let sjis = decode_text(&[0x83, 0x7D, 0x83, 0x8A, 0x83, 0x49, 0, 0]);
+    assert_eq!(sjis.text, "マリオ");
+    assert_eq!(sjis.encoding, TextEncoding::ShiftJis);
+
+    let latin1 = decode_text(b"Pok\xE9mon\0");
+    assert_eq!(latin1.text, "Pokémon");
+    assert_eq!(latin1.encoding, TextEncoding::Latin1);
+    let utf8 = decode_text("Pokémon".as_bytes());
+    assert_eq!(utf8.encoding, TextEncoding::Utf8);
+
+    let utf16 = decode_text(b"A\0u\0t\0h\0\xE9\0r\0\0\0");
+    assert_eq!(utf16.text, "Authér");
+    assert_eq!(utf16.encoding, TextEncoding::Utf16Le);
+
+    let utf16 = decode_text(b"\xFE\xFF\0A\0B\0\0");
+    assert_eq!(utf16.text, "AB");
+    assert_eq!(utf16.encoding, TextEncoding::Utf16Be);
+}
+
+#[test]
+fn parse_with_encoding_detection() {
+    let mut file = include_bytes!("./m64s/bitfs_noreds2.m64").to_vec();
+    file[0x222..0x222 + 222].fill(0);
+    file[0x222..0x228].copy_from_slice(&[0x83, 0x7D, 0x83, 0x8A, 0x83, 0x49]);
+    file[0x300..0x300 + 256].fill(0);
+    file[0x300..0x311].copy_from_slice(b"Caf\xE9 run by Ren\xE9e");
+
+    assert!(matches!(
+        M64::from_u8_array(&file),
+        Err(M64ParseError::InvalidString(FieldName::Author))
+    ));
+
+    let (m64, decoded) = M64::from_u8_array_detect_encoding(&file).unwrap();
+    assert_eq!(
+        decoded,
+        vec![
+            (FieldName::Author, TextEncoding::ShiftJis),
+            (FieldName::Description, TextEncoding::Latin1),
+        ]
+    );
+    assert_eq!(m64.author.as_str(), "マリオ");
+    assert_eq!(m64.description.as_str(), "Café run by Renée");
+}
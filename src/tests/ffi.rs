@@ -0,0 +1,48 @@
+use std::{mem::MaybeUninit, slice};
+
+use crate::ffi::*;
+
+#[test]
+fn ffi_round_trip() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64").to_vec();
+    unsafe {
+        let m64 = m64_parse(file.as_ptr(), file.len());
+        assert!(!m64.is_null());
+        assert_eq!(m64_input_count(m64), 1667);
+
+        let mut header = MaybeUninit::uninit();
+        m64_get_header(m64, header.as_mut_ptr());
+        let mut header = header.assume_init();
+        assert_eq!(header.rerecords, 3377);
+        assert_eq!(header.controller_count, 2);
+        let name = header.rom_internal_name.map(|c| c as u8);
+        assert_eq!(&name[..4], b"SM64");
+
+        let mut bytes_len = 0;
+        let bytes = m64_to_bytes(m64, &mut bytes_len);
+        assert_eq!(slice::from_raw_parts(bytes, bytes_len), file);
+        m64_bytes_free(bytes, bytes_len);
+
+        let mut input = 0;
+        assert!(m64_get_input(m64, 0, &mut input));
+        assert!(m64_set_input(m64, 0, 0x8000));
+        assert!(m64_get_input(m64, 0, &mut input));
+        assert_eq!(input, 0x8000);
+        assert!(!m64_get_input(m64, 1667, &mut input));
+
+        header.rerecords = 1;
+        assert!(m64_set_header(m64, &header));
+        header.movie_start_type = 3;
+        assert!(!m64_set_header(m64, &header));
+        m64_get_header(m64, &mut header);
+        assert_eq!((header.rerecords, header.movie_start_type), (1, 1));
+        m64_free(m64);
+
+        let m64 = m64_new();
+        m64_push_input(m64, 0x8000);
+        assert_eq!(m64_input_count(m64), 1);
+        m64_free(m64);
+
+        assert!(m64_parse(b"M64".as_ptr(), 3).is_null());
+    }
+}
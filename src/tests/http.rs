@@ -0,0 +1,43 @@
+use std::{
+    io::{Read, Write},
+    net::TcpListener,
+    thread,
+};
+
+use crate::{error::HttpError, m64::M64};
+
+/// Serves `body` once for each request, returning the URL of the server.
+fn serve(body: &'static [u8], requests: usize) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/movie.m64", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for stream in listener.incoming().take(requests) {
+            let mut stream = stream.unwrap();
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request).unwrap();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            )
+            .unwrap();
+            stream.write_all(body).unwrap();
+        }
+    });
+    url
+}
+
+#[test]
+fn http_from_url() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64");
+    let url = serve(file, 2);
+
+    assert_eq!(
+        M64::from_url(&url).unwrap(),
+        M64::from_u8_array(file).unwrap()
+    );
+    assert!(matches!(
+        M64::from_url_with_limit(&url, 1024),
+        Err(HttpError::TooLarge(1024))
+    ));
+}
@@ -0,0 +1,178 @@
+use std::{env, fs};
+
+use crate::{
+    error::SidecarError,
+    m64::M64,
+    sidecar::{sidecar_path, Bookmark, Sidecar},
+    srt::Annotation,
+    trailer::URL,
+};
+
+#[test]
+fn json_round_trip() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64").to_vec();
+    let m64 = M64::from_u8_array(&file).unwrap();
+    let json = m64.to_json();
+    assert!(json.contains(r#""movie_start_type":"snapshot""#));
+    assert!(json.contains(r#""rom_internal_name":"SM64 Multiplayer""#));
+
+    let imported = M64::from_json(&json).unwrap();
+    assert_eq!(imported, m64);
+
+    let err = M64::from_json(&json.replace(r#""fps":60"#, r#""fps":600"#)).unwrap_err();
+    assert_eq!(format!("{err}"), "Missing or invalid JSON field fps");
+
+    // extended header and rerecord counts past 32 bits
+    let mut m64 = m64;
+    m64.set_wii_vc(true);
+    m64.set_total_rerecords((1 << 32) + 5);
+    m64.extended.as_mut().unwrap().bruteforce_data = 0xDEADBEEF;
+    let json = m64.to_json();
+    assert!(json.contains(r#""rerecords":4294967301"#));
+    assert!(json.contains(r#""authorship_tag":[77,85,80,78]"#));
+    let imported = M64::from_json(&json).unwrap();
+    assert_eq!(imported, m64);
+    assert_eq!(imported.total_rerecords(), (1 << 32) + 5);
+    assert!(imported.is_wii_vc());
+
+    // metadata block entries, as strings or bytes
+    m64.metadata_block
+        .insert(URL, "https://tasvideos.org")
+        .unwrap();
+    m64.metadata_block.insert("tool.data", [0xFF, 0]).unwrap();
+    let json = m64.to_json();
+    assert!(json.contains(r#""metadata":[["url","https://tasvideos.org"],["tool.data",[255,0]]]"#));
+    assert_eq!(M64::from_json(&json).unwrap(), m64);
+    let err = M64::from_json(&json.replace(r#"[255,0]"#, r#"[256]"#)).unwrap_err();
+    assert_eq!(format!("{err}"), "Missing or invalid JSON field metadata");
+
+    let err = M64::from_json(&json.replace(r#""version":1"#, r#""version":0"#)).unwrap_err();
+    assert_eq!(format!("{err}"), "Missing or invalid JSON field extended");
+}
+
+#[test]
+fn overlay_json_lines() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64").to_vec();
+    let m64 = M64::from_u8_array(&file).unwrap();
+    let mut overlay = Vec::new();
+    m64.write_overlay_json(&mut overlay).unwrap();
+    let overlay = String::from_utf8(overlay).unwrap();
+
+    let lines = overlay.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), m64.frames().count());
+
+    let frame = serde_json::from_str::<serde_json::Value>(lines[60]).unwrap();
+    assert_eq!(frame["frame"], 60);
+    assert_eq!(frame["time_ms"], m64.frame_time(60).as_millis() as u64);
+    let controllers = frame["controllers"].as_array().unwrap();
+    assert_eq!(controllers.len(), 2);
+    let input = m64.inputs[121];
+    assert_eq!(controllers[1]["buttons"]["a"], input.a_button);
+    assert_eq!(controllers[1]["stick"]["x"], input.x_axis);
+
+    // VI frames without input frames make the input frame rate 0
+    let m64 = M64 {
+        input_frames: 0,
+        ..m64
+    };
+    let mut overlay = Vec::new();
+    m64.write_overlay_json(&mut overlay).unwrap();
+    let overlay = String::from_utf8(overlay).unwrap();
+    let frame =
+        serde_json::from_str::<serde_json::Value>(overlay.lines().nth(60).unwrap()).unwrap();
+    assert_eq!(frame["time_ms"], 0);
+}
+
+#[test]
+fn sidecar_round_trip() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64").to_vec();
+    let m64 = M64::from_u8_array(&file).unwrap();
+    let mut sidecar = Sidecar {
+        authors: vec!["Ünïcode Author".to_string(), "Another Author".to_string()],
+        urls: vec!["https://tasvideos.org/".to_string()],
+        comments: vec![Annotation::new(10..20, "first jump")],
+        bookmarks: vec![
+            Bookmark::new(100, "bowser"),
+            Bookmark {
+                frame: 200,
+                label: "star".to_string(),
+                note: Some("grab on the first possible frame".to_string()),
+            },
+        ],
+        ..Default::default()
+    };
+    sidecar
+        .metadata
+        .insert("category".to_string(), "any%".to_string());
+
+    let dir = env::temp_dir().join(format!("m64-sidecar-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let movie_path = dir.join("movie.m64");
+    assert_eq!(sidecar_path(&movie_path), dir.join("movie.m64.json"));
+
+    m64.save_sidecar(&movie_path, &sidecar).unwrap();
+    assert_eq!(m64.load_sidecar(&movie_path).unwrap(), sidecar);
+
+    let other = M64 {
+        uid: m64.uid + 1,
+        ..M64::default()
+    };
+    assert!(matches!(
+        other.load_sidecar(&movie_path),
+        Err(SidecarError::UidMismatch { .. })
+    ));
+    fs::remove_dir_all(dir).unwrap();
+
+    let (sidecar, uid) = Sidecar::from_json(r#"{"version":1,"uid":5}"#).unwrap();
+    assert_eq!((sidecar, uid), (Sidecar::default(), 5));
+    assert!(Sidecar::from_json(r#"{"version":2,"uid":5}"#).is_err());
+}
+
+#[test]
+fn sidecar_bookmarks() {
+    let mut sidecar = Sidecar {
+        comments: vec![
+            Annotation::new(10..20, "jump"),
+            Annotation::new(30..35, "dive"),
+        ],
+        ..Default::default()
+    };
+    sidecar.add_bookmark(Bookmark::new(50, "second"));
+    sidecar.add_bookmark(Bookmark::new(5, "first"));
+    sidecar.add_bookmark(Bookmark::new(32, "middle"));
+    let labels = |sidecar: &Sidecar| {
+        sidecar
+            .bookmarks
+            .iter()
+            .map(|bookmark| (bookmark.frame, bookmark.label.clone()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        labels(&sidecar),
+        [
+            (5, "first".to_string()),
+            (32, "middle".to_string()),
+            (50, "second".to_string())
+        ]
+    );
+    assert_eq!(sidecar.bookmark("second").unwrap().frame, 50);
+    assert_eq!(sidecar.bookmarks_in(0..40).count(), 2);
+
+    sidecar.insert_frames(15, 10);
+    assert_eq!(sidecar.bookmark("middle").unwrap().frame, 42);
+    assert_eq!(sidecar.comments[0].frames, 10..30);
+    assert_eq!(sidecar.comments[1].frames, 40..45);
+
+    let deleted = sidecar.delete_frames(35..50);
+    assert_eq!(deleted, [Bookmark::new(42, "middle")]);
+    assert_eq!(sidecar.bookmark("second").unwrap().frame, 45);
+    assert_eq!(sidecar.comments.len(), 1);
+
+    assert_eq!(sidecar.remove_bookmarks("first"), 1);
+    assert!(sidecar.bookmark("first").is_none());
+
+    let (sidecar, _) =
+        Sidecar::from_json(r#"{"version":1,"uid":5,"bookmarks":[{"frame":3,"name":"legacy"}]}"#)
+            .unwrap();
+    assert_eq!(sidecar.bookmarks, [Bookmark::new(3, "legacy")]);
+}
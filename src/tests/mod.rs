@@ -1,6 +1,98 @@
+use std::{
+    env,
+    f64::consts::{FRAC_PI_2, FRAC_PI_4, PI, SQRT_2},
+    fs,
+    io::Write,
+    time::Duration,
+};
+
+#[cfg(feature = "chrono")]
 use chrono::{TimeZone, Utc};
+use flate2::{write::GzEncoder, Compression};
+
+use crate::{
+    analysis::{
+        duplicate::DuplicateSegment,
+        lag::{LagAnalysisOptions, LagIssue},
+        pattern::InputPredicate,
+        stick::{StickAnalysisOptions, StickIssue},
+    },
+    checksum::DigestAlgorithm,
+    compress::{decode_compressed, encode_compressed},
+    content::ContentEq,
+    controller::{
+        decode_inputs, decode_raw_inputs, encode_inputs, encode_raw_inputs, Buttons, Input,
+        RawInput,
+    },
+    convert::{Converted, TasdFile},
+    csv::MAX_INPUT_GAP,
+    detect::{detect_format, Confidence, DetectedFormat, MovieFileFormat},
+    diff::{DiffOp, DEFAULT_DIVERGENCE_CONTEXT},
+    edit::RerecordPolicy,
+    error::{
+        CsvError, FieldName, InPlaceError, LagAnalysisError, LoadError, M64ParseError,
+        MetadataBlockError, PatchError, ScriptError, Severity,
+    },
+    extended::{ExtendedFlags, ExtendedHeader},
+    format::MovieFormat,
+    frame::Frame,
+    in_place::InPlaceM64,
+    lint::{vault_check, LintRule},
+    m64::{collect_into_m64, MovieStartType, M64},
+    merge::merge3,
+    metadata::M64Metadata,
+    padded_string::PaddedString,
+    parser::{self, ParseField},
+    patch::{InputEdit, Patch},
+    plugin::{Plugin, PluginIdentity, PluginKind},
+    project::{M64Project, DEFAULT_BRANCH},
+    region::{CountryCode, Region, VerticalRate},
+    repair::{Repair, RepairOptions},
+    rom::{normalize_rom, ByteOrder, RomHeader, RomMismatch},
+    save::{save_path, SaveType, SaveWarning},
+    script::{format_script, parse_script},
+    srt::Annotation,
+    st::{SavestateMismatch, StHeader, StMovie},
+    stats::{compare, InputStats},
+    stick::{OctagonGate, StickPolar},
+    storage::InputStorage,
+    svg::StickSvgOptions,
+    timing::Timing,
+    tracked::TrackedM64,
+    trailer::{MetadataBlock, AUTHORS, URL},
+    validate::ValidationIssue,
+};
 
-use crate::{controller::Input, m64::M64};
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+#[cfg(feature = "bk2")]
+mod bk2;
+#[cfg(feature = "bytemuck")]
+mod bytemuck;
+#[cfg(feature = "encoding")]
+mod encoding;
+#[cfg(feature = "ffi")]
+mod ffi;
+#[cfg(feature = "http")]
+mod http;
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(feature = "rayon")]
+mod rayon;
+#[cfg(feature = "serde")]
+mod serde;
+#[cfg(feature = "signing")]
+mod signing;
+#[cfg(feature = "sm64")]
+mod sm64;
+#[cfg(feature = "time")]
+mod time;
+#[cfg(feature = "wasm")]
+mod wasm;
+#[cfg(feature = "watch")]
+mod watch;
 
 #[test]
 fn test_files_parse() {
@@ -22,11 +114,11 @@ fn test_files_parse() {
 
 #[test]
 fn inputs_parse() {
-    let inputs_raw = vec![
+    let inputs_raw = [
         0b00110111_11110110_00000000_10000000u32,
         0b10000011_01111101_00000011_00000000u32,
     ];
-    let inputs = vec![
+    let inputs = [
         Input {
             a_button: true,
             x_axis: -10,
@@ -147,9 +239,2410 @@ fn not_enough_input_data() {
     );
 }
 
+#[cfg(feature = "chrono")]
 #[test]
 fn recording_time_test() {
     let file = include_bytes!("./m64s/120 star tas (2012).m64").to_vec();
     let m64 = M64::from_u8_array(&file).unwrap();
-    assert_eq!(m64.recording_time().unwrap(), Utc.timestamp(1272727295, 0));
+    assert_eq!(
+        m64.recording_time().unwrap(),
+        Utc.timestamp_opt(1272727295, 0).unwrap()
+    );
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn set_recording_time() {
+    let mut m64 = M64::default();
+    let time = Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap();
+    m64.set_recording_time(time).unwrap();
+    assert_eq!(m64.uid, 1704164645);
+    assert_eq!(m64.recording_time().unwrap(), time);
+
+    let before_epoch = Utc.with_ymd_and_hms(1969, 12, 31, 0, 0, 0).unwrap();
+    assert!(m64.set_recording_time(before_epoch).is_err());
+    assert_eq!(m64.uid, 1704164645);
+}
+
+#[test]
+fn controllers_demux() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64").to_vec();
+    let m64 = M64::from_u8_array(&file).unwrap();
+    let controllers = m64.controllers();
+
+    assert_eq!(controllers.len(), 2);
+    assert_eq!(controllers[0].len(), 834);
+    assert_eq!(controllers[1].len(), 833);
+    for (i, input) in m64.inputs.iter().enumerate() {
+        assert_eq!(controllers[i % 2][i / 2], *input);
+    }
+    assert_eq!(m64.inputs_for_controller(2).count(), 0);
+}
+
+#[test]
+fn frames_group_controllers() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64").to_vec();
+    let mut m64 = M64::from_u8_array(&file).unwrap();
+    let frames = m64.frames().collect::<Vec<_>>();
+
+    assert_eq!(frames.len(), 834);
+    assert_eq!(frames[10].controller(0), Some(m64.inputs[20]));
+    assert_eq!(frames[10].controller(1), Some(m64.inputs[21]));
+    assert_eq!(frames.last().unwrap().len(), 1);
+
+    let frame = Frame::from_inputs(&[Input::default(); 2]).unwrap();
+    m64.inputs.truncate(1666);
+    m64.push_frame(frame.clone()).unwrap();
+    assert_eq!(m64.frames().last().unwrap(), frame);
+    assert!(m64.push_frame(Frame::new()).is_err());
+}
+
+#[test]
+fn frame_indexing() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64").to_vec();
+    let mut m64 = M64::from_u8_array(&file).unwrap();
+
+    assert_eq!(m64[5], m64.inputs[10..12]);
+    assert_eq!(m64[(5, 1)], m64.inputs[11]);
+    assert_eq!(m64[833].len(), 1);
+    assert_eq!(m64.get(834), None);
+    assert_eq!(m64.get((833, 1)), None);
+    assert_eq!(m64.get((0, 2)), None);
+
+    m64[(5, 1)] = Input {
+        start: true,
+        ..Default::default()
+    };
+    assert!(m64.inputs[11].start);
+    m64.get_mut(5).unwrap()[0].z_button = true;
+    assert!(m64.inputs[10].z_button);
+}
+
+#[test]
+fn frame_iterator_adapters() {
+    let file = include_bytes!("./m64s/120 star tas (2012).m64").to_vec();
+    let m64 = M64::from_u8_array(&file).unwrap();
+
+    let windows = m64.windows(3).collect::<Vec<_>>();
+    assert_eq!(windows.len(), m64.inputs.len() - 2);
+    assert_eq!(windows[7], &m64.inputs[7..10]);
+
+    // sm64 polls input every 2 vi frames
+    let chunks = m64.chunks_per_second().collect::<Vec<_>>();
+    assert_eq!(chunks[0].len(), 29);
+    assert_eq!(
+        chunks.iter().map(|c| c.len()).sum::<usize>(),
+        m64.inputs.len()
+    );
+
+    let (i, frame) = m64.enumerate_frames().nth(42).unwrap();
+    assert_eq!(i, 42);
+    assert_eq!(frame.controller(0), Some(m64.inputs[42]));
+
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64").to_vec();
+    let m64 = M64::from_u8_array(&file).unwrap();
+    assert_eq!(m64.windows(2).nth(3).unwrap(), &m64.inputs[6..10]);
+}
+
+#[test]
+fn input_runs() {
+    let file = include_bytes!("./m64s/120 star tas (2012).m64").to_vec();
+    let m64 = M64::from_u8_array(&file).unwrap();
+    let runs = m64.runs().collect::<Vec<_>>();
+
+    assert_eq!(
+        runs.iter().map(|(_, len)| len).sum::<usize>(),
+        m64.inputs.len()
+    );
+    assert!(runs.windows(2).all(|w| w[0].0 != w[1].0));
+
+    let decoded = runs
+        .iter()
+        .flat_map(|(input, len)| std::iter::repeat_n(*input, *len))
+        .collect::<Vec<_>>();
+    assert_eq!(decoded, m64.inputs);
+}
+
+#[test]
+fn stick_polar_gate() {
+    let gate = OctagonGate::default();
+
+    let polar = Input {
+        x_axis: 0,
+        y_axis: 50,
+        ..Default::default()
+    }
+    .stick_polar();
+    assert!((polar.angle - FRAC_PI_2).abs() < 1e-9);
+    assert!((polar.magnitude - 50.0).abs() < 1e-9);
+
+    assert!((gate.max_magnitude(0.0) - 80.0).abs() < 1e-9);
+    assert!((gate.max_magnitude(FRAC_PI_4) - 70.0 * SQRT_2).abs() < 1e-9);
+    assert!(gate.contains(70, 70));
+    assert!(!gate.contains(71, 70));
+    assert!(!gate.contains(-81, 0));
+
+    let snapped = gate.snap(StickPolar {
+        angle: FRAC_PI_4,
+        magnitude: 200.0,
+    });
+    assert_eq!(snapped, (70, 70));
+
+    let mut input = Input::default();
+    input.set_stick_polar(
+        StickPolar {
+            angle: PI,
+            magnitude: 127.0,
+        },
+        &gate,
+    );
+    assert_eq!((input.x_axis, input.y_axis), (-80, 0));
+}
+
+#[test]
+fn stick_range_report() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64").to_vec();
+    let mut m64 = M64::from_u8_array(&file).unwrap();
+    let options = StickAnalysisOptions::default();
+    let baseline = m64.stick_report(&options);
+
+    m64[(3, 1)].x_axis = -128;
+    m64[(7, 0)].x_axis = 3;
+    m64[(7, 0)].y_axis = -2;
+    let report = m64.stick_report(&options);
+
+    let issue = StickIssue {
+        frame: 3,
+        controller: 1,
+        x: -128,
+        y: m64[(3, 1)].y_axis,
+    };
+    assert!(report.out_of_range.contains(&issue));
+    assert!(!baseline.out_of_range.contains(&issue));
+    assert!(report.in_deadzone.contains(&StickIssue {
+        frame: 7,
+        controller: 0,
+        x: 3,
+        y: -2,
+    }));
+}
+
+#[test]
+fn find_input_pattern() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64").to_vec();
+    let mut m64 = M64::from_u8_array(&file).unwrap();
+    let marker = Input {
+        reserved_1: true,
+        reserved_2: true,
+        ..Default::default()
+    };
+    m64[(100, 1)] = marker;
+    m64[(101, 1)] = Input {
+        z_button: true,
+        ..Default::default()
+    };
+
+    let pattern = [
+        InputPredicate::from(marker),
+        InputPredicate::new(|input| input.z_button),
+    ];
+    assert_eq!(m64.find_pattern(1, &pattern), vec![100]);
+    assert!(!m64.find_pattern(0, &pattern).contains(&100));
+    assert!(m64.find_pattern(2, &pattern).is_empty());
+    assert_eq!(
+        m64.find_pattern(0, &[InputPredicate::Any]).len(),
+        m64.inputs_for_controller(0).count()
+    );
+
+    // closures don't have to be thread safe without the rayon feature
+    #[cfg(not(feature = "rayon"))]
+    {
+        let calls = std::cell::Cell::new(0);
+        let pattern = [InputPredicate::new(|_| {
+            calls.set(calls.get() + 1);
+            false
+        })];
+        assert!(m64.find_pattern(0, &pattern).is_empty());
+        assert_eq!(calls.get(), m64.inputs_for_controller(0).count());
+    }
+}
+
+#[test]
+fn diff_inputs_ops() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64").to_vec();
+    let mut m64 = M64::from_u8_array(&file).unwrap();
+    assert!(m64.diff_inputs(&m64).is_empty());
+
+    // make every input unique so the edits are unambiguous
+    for (i, input) in m64.inputs.iter_mut().enumerate() {
+        input.x_axis = (i % 100) as i8;
+        input.y_axis = (i / 100) as i8;
+    }
+
+    let pressed = Input {
+        start: true,
+        ..Default::default()
+    };
+    let mut edited = m64.clone();
+    // replace frame 10, delete frame 200 and insert 2 frames at 500, for controller 1 only
+    edited[(10, 1)] = pressed;
+    let mut controllers = edited.controllers();
+    controllers[1].remove(200);
+    controllers[1].splice(500..500, [pressed; 2]);
+
+    edited.inputs.clear();
+    for frame in 0..controllers[0].len() {
+        for controller in &controllers {
+            if let Some(input) = controller.get(frame) {
+                edited.inputs.push(*input);
+            }
+        }
+    }
+
+    let ops = m64.diff_inputs(&edited);
+    assert!(ops.iter().all(|op| op.controller() == 1));
+    assert_eq!(ops.len(), 3);
+    assert!(
+        matches!(&ops[0], DiffOp::Replace { range, other, .. } if *range == (10..11) && *other == (10..11))
+    );
+    assert_eq!(
+        ops[1],
+        DiffOp::Delete {
+            controller: 1,
+            range: 200..201
+        }
+    );
+    assert_eq!(
+        ops[2],
+        DiffOp::Insert {
+            controller: 1,
+            at: 501,
+            other: 500..502
+        }
+    );
+}
+
+#[test]
+fn button_masking() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64").to_vec();
+    let mut m64 = M64::from_u8_array(&file).unwrap();
+    let all = Input {
+        up_dpad: true,
+        a_button: true,
+        reserved_1: true,
+        x_axis: 20,
+        ..Default::default()
+    };
+    assert_eq!(
+        all.buttons(),
+        Buttons::UP_DPAD | Buttons::A | Buttons::RESERVED_1
+    );
+
+    for input in &mut m64.inputs[..40] {
+        *input = all;
+    }
+    m64.strip_buttons(Buttons::DPAD, 5..10);
+    assert!(m64[4].iter().all(|input| input.up_dpad));
+    assert!(m64.inputs[10..20]
+        .iter()
+        .all(|input| !input.up_dpad && input.a_button));
+    assert_eq!(m64[(9, 1)].x_axis, 20);
+    assert!(m64[10].iter().all(|input| input.up_dpad));
+
+    m64.retain_buttons(!Buttons::RESERVED);
+    assert!(m64.inputs.iter().all(|input| !input.reserved_1));
+    assert!(m64[0].iter().all(|input| input.a_button && input.up_dpad));
+}
+
+#[test]
+fn reset_frame_detection() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64").to_vec();
+    let mut m64 = M64::from_u8_array(&file).unwrap();
+    assert!(!m64.has_resets());
+    assert_eq!(m64.reset_frames().count(), 0);
+
+    let reset = Input {
+        reserved_1: true,
+        reserved_2: true,
+        ..Default::default()
+    };
+    m64[(30, 0)] = reset;
+    m64[(40, 1)] = reset;
+    m64[(41, 1)].reserved_1 = true;
+    assert!(m64.has_resets());
+    assert_eq!(m64.reset_frames().collect::<Vec<_>>(), vec![30, 40]);
+}
+
+#[test]
+fn input_stats() {
+    let a = Input {
+        a_button: true,
+        ..Default::default()
+    };
+    let stick = Input {
+        x_axis: 30,
+        y_axis: 40,
+        ..Default::default()
+    };
+    let reset = Input {
+        reserved_1: true,
+        reserved_2: true,
+        ..Default::default()
+    };
+    let inputs = [a, a, reset, a, stick, a, a, a];
+    let stats = InputStats::from_inputs(inputs, 4.0);
+
+    assert_eq!(stats.frames, 8);
+    assert_eq!(stats.total_presses, 3);
+    assert_eq!(stats.presses_per_second, 1.5);
+    let a_stats = stats.button(Buttons::A).unwrap();
+    assert_eq!(a_stats.presses, 3);
+    assert_eq!(a_stats.held_frames, 6);
+    assert_eq!(
+        a_stats.hold_times.iter().collect::<Vec<_>>(),
+        vec![(&1, &1), (&2, &1), (&3, &1)]
+    );
+    assert_eq!(stats.button(Buttons::B).unwrap().presses, 0);
+    // the reset isn't counted as a press of the reserved bits
+    assert_eq!(stats.buttons.len(), 14);
+    assert_eq!(stats.button(Buttons::RESERVED_1), None);
+    assert_eq!(
+        stats
+            .buttons
+            .iter()
+            .map(|stats| stats.presses)
+            .sum::<usize>(),
+        stats.total_presses
+    );
+    // magnitude 50 lands in bucket 6
+    assert_eq!(stats.stick_histogram.len(), 7);
+    assert_eq!(stats.stick_histogram[0], 7);
+    assert_eq!(stats.stick_histogram[6], 1);
+
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64").to_vec();
+    let m64 = M64::from_u8_array(&file).unwrap();
+    let stats = m64.stats();
+    assert_eq!(stats.len(), 2);
+    assert_eq!(stats[1].frames, 833);
+}
+
+#[test]
+fn idle_frames() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64").to_vec();
+    let mut m64 = M64::from_u8_array(&file).unwrap();
+    let a = Input {
+        a_button: true,
+        ..Default::default()
+    };
+    m64.inputs = vec![Input::default(); 20];
+    m64.inputs[2] = a;
+    m64.inputs[11] = a;
+    m64.input_frames = 20;
+
+    let report = m64.idle_report();
+    assert_eq!(report.idle_frames, 8);
+    assert_eq!(report.longest_idle, Some(6..10));
+    assert_eq!(report.last_active_frame, Some(5));
+
+    assert_eq!(m64.trim_trailing_idle(), 4);
+    assert_eq!(m64.inputs.len(), 12);
+    assert_eq!(m64.input_frames, 12);
+    assert_eq!(m64.trim_trailing_idle(), 0);
+}
+
+#[test]
+fn build_from_iterator() {
+    let a = Input {
+        a_button: true,
+        ..Default::default()
+    };
+
+    let m64 = std::iter::repeat_n(a, 10).collect::<M64>();
+    assert_eq!(m64.input_frames, 10);
+    assert_eq!(m64.inputs, vec![a; 10]);
+    let mut written = Vec::new();
+    m64.write_m64(&mut written).unwrap();
+    assert_eq!(M64::from_u8_array(&written).unwrap(), m64);
+
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64").to_vec();
+    let header = M64::from_u8_array(&file).unwrap();
+    let mut m64 = collect_into_m64(header.clone(), [a; 4]);
+    assert_eq!(m64.input_frames, 4);
+    assert_eq!(m64.rerecords, header.rerecords);
+
+    m64.extend([Input::default(); 2]);
+    assert_eq!(m64.input_frames, 6);
+    assert_eq!(m64.inputs.len(), 6);
+}
+
+#[test]
+fn input_hash_ignores_metadata() {
+    let file = include_bytes!("./m64s/120 star tas (2012).m64").to_vec();
+    let m64 = M64::from_u8_array(&file).unwrap();
+    let mut other = m64.clone();
+    other.rerecords += 1;
+    other.uid = 0;
+    other.author = PaddedString::zeroed();
+    assert_eq!(m64.input_hash(), other.input_hash());
+
+    other.inputs[100].a_button = !other.inputs[100].a_button;
+    assert_ne!(m64.input_hash(), other.input_hash());
+
+    // fnv-1a of no data is the offset basis
+    assert_eq!(M64::default().input_hash(), 0xcbf2_9ce4_8422_2325);
+}
+
+#[test]
+fn tasd_round_trip() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64").to_vec();
+    let m64 = M64::from_u8_array(&file).unwrap();
+    let mut tasd = Vec::new();
+    m64.write_tasd(&mut tasd).unwrap();
+    assert_eq!(&tasd[..7], b"TASD\x00\x01\x02");
+
+    let imported = M64::from_tasd(&tasd).unwrap();
+    // the incomplete last frame is filled up with a neutral sample
+    assert_eq!(imported.inputs[..m64.inputs.len()], m64.inputs);
+    assert!(imported.inputs[m64.inputs.len()..]
+        .iter()
+        .all(Input::is_neutral));
+    assert!(imported.inputs.len().is_multiple_of(2));
+    assert_eq!(imported.controller_count, 2);
+    assert_eq!(imported.controller_flags, m64.controller_flags);
+    assert_eq!(imported.rerecords, m64.rerecords);
+    assert_eq!(imported.rom_internal_name, m64.rom_internal_name);
+    assert_eq!(imported.author, m64.author);
+    assert_eq!(imported.description, m64.description);
+
+    assert!(M64::from_tasd(b"TASD\x00\x01\x02\x00\x01\x01\x01\x01").is_err());
+    assert!(M64::from_tasd(&file).is_err());
+
+    // the first port has 2 more frames than the second, which are neutral for the second
+    let mut m64 = m64;
+    m64.inputs.truncate(2);
+    let mut tasd = Vec::new();
+    m64.write_tasd(&mut tasd).unwrap();
+    let extra = [Input::from(0x0080), Input::from(0x0040)];
+    tasd.extend_from_slice(&[0xFE, 0x01, 0x01, 0x09, 0x01]);
+    for input in extra {
+        tasd.extend_from_slice(&u32::from(input).to_le_bytes());
+    }
+    let imported = M64::from_tasd(&tasd).unwrap();
+    assert_eq!(
+        imported.inputs,
+        [
+            m64.inputs[0],
+            m64.inputs[1],
+            extra[0],
+            Input::default(),
+            extra[1],
+            Input::default()
+        ]
+    );
+}
+
+#[test]
+fn csv_round_trip() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64").to_vec();
+    let m64 = M64::from_u8_array(&file).unwrap();
+    let mut csv = Vec::new();
+    m64.write_inputs_csv(&mut csv).unwrap();
+
+    let imported = M64::read_inputs_csv(csv.as_slice(), m64.clone()).unwrap();
+    assert_eq!(imported.inputs, m64.inputs);
+    assert_eq!(imported.input_frames, 1667);
+
+    let csv = "frame,controller,buttons,x,y\n1,1,A+Z,-5,60\n0,0,,0,0\n";
+    let imported = M64::read_inputs_csv(csv.as_bytes(), m64.clone()).unwrap();
+    assert_eq!(imported.inputs.len(), 4);
+    assert_eq!(imported[(1, 1)].buttons(), Buttons::A | Buttons::Z);
+    assert_eq!((imported[(1, 1)].x_axis, imported[(1, 1)].y_axis), (-5, 60));
+    assert!(imported[(1, 0)].is_neutral());
+
+    let csv = "frame,controller,buttons,x,y\n0,2,,0,0\n";
+    let err = M64::read_inputs_csv(csv.as_bytes(), m64.clone()).unwrap_err();
+    assert_eq!(format!("{err}"), "Invalid CSV row at line 2");
+
+    // frame indices far past the end aren't allocated
+    let csv = format!("0,0,,0,0\n{},0,,0,0\n", usize::MAX / 2);
+    let err = M64::read_inputs_csv(csv.as_bytes(), m64.clone()).unwrap_err();
+    assert!(matches!(err, CsvError::InputGap(2)));
+    let csv = format!("{},1,,0,0\n", MAX_INPUT_GAP / 2 - 1);
+    let imported = M64::read_inputs_csv(csv.as_bytes(), m64).unwrap();
+    assert_eq!(imported.inputs.len(), MAX_INPUT_GAP);
+}
+
+#[test]
+fn input_script() {
+    let script = "# jump\n2-4: A+Z x=60 y=-3\n\n6: START # pause\n3: B\n";
+    let inputs = parse_script(script).unwrap();
+    assert_eq!(inputs.len(), 7);
+    assert!(inputs[0].is_neutral() && inputs[5].is_neutral());
+    assert_eq!(inputs[2].buttons(), Buttons::A | Buttons::Z);
+    assert_eq!((inputs[2].x_axis, inputs[2].y_axis), (60, -3));
+    assert_eq!(inputs[3].buttons(), Buttons::B);
+    assert_eq!(inputs[6].buttons(), Buttons::START);
+
+    let formatted = format_script(&inputs);
+    assert_eq!(
+        formatted,
+        "2: Z+A x=60 y=-3\n3: B\n4: Z+A x=60 y=-3\n6: START\n"
+    );
+    assert_eq!(parse_script(&formatted).unwrap(), inputs);
+
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64").to_vec();
+    let m64 = M64::from_u8_array(&file).unwrap();
+    assert_eq!(
+        parse_script(&format_script(&m64.inputs)).unwrap(),
+        m64.inputs
+    );
+
+    let err = parse_script("1: A\n5-2: B").unwrap_err();
+    assert_eq!(format!("{err}"), "Invalid script line 2");
+    // frames far past the end aren't allocated
+    let err = parse_script(&format!("1: A\n2-{}: B", usize::MAX)).unwrap_err();
+    assert!(matches!(err, ScriptError::InputGap(2)));
+    let inputs = parse_script(&format!("{}: A", MAX_INPUT_GAP - 1)).unwrap();
+    assert_eq!(inputs.len(), MAX_INPUT_GAP);
+    assert!(parse_script("1: X").is_err());
+}
+
+#[test]
+fn lua_joypad_script() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64").to_vec();
+    let mut m64 = M64::from_u8_array(&file).unwrap();
+    m64.inputs.truncate(4);
+    m64.inputs[1].a_button = true;
+    m64.inputs[1].z_button = true;
+    m64.inputs[2].x_axis = -60;
+
+    let script = m64.to_lua_joypad_script();
+    assert!(script.contains("local ports = { 1, 2 }\n"));
+    assert!(script.contains("  { {}, {Z = true, A = true} },\n  { {X = -60}, {} },\n}\n"));
+    assert!(script.contains("joypad.set(port, inputs[i])"));
+}
+
+#[test]
+fn srt_subtitles() {
+    let m64 = M64::default();
+    let annotations = [
+        Annotation::new(3600..3660, "second"),
+        Annotation::new(10..10, "skipped"),
+        Annotation::new(90..120, "first\n"),
+    ];
+
+    assert_eq!(
+        m64.to_srt(&annotations),
+        "1\n00:00:01,501 --> 00:00:02,002\nfirst\n\n2\n00:01:00,060 --> 00:01:01,061\nsecond\n\n"
+    );
+
+    // VI frames without input frames make the input frame rate 0
+    let m64 = M64 {
+        vi_frames: 100,
+        input_frames: 0,
+        ..Default::default()
+    };
+    assert_eq!(m64.frame_time(60), Duration::ZERO);
+    assert_eq!(
+        m64.to_srt(&annotations[..1]),
+        "1\n00:00:00,000 --> 00:00:00,000\nsecond\n\n"
+    );
+}
+
+#[test]
+fn st_header() {
+    let mut state = b"0123456789ABCDEF0123456789abcdef".to_vec();
+    // emulator state
+    state.extend_from_slice(&[0xAA; 64]);
+    let samples = [0u32, 0x8000, 0x1234_0000];
+    state.extend_from_slice(&(16 + 4 * samples.len() as u32).to_le_bytes());
+    for value in [1234u32, 2, 5, samples.len() as u32 - 1] {
+        state.extend_from_slice(&value.to_le_bytes());
+    }
+    for sample in samples {
+        state.extend_from_slice(&sample.to_le_bytes());
+    }
+
+    let mut compressed = GzEncoder::new(Vec::new(), Compression::default());
+    compressed.write_all(&state).unwrap();
+    let compressed = compressed.finish().unwrap();
+
+    for data in [&state, &compressed] {
+        let header = StHeader::from_u8_array(data).unwrap();
+        assert_eq!(header.rom_md5, "0123456789abcdef0123456789abcdef");
+        let movie = header.movie.unwrap();
+        assert_eq!(
+            (
+                movie.uid,
+                movie.current_sample,
+                movie.current_vi,
+                movie.length_samples
+            ),
+            (1234, 2, 5, 2)
+        );
+        assert_eq!(movie.inputs[1], Input::from(0x8000));
+    }
+
+    let header = StHeader::from_u8_array(&state[..96]).unwrap();
+    assert_eq!(header.movie, None);
+    assert!(StHeader::from_u8_array(b"not a savestate").is_err());
+}
+
+#[test]
+fn verify_savestate() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64").to_vec();
+    let m64 = M64::from_u8_array(&file).unwrap();
+    let mut header = StHeader {
+        rom_md5: "0".repeat(32),
+        movie: Some(StMovie {
+            uid: m64.uid,
+            current_sample: 10,
+            current_vi: 20,
+            length_samples: m64.input_frames,
+            inputs: m64.inputs.clone(),
+        }),
+    };
+    assert_eq!(m64.verify_savestate(&header), []);
+
+    let movie = header.movie.as_mut().unwrap();
+    movie.uid += 1;
+    movie.current_sample = 5000;
+    movie.inputs[3].a_button = !movie.inputs[3].a_button;
+    assert_eq!(
+        m64.verify_savestate(&header),
+        [
+            SavestateMismatch::Uid {
+                movie: m64.uid,
+                savestate: m64.uid + 1
+            },
+            SavestateMismatch::SampleOutOfRange {
+                current_sample: 5000,
+                input_frames: m64.input_frames
+            },
+            SavestateMismatch::Inputs { sample: 3 },
+        ]
+    );
+
+    header.movie = None;
+    assert_eq!(
+        M64::default().verify_savestate(&header),
+        [
+            SavestateMismatch::NotSnapshotMovie,
+            SavestateMismatch::NoMovie
+        ]
+    );
+}
+
+#[test]
+fn eeprom_save_files() {
+    let m64 = M64 {
+        movie_start_type: MovieStartType::Eeprom,
+        ..M64::default()
+    };
+    let dir = env::temp_dir().join(format!("m64-saves-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let movie_path = dir.join("movie.m64");
+
+    let report = m64.check_save_files(&movie_path).unwrap();
+    assert_eq!(report.warnings, [SaveWarning::Missing]);
+
+    let mut eeprom = vec![0xFF; 0x200];
+    eeprom[10] = 1;
+    fs::write(save_path(&movie_path, SaveType::Eeprom), &eeprom).unwrap();
+    fs::write(save_path(&movie_path, SaveType::Sram), [0; 0x8000]).unwrap();
+    fs::write(save_path(&movie_path, SaveType::FlashRam), [1; 100]).unwrap();
+
+    let report = m64.check_save_files(&movie_path).unwrap();
+    assert_eq!(report.files.len(), 3);
+    assert_eq!(report.files[0].path, dir.join("movie.eep"));
+    assert!(matches!(
+        &report.warnings[..],
+        [SaveWarning::Blank(sram), SaveWarning::InvalidSize(flash)]
+            if sram.save_type == SaveType::Sram && flash.size == 100
+    ));
+
+    let report = M64::default().check_save_files(&movie_path).unwrap();
+    assert_eq!(report.warnings[0], SaveWarning::NotEepromMovie);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+/// Returns the header of a big endian SM64 (U) ROM.
+fn sm64_rom_header() -> Vec<u8> {
+    let mut rom = vec![0; 0x40];
+    rom[..4].copy_from_slice(&[0x80, 0x37, 0x12, 0x40]);
+    rom[0x10..0x18].copy_from_slice(&[0x63, 0x5A, 0x2B, 0xFF, 0x8B, 0x02, 0x23, 0x26]);
+    rom[0x20..0x34].copy_from_slice(b"SUPER MARIO 64      ");
+    rom[0x3B..0x3F].copy_from_slice(b"NSME");
+    rom
+}
+
+#[test]
+fn rom_header() {
+    let header = RomHeader::from_u8_array(&sm64_rom_header()).unwrap();
+    assert_eq!(header.internal_name, "SUPER MARIO 64");
+    assert_eq!((header.crc1, header.crc2), (0x635A2BFF, 0x8B022326));
+    assert_eq!(header.country_code, b'E');
+
+    assert!(RomHeader::from_u8_array(&sm64_rom_header()[..0x3F]).is_err());
+    assert!(RomHeader::from_u8_array(&[0; 0x40]).is_err());
+}
+
+#[test]
+fn verify_rom() {
+    let file = include_bytes!("./m64s/120 star tas (2012).m64").to_vec();
+    let m64 = M64::from_u8_array(&file).unwrap();
+    assert_eq!(m64.verify_rom(&sm64_rom_header()).unwrap(), []);
+
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64").to_vec();
+    let m64 = M64::from_u8_array(&file).unwrap();
+    let mut rom = sm64_rom_header();
+    rom[0x3E] = b'J';
+    let mismatches = m64.verify_rom(&rom).unwrap();
+    assert_eq!(
+        mismatches,
+        [
+            RomMismatch::InternalName {
+                movie: "SM64 Multiplayer".to_string(),
+                rom: "SUPER MARIO 64".to_string()
+            },
+            RomMismatch::Crc32 {
+                movie: 0xC368EB2D,
+                rom: 0xFF2B5A63
+            },
+            RomMismatch::CountryCode {
+                movie: 0x45,
+                rom: 0x4A
+            },
+        ]
+    );
+    assert_eq!(
+        mismatches[1].to_string(),
+        "movie was recorded on a ROM with CRC C368EB2D, but the ROM has CRC FF2B5A63"
+    );
+    assert!(m64.verify_rom(&[]).is_err());
+}
+
+#[test]
+fn rom_byte_order() {
+    let z64 = sm64_rom_header();
+    let v64 = z64
+        .chunks(2)
+        .flat_map(|word| [word[1], word[0]])
+        .collect::<Vec<_>>();
+    let n64 = z64
+        .chunks(4)
+        .flat_map(|word| word.iter().rev().copied().collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+
+    assert_eq!(ByteOrder::detect(&z64), Some(ByteOrder::BigEndian));
+    assert_eq!(ByteOrder::detect(&v64), Some(ByteOrder::ByteSwapped));
+    assert_eq!(ByteOrder::detect(&n64), Some(ByteOrder::LittleEndian));
+    assert_eq!(ByteOrder::detect(&[0; 4]), None);
+
+    let file = include_bytes!("./m64s/120 star tas (2012).m64").to_vec();
+    let m64 = M64::from_u8_array(&file).unwrap();
+    for rom in [&v64, &n64] {
+        assert_eq!(normalize_rom(rom).unwrap(), z64);
+        assert_eq!(m64.verify_rom(rom).unwrap(), []);
+    }
+}
+
+#[test]
+fn country_code() {
+    let file = include_bytes!("./m64s/120 star tas (2012).m64").to_vec();
+    let mut m64 = M64::from_u8_array(&file).unwrap();
+    assert_eq!(m64.country_code(), CountryCode::NorthAmerica);
+    assert_eq!(m64.country_code().region(), Some(Region::Ntsc));
+
+    m64.set_country_code(CountryCode::Europe);
+    assert_eq!(m64.rom_country_code, 0x50);
+    assert_eq!(m64.country_code().vi_rate(), Some(50));
+    assert_eq!(CountryCode::Brazil.region(), Some(Region::Mpal));
+
+    m64.rom_country_code = 0x1234;
+    assert_eq!(m64.country_code(), CountryCode::Unknown(0x1234));
+    assert_eq!(u16::from(m64.country_code()), 0x1234);
+    assert_eq!(m64.country_code().vi_rate(), None);
+
+    let header = RomHeader::from_u8_array(&sm64_rom_header()).unwrap();
+    assert_eq!(header.country(), CountryCode::NorthAmerica);
+}
+
+#[test]
+fn plugin_identities() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64").to_vec();
+    let plugins = M64::from_u8_array(&file).unwrap().plugins();
+    assert_eq!(plugins.video.plugin, Plugin::GlN64);
+    assert_eq!(plugins.video.version.as_deref(), Some("0.4.1"));
+    assert_eq!(plugins.sound.plugin, Plugin::JaboDirectSound);
+    assert_eq!(plugins.input.plugin, Plugin::TasInput);
+    assert_eq!(plugins.input.version.as_deref(), Some("0.6"));
+    assert_eq!(plugins.rsp.plugin, Plugin::RspEmulation);
+    assert_eq!(plugins.rsp.version, None);
+
+    let video = PluginIdentity::from_name("glide64mk2 version 2.0");
+    assert_eq!(video.plugin, Plugin::Glide64Mk2);
+    assert_eq!(video.plugin.kind(), Some(PluginKind::Video));
+    assert_eq!(video.version.as_deref(), Some("2.0"));
+
+    let unknown = PluginIdentity::from_name("My Plugin v1.2\0\0");
+    assert_eq!(unknown.plugin, Plugin::Unknown);
+    assert_eq!(unknown.version.as_deref(), Some("1.2"));
+    assert_eq!(unknown.name, "My Plugin v1.2");
+}
+
+#[test]
+fn lint_vault_check() {
+    let file = include_bytes!("./m64s/120 star tas (2012).m64").to_vec();
+    let m64 = M64::from_u8_array(&file).unwrap();
+    let rules = vault_check(&m64)
+        .into_iter()
+        .map(|finding| finding.rule)
+        .collect::<Vec<_>>();
+    assert!(!rules.contains(&LintRule::SnapshotStart));
+    assert!(!rules.contains(&LintRule::NoAuthor));
+
+    let findings = vault_check(&M64::default());
+    let rules = findings
+        .iter()
+        .map(|finding| finding.rule)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        rules,
+        [
+            LintRule::NoAuthor,
+            LintRule::NoInputs,
+            LintRule::NoRerecords,
+            LintRule::UnknownPlugin,
+            LintRule::UnknownPlugin,
+            LintRule::UnknownPlugin,
+            LintRule::UnknownPlugin,
+        ]
+    );
+    assert_eq!(findings[0].severity(), Severity::Error);
+    assert_eq!(findings[0].to_string(), "Error: author is empty");
+
+    // the low 32 bits of the rerecord count are 0
+    let mut m64 = M64::default();
+    m64.set_total_rerecords(1 << 32);
+    assert!(vault_check(&m64)
+        .iter()
+        .all(|finding| finding.rule != LintRule::NoRerecords));
+}
+
+#[test]
+fn bogus_input_frames_capacity() {
+    let mut m64 = M64::default();
+    m64.extend([Input::default(); 10]);
+    m64.input_frames = u32::MAX;
+
+    let mut written = Vec::new();
+    m64.write_m64(&mut written).unwrap();
+    let parsed = M64::from_u8_array(&written).unwrap();
+    assert_eq!(parsed.inputs.len(), 10);
+    assert!(parsed.inputs.capacity() < 1000);
+}
+
+#[test]
+fn input_bit_conversions() {
+    for raw in (0..=0xFFFF).map(|buttons| buttons | 0x8081_0000u32) {
+        let input = Input::from(raw);
+        let buttons = Buttons::from_bits_retain(raw as u16);
+        assert_eq!(input.a_button, buttons.contains(Buttons::A));
+        assert_eq!(
+            input.right_cbutton,
+            buttons.contains(Buttons::RIGHT_CBUTTON)
+        );
+        assert_eq!(input.reserved_2, buttons.contains(Buttons::RESERVED_2));
+        assert_eq!((input.x_axis, input.y_axis), (-127, -128));
+        assert_eq!(u32::from(input), raw);
+    }
+
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64");
+    let m64 = M64::from_u8_array(file).unwrap();
+    let bytes = &file[0x400..];
+    assert_eq!(decode_inputs(bytes), m64.inputs);
+    assert_eq!(encode_inputs(&m64.inputs), bytes);
+    assert_eq!(
+        decode_raw_inputs(&encode_raw_inputs(&m64.inputs)),
+        m64.inputs
+    );
+
+    // the vectorized bulk conversions match the scalar ones for every button combination
+    let raw = (0..=0xFFFFu32)
+        .map(|buttons| buttons | buttons.wrapping_mul(0x9E37_0000))
+        .chain([0x1234_5678, 0xFFFF_FFFF, 0x0000_0001])
+        .collect::<Vec<_>>();
+    let scalar = raw.iter().map(|&raw| Input::from(raw)).collect::<Vec<_>>();
+    let bytes = raw
+        .iter()
+        .flat_map(|raw| raw.to_le_bytes())
+        .collect::<Vec<_>>();
+    assert_eq!(decode_raw_inputs(&raw), scalar);
+    assert_eq!(decode_inputs(&bytes), scalar);
+    assert_eq!(encode_raw_inputs(&scalar), raw);
+    assert_eq!(encode_inputs(&scalar), bytes);
+}
+
+#[test]
+fn raw_input_storage() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64");
+    let m64 = M64::from_u8_array(file).unwrap();
+
+    let mut raw = M64::<Vec<RawInput>>::from_u8_array_with_storage(file).unwrap();
+    assert_eq!(raw.inputs.len(), m64.inputs.len());
+    assert_eq!(raw.input(100), Some(m64.inputs[100]));
+    assert_eq!(raw.input(m64.inputs.len()), None);
+    assert_eq!(raw.to_bytes(), file);
+
+    let boxed = raw.clone().with_storage::<Box<[RawInput]>>();
+    assert_eq!(boxed.to_bytes(), file);
+    assert_eq!(boxed.with_storage::<Vec<Input>>(), m64);
+
+    let pressed = Input::from(0x0080);
+    assert_eq!(raw.inputs.replace_sample(0, pressed), Some(m64.inputs[0]));
+    assert_eq!(raw.input(0), Some(pressed));
+}
+
+#[test]
+fn lazy_m64() {
+    let file = include_bytes!("./m64s/120 star tas (2012).m64");
+    let m64 = M64::from_u8_array(file).unwrap();
+    let mut lazy = M64::from_u8_array_lazy(file).unwrap();
+
+    assert_eq!(size_of::<RawInput>(), 4);
+    assert!(lazy.iter_inputs().eq(m64.inputs.iter().copied()));
+    assert_eq!(lazy.input(1000), Some(m64.inputs[1000]));
+
+    let pressed = Input::from(0x0080);
+    assert_eq!(lazy.set_input(1000, pressed), Some(m64.inputs[1000]));
+    assert_eq!(lazy.input(1000), Some(pressed));
+    assert_eq!(lazy.set_input(m64.inputs.len(), pressed), None);
+}
+
+#[test]
+fn checksum_digests() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64");
+    let m64 = M64::from_u8_array(file).unwrap();
+
+    assert_eq!(m64.file_digest(DigestAlgorithm::Crc32), "f518894f");
+    assert_eq!(
+        m64.file_digest(DigestAlgorithm::Sha1),
+        "d9192cd7374d2c8680220c06d76f5804a743a852"
+    );
+    assert_eq!(
+        m64.header_digest(DigestAlgorithm::Sha256),
+        "3e09654c4028ecb416eb0add16d2f88a95a25f1ff7dcf0b1140332a2def8f0c3"
+    );
+    assert_eq!(
+        m64.inputs_digest(DigestAlgorithm::Sha256),
+        "3ce1e8892f3f437a969bd695d105aca88070e0b7e8ae6ee0f8ae4d9e9ee7309d"
+    );
+
+    // the metadata block isn't part of the inputs
+    let mut with_block = m64.clone();
+    with_block
+        .metadata_block
+        .insert(URL, "https://tasvideos.org")
+        .unwrap();
+    assert_eq!(
+        with_block.inputs_digest(DigestAlgorithm::Sha256),
+        m64.inputs_digest(DigestAlgorithm::Sha256)
+    );
+    assert_ne!(
+        with_block.file_digest(DigestAlgorithm::Sha256),
+        m64.file_digest(DigestAlgorithm::Sha256)
+    );
+}
+
+#[test]
+fn patch_round_trip() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64");
+    let m64 = M64::from_u8_array(file).unwrap();
+    let mut edited = m64.clone();
+    edited.rerecords += 10;
+    edited.inputs[500].a_button = !edited.inputs[500].a_button;
+    edited.inputs.drain(1000..1010);
+    edited.inputs.push(Input::from(0x0080));
+
+    let patch = m64.create_patch(&edited);
+    assert_eq!(patch.header.len(), 1);
+    assert_eq!(patch.inputs.len(), 3);
+
+    let bytes = patch.to_bytes();
+    assert!(bytes.len() < 100);
+    let patch = Patch::from_bytes(&bytes).unwrap();
+    assert_eq!(m64.apply_patch(&patch).unwrap(), edited);
+    assert!(edited.apply_patch(&patch).is_err());
+    assert!(Patch::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+
+    // a reversed range that starts past the end of the inputs
+    let mut patch = patch;
+    patch.inputs = vec![InputEdit {
+        range: m64.inputs.len() + 10..5,
+        inputs: Vec::new(),
+    }];
+    assert!(matches!(
+        m64.apply_patch(&patch),
+        Err(PatchError::InvalidEdit)
+    ));
+}
+
+#[test]
+fn compressed_inputs() {
+    let file = include_bytes!("./m64s/120 star tas (2012).m64");
+    let m64 = M64::from_u8_array(file).unwrap();
+
+    let compressed = encode_compressed(&m64.inputs, m64.samples_per_frame());
+    assert!(compressed.len() * 3 < m64.inputs.len() * 4);
+    assert_eq!(decode_compressed(&compressed).unwrap(), m64.inputs);
+
+    let inputs = [0, 0, 0x0080, 0, 0x0080, 0x0080, 0, 0].map(Input::from);
+    for controllers in 1..=3 {
+        let compressed = encode_compressed(&inputs, controllers);
+        assert_eq!(decode_compressed(&compressed).unwrap(), inputs);
+        assert!(decode_compressed(&compressed[..compressed.len() - 1]).is_err());
+    }
+    assert_eq!(decode_compressed(&encode_compressed(&[], 1)).unwrap(), []);
+}
+
+#[test]
+fn stick_path_svg() {
+    let mut m64 = M64::default();
+    m64.extend(
+        [(10, 20), (10, 20), (-30, 0), (10, 20)].map(|(x, y)| Input {
+            x_axis: x,
+            y_axis: y,
+            ..Default::default()
+        }),
+    );
+
+    let svg = m64.stick_svg(0, .., &StickSvgOptions::default());
+    assert!(svg.starts_with("<svg"));
+    assert!(svg.contains(r#"d="M10 -20L10 -20L-30 0L10 -20""#));
+    assert_eq!(svg.matches("<circle").count(), 2);
+
+    let options = StickSvgOptions {
+        heatmap: true,
+        ..Default::default()
+    };
+    let svg = m64.stick_svg(0, 2.., &options);
+    assert!(svg.contains(r#"d="M-30 0L10 -20""#));
+    assert!(svg.contains("hsl(240,100%,45%)"));
+
+    // the movie has a single controller
+    let svg = m64.stick_svg(1, .., &options);
+    assert!(svg.contains("<polygon"));
+    assert!(!svg.contains("<circle") && !svg.contains(r##"stroke="#36c""##));
+
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64");
+    let m64 = M64::from_u8_array(file).unwrap();
+    let svg = m64.stick_svg(1, 100..200, &options);
+    assert!(svg.contains("hsl(0,100%,45%)"));
+}
+
+#[test]
+fn movie_duration() {
+    let file = include_bytes!("./m64s/120 star tas (2012).m64");
+    let m64 = M64::from_u8_array(file).unwrap();
+    assert_eq!(m64.region(), Region::Ntsc);
+    assert!((Region::Ntsc.vi_rate_exact() - 59.94).abs() < 0.01);
+    assert!((Region::Pal.vi_rate_exact() - 50.0).abs() < 0.01);
+
+    assert_eq!(m64.duration().as_secs(), 4846);
+    assert_eq!(m64.length_string(), "1:20:46.36");
+
+    let mut m64 = M64 {
+        vi_frames: 150,
+        fps: 50,
+        ..Default::default()
+    };
+    assert_eq!(m64.region(), Region::Pal);
+    assert_eq!(m64.length_string(), "0:00:03.00");
+    m64.set_country_code(CountryCode::Japan);
+    assert_eq!(m64.length_string(), "0:00:02.50");
+}
+
+#[test]
+fn frame_time_conversions() {
+    let timing = Timing::new(Region::Pal, 2);
+    assert_eq!(timing.frame_to_time(0), Duration::ZERO);
+    assert_eq!(timing.time_to_frame(timing.frame_to_time(500)), 500);
+    assert_eq!(timing.sample_to_frame(7), 3);
+    assert_eq!(timing.frame_to_sample(3), 6);
+
+    let timing = Timing {
+        vis_per_input_frame: 2.0,
+        ..Timing::new(Region::Ntsc, 1)
+    };
+    assert_eq!(timing.vi_to_input_frame(9), 4);
+    assert_eq!(timing.input_frame_to_vi(4), 8);
+    assert_eq!(timing.time_to_frame(Duration::from_secs(1)), 29);
+
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64");
+    let m64 = M64::from_u8_array(file).unwrap();
+    let timing = m64.timing();
+    assert_eq!(timing.controllers, 2);
+    assert_eq!(
+        timing.vis_per_input_frame,
+        m64.vi_frames as f64 / (m64.input_frames as f64 / 2.0)
+    );
+    assert_eq!(m64.time_to_frame(m64.frame_to_time(300)), 300);
+}
+
+#[test]
+fn lint_region_fps_mismatch() {
+    let file = include_bytes!("./m64s/120 star tas (2012).m64");
+    let mut m64 = M64::from_u8_array(file).unwrap();
+    let has_mismatch = |m64: &M64| {
+        vault_check(m64)
+            .iter()
+            .any(|finding| finding.rule == LintRule::RegionFpsMismatch)
+    };
+    assert!(!has_mismatch(&m64));
+
+    m64.fps = 50;
+    assert!(has_mismatch(&m64));
+    m64.set_country_code(CountryCode::Europe);
+    assert!(!has_mismatch(&m64));
+}
+
+#[test]
+fn recording_system_time() {
+    let file = include_bytes!("./m64s/120 star tas (2012).m64");
+    let m64 = M64::from_u8_array(file).unwrap();
+    assert_eq!(
+        m64.recording_system_time(),
+        std::time::UNIX_EPOCH + Duration::from_secs(1272727295)
+    );
+}
+
+#[test]
+fn lag_estimate() {
+    let options = LagAnalysisOptions::default();
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64");
+    let m64 = M64::from_u8_array(file).unwrap();
+    let report = m64.lag_report(&options).unwrap();
+    assert_eq!(report.input_frames, 600);
+    assert_eq!(report.lag_frames, 9);
+    assert_eq!(report.lag_time.as_millis(), 316);
+    assert!(report.issues.is_empty());
+
+    let m64 = M64 {
+        vi_frames: 50,
+        input_frames: 201,
+        controller_count: 2,
+        ..Default::default()
+    };
+    assert_eq!(
+        m64.lag_report(&options).unwrap().issues,
+        [LagIssue::IncompleteFrame, LagIssue::FewerVisThanInputFrames]
+    );
+
+    let m64 = M64 {
+        vi_frames: 1000,
+        input_frames: 100,
+        ..Default::default()
+    };
+    let report = m64.lag_report(&options).unwrap();
+    assert_eq!(report.lag_frames, 400);
+    assert_eq!(report.issues, [LagIssue::ExcessiveLag]);
+
+    for vis_per_poll in [0.0, -2.0, f64::INFINITY] {
+        let options = LagAnalysisOptions {
+            vis_per_poll,
+            ..options
+        };
+        assert_eq!(
+            m64.lag_report(&options),
+            Err(LagAnalysisError::InvalidVisPerPoll(vis_per_poll))
+        );
+    }
+    assert!(m64
+        .lag_report(&LagAnalysisOptions {
+            vis_per_poll: f64::NAN,
+            ..options
+        })
+        .is_err());
+}
+
+#[test]
+fn compare_movies() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64");
+    let m64 = M64::from_u8_array(file).unwrap();
+    let same = compare(&m64, &m64);
+    assert_eq!(same.frame_difference, 0);
+    assert_eq!(same.first_divergence, None);
+    assert!(same.buttons.iter().all(|delta| delta.delta() == 0));
+
+    let mut improved = m64.clone();
+    improved.inputs.truncate(1000);
+    improved.inputs[401].a_button = !improved.inputs[401].a_button;
+    improved.rerecords += 100;
+    improved.vi_frames -= 20;
+
+    let comparison = compare(&m64, &improved);
+    assert_eq!(comparison.frames, 834);
+    assert_eq!(comparison.other_frames, 500);
+    assert_eq!(comparison.frame_difference, -334);
+    assert_eq!(comparison.vi_difference, -20);
+    assert_eq!(comparison.rerecord_difference, 100);
+    assert_eq!(comparison.first_divergence, Some(200));
+    assert_eq!(
+        comparison.total_presses_difference,
+        comparison
+            .buttons
+            .iter()
+            .map(|delta| delta.delta())
+            .sum::<i64>()
+    );
+    assert!(comparison.total_presses_difference < 0);
+    improved.inputs[402].reserved_1 = true;
+    assert!(compare(&m64, &improved)
+        .buttons
+        .iter()
+        .all(|delta| !Buttons::RESERVED.contains(delta.button)));
+
+    // the upper 32 bits of the rerecord count differ
+    improved.set_total_rerecords(m64.total_rerecords() + (1 << 32));
+    assert_eq!(compare(&m64, &improved).rerecord_difference, 1 << 32);
+    improved.set_total_rerecords(u64::MAX);
+    assert_eq!(compare(&m64, &improved).rerecord_difference, i64::MAX);
+}
+
+#[test]
+fn button_hold_durations() {
+    let mut m64 = M64::default();
+    m64.extend(
+        [1, 1, 1, 0, 1, 0, 1, 1, 1, 0, 1, 1]
+            .map(|held| Input::from(if held == 1 { 0x0080 } else { 0 })),
+    );
+
+    let stats = m64.stats();
+    let a = stats[0].button(Buttons::A).unwrap();
+    assert_eq!(a.presses, 4);
+    assert_eq!(a.longest_hold(), Some(3));
+    assert_eq!(a.shortest_hold(), Some(1));
+    assert_eq!(a.average_hold(), Some(9.0 / 4.0));
+    assert_eq!(a.most_common_hold(), Some(3));
+
+    let stats = m64.stats_in_range(4..8);
+    let a = stats[0].button(Buttons::A).unwrap();
+    assert_eq!(stats[0].frames, 4);
+    assert_eq!(
+        a.hold_times.iter().collect::<Vec<_>>(),
+        [(&1, &1), (&2, &1)]
+    );
+    assert_eq!(a.most_common_hold(), Some(1));
+
+    let b = stats[0].button(Buttons::B).unwrap();
+    assert_eq!(b.longest_hold(), None);
+    assert_eq!(b.average_hold(), None);
+}
+
+#[test]
+fn duplicate_segment_detection() {
+    let mut m64 = M64 {
+        controller_count: 2,
+        ..Default::default()
+    };
+    let segment = (0..20)
+        .map(|i| Input::from(i * 0x0101_0000))
+        .collect::<Vec<_>>();
+    m64.extend(segment.iter().copied());
+    m64.extend((50..60).map(|i| Input::from(i * 0x0101_0000)));
+    m64.extend(segment.iter().copied());
+    m64.extend(segment[..8].iter().copied());
+
+    let segments = m64.duplicate_segments(4);
+    assert_eq!(
+        segments,
+        [
+            DuplicateSegment {
+                original: 0..10,
+                copy: 15..25
+            },
+            DuplicateSegment {
+                original: 0..4,
+                copy: 25..29
+            }
+        ]
+    );
+    assert_eq!(segments[0].len(), 10);
+    assert!(m64.duplicate_segments(11).is_empty());
+
+    // the incomplete last frame is left out
+    let mut m64 = M64 {
+        controller_count: 2,
+        ..Default::default()
+    };
+    m64.extend([Input::default(); 3]);
+    assert!(m64.duplicate_segments(1).is_empty());
+    m64.extend([Input::default(); 2]);
+    assert_eq!(
+        m64.duplicate_segments(1),
+        [DuplicateSegment {
+            original: 0..1,
+            copy: 1..2
+        }]
+    );
+
+    let file = include_bytes!("./m64s/120 star tas (2012).m64");
+    let m64 = M64::from_u8_array(file).unwrap();
+    for segment in m64.duplicate_segments(300) {
+        assert!(segment.len() >= 300);
+        assert!(segment.original.end <= segment.copy.start);
+        assert_eq!(
+            m64.inputs[segment.original.clone()],
+            m64.inputs[segment.copy.clone()]
+        );
+    }
+}
+
+#[test]
+fn first_divergence_context() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64");
+    let m64 = M64::from_u8_array(file).unwrap();
+    assert_eq!(m64.first_divergence(&m64), None);
+
+    let mut edited = m64.clone();
+    edited.inputs[301].b_button = !edited.inputs[301].b_button;
+    let divergence = m64.first_divergence(&edited).unwrap();
+    assert_eq!(divergence.frame, 150);
+    assert_eq!(divergence.controller, 1);
+    assert_eq!(divergence.context_start, 150 - DEFAULT_DIVERGENCE_CONTEXT);
+    assert_eq!(divergence.ours.len(), DEFAULT_DIVERGENCE_CONTEXT * 2 + 1);
+    assert_eq!(
+        divergence.ours[DEFAULT_DIVERGENCE_CONTEXT].controller(1),
+        Some(m64.inputs[301])
+    );
+    assert_eq!(
+        divergence.theirs[DEFAULT_DIVERGENCE_CONTEXT].controller(1),
+        Some(edited.inputs[301])
+    );
+
+    edited.inputs = m64.inputs[..6].to_vec();
+    let divergence = m64.first_divergence_with_context(&edited, 10).unwrap();
+    assert_eq!(divergence.frame, 3);
+    assert_eq!(divergence.controller, 0);
+    assert_eq!(divergence.context_start, 0);
+    assert_eq!(divergence.ours.len(), 14);
+    assert_eq!(divergence.theirs.len(), 3);
+}
+
+#[test]
+fn three_way_merge() {
+    let mut base = M64 {
+        rerecords: 100,
+        ..Default::default()
+    };
+    base.extend((0..1000).map(|i| Input::from(i << 16)));
+    let pressed = Input::from(0x0080);
+
+    let mut ours = base.clone();
+    ours.inputs[10] = pressed;
+    ours.inputs.drain(500..510);
+    ours.rerecords = 150;
+    let mut theirs = base.clone();
+    theirs.inputs[10] = pressed;
+    theirs.inputs[700] = pressed;
+    theirs.inputs.insert(900, pressed);
+    theirs.rerecords = 120;
+
+    let merge = merge3(&base, &ours, &theirs);
+    assert!(merge.is_clean());
+    let mut expected = base.inputs.clone();
+    expected[10] = pressed;
+    expected[700] = pressed;
+    expected.insert(900, pressed);
+    expected.drain(500..510);
+    assert_eq!(merge.m64.inputs, expected);
+    assert_eq!(merge.m64.input_frames, 991);
+    assert_eq!(merge.m64.rerecords, 170);
+    theirs.set_total_rerecords((1 << 32) + 120);
+    let merge = merge3(&base, &ours, &theirs);
+    assert_eq!(merge.m64.total_rerecords(), (1 << 32) + 170);
+    theirs.set_total_rerecords(120);
+
+    theirs.inputs[505] = Input::from(0x0040);
+    let merge = merge3(&base, &ours, &theirs);
+    assert_eq!(merge.conflicts.len(), 1);
+    let conflict = &merge.conflicts[0];
+    assert_eq!(conflict.base, 500..510);
+    assert_eq!(conflict.merged, 500..500);
+    assert!(conflict.ours.is_empty());
+    assert_eq!(conflict.theirs[5], Input::from(0x0040));
+    assert_eq!(merge.m64.inputs, expected);
+}
+
+#[test]
+fn project_branches() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64");
+    let m64 = M64::from_u8_array(file).unwrap();
+    let mut project = M64Project::new(m64.clone());
+    assert_eq!(project.current_branch(), DEFAULT_BRANCH);
+    assert_eq!(project.inputs(), m64.inputs);
+    assert!(project.header().inputs.is_empty());
+
+    project.create_branch("faster").unwrap();
+    assert!(project.create_branch("faster").is_err());
+    project.switch_branch("faster").unwrap();
+    project.inputs_mut().truncate(1000);
+    project.header_mut().rerecords += 1;
+
+    assert_eq!(
+        project.branch_names().collect::<Vec<_>>(),
+        ["faster", "main"]
+    );
+    assert_eq!(project.branch(DEFAULT_BRANCH).unwrap().len(), 1667);
+    assert_eq!(
+        project
+            .diff_branches(DEFAULT_BRANCH, "faster")
+            .unwrap()
+            .len(),
+        2
+    );
+
+    let exported = project.export_current();
+    assert_eq!(exported.input_frames, 1000);
+    assert_eq!(exported.rerecords, m64.rerecords + 1);
+    assert_eq!(project.export(DEFAULT_BRANCH).unwrap().inputs, m64.inputs);
+
+    assert!(project.delete_branch("faster").is_err());
+    assert!(project.switch_branch("missing").is_err());
+    project.switch_branch(DEFAULT_BRANCH).unwrap();
+    assert_eq!(project.delete_branch("faster").unwrap().len(), 1000);
+}
+
+#[test]
+fn editor_rerecord_policy() {
+    let file = include_bytes!("./m64s/120 star tas (2012).m64");
+    let m64 = M64::from_u8_array(file).unwrap();
+
+    let edit = |policy| {
+        let mut m64 = m64.clone();
+        let mut editor = m64.editor(policy);
+        editor.set_input(0, Input::from(0x0080));
+        editor.strip_buttons(Buttons::B, 10..20);
+        editor.apply(|m64| m64.description.clear());
+        assert_eq!(editor.edit_count(), 3);
+        assert_eq!(editor.m64().inputs[0], Input::from(0x0080));
+        m64.rerecords
+    };
+
+    assert_eq!(edit(RerecordPolicy::Keep), m64.rerecords);
+    assert_eq!(edit(RerecordPolicy::PerEdit), m64.rerecords + 3);
+    assert_eq!(edit(RerecordPolicy::Once), m64.rerecords + 1);
+}
+
+#[test]
+fn content_equality() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64");
+    let m64 = M64::from_u8_array(file).unwrap();
+    let mut resubmitted = m64.clone();
+    resubmitted.uid += 1;
+    resubmitted.rerecords += 1000;
+    resubmitted.author.clear();
+    resubmitted.video_plugin.clear();
+    assert_ne!(m64, resubmitted);
+    assert!(m64.eq_content(&resubmitted));
+    assert!(m64.eq_inputs(&resubmitted));
+
+    let mut other_rom = resubmitted.clone();
+    other_rom.rom_crc_32 ^= 1;
+    assert!(!m64.eq_content(&other_rom));
+    assert!(m64.eq_inputs(&other_rom));
+
+    let movies = [&m64, &resubmitted, &other_rom]
+        .into_iter()
+        .map(ContentEq)
+        .collect::<std::collections::HashSet<_>>();
+    assert_eq!(movies.len(), 2);
+}
+
+#[test]
+fn normalize_movie() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64");
+    let mut m64 = M64::from_u8_array(file).unwrap();
+    // input_frames counts fewer samples than the file has
+    assert!(!m64.is_normalized());
+    m64.normalize();
+    assert!(m64.is_normalized());
+
+    let mut edited = m64.clone();
+    edited.author = PaddedString::truncated("author\0left over").0;
+    edited.inputs[0].reserved_1 = true;
+    edited.inputs[1].reserved_1 = true;
+    edited.inputs[1].reserved_2 = true;
+    edited.input_frames += 10;
+    assert!(!edited.is_normalized());
+    assert_ne!(edited.canonical_bytes(), m64.canonical_bytes());
+
+    edited.normalize();
+    assert!(edited.is_normalized());
+    assert_eq!(edited.author.as_str(), "author");
+    assert!(!edited.inputs[0].reserved_1);
+    assert!(edited.inputs[1].is_reset());
+    assert_eq!(edited.input_frames as usize, edited.inputs.len());
+}
+
+#[test]
+fn validate_controller_count() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64");
+    let mut m64 = M64::from_u8_array(file).unwrap();
+    m64.inputs.truncate(1666);
+    m64.input_frames = 1666;
+    assert!(m64.validate().is_empty());
+
+    m64.controller_flags[2].controller_present = true;
+    assert_eq!(
+        m64.validate(),
+        [
+            ValidationIssue::ControllerCountMismatch {
+                controller_count: 2,
+                present: 3
+            },
+            ValidationIssue::IncompleteFrame {
+                samples: 1666,
+                present: 3
+            }
+        ]
+    );
+}
+
+#[test]
+fn validate_absent_controller_input() {
+    let mut m64 = M64 {
+        controller_count: 2,
+        vi_frames: 20,
+        ..Default::default()
+    };
+    m64.extend([Input::default(); 20]);
+    m64.inputs[7].a_button = true;
+    m64.inputs[11].x_axis = 80;
+    m64.inputs[12].b_button = true;
+
+    assert_eq!(
+        m64.validate(),
+        [
+            ValidationIssue::ControllerCountMismatch {
+                controller_count: 2,
+                present: 1
+            },
+            ValidationIssue::AbsentControllerInput {
+                port: 1,
+                first_frame: 3,
+                frames: 2
+            }
+        ]
+    );
+}
+
+#[test]
+fn samples_and_frames() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64");
+    let mut m64 = M64::from_u8_array(file).unwrap();
+    assert_eq!(m64.sample_count(), 1667);
+    assert_eq!(m64.frame_count(), 834);
+    assert_eq!(m64.header_frame_count(), 600);
+    assert_eq!(m64.sample_to_frame(1665), (832, 1));
+    assert_eq!(m64.frame_range_to_samples(10..12), 20..24);
+
+    let frames = m64.remove_frames(10..12);
+    assert_eq!(frames.len(), 2);
+    assert!(frames.iter().all(|frame| frame.len() == 2));
+    assert_eq!(m64.sample_count(), 1663);
+    assert_eq!(m64.input_frames, 1196);
+
+    assert_eq!(m64.insert_frames(10, [Frame::new()]), Err(Frame::new()));
+    m64.insert_frames(10, frames).unwrap();
+    assert_eq!(m64, M64::from_u8_array(file).unwrap());
+}
+
+#[test]
+fn parse_never_panics() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64");
+    for len in 0..0x402 {
+        assert!(M64::from_u8_array(&file[..len]).is_err() == (len != 0x400));
+    }
+
+    for offset in [0x00, 0x04, 0x16, 0x1C, 0x1E, 0x24, 0xC4, 0xEA, 0x122, 0x300] {
+        let mut corrupted = file.to_vec();
+        corrupted[offset] = 0xFF;
+        corrupted[offset + 1] = 0xFF;
+        // the lenient parser reads the extended header from these bytes
+        let extended = (0x16..0x18).contains(&offset) || (0x24..0x44).contains(&offset);
+        assert!(M64::from_u8_array(&corrupted).is_err() || extended);
+        assert!(M64::from_u8_array_strict(&corrupted).is_err());
+    }
+}
+
+#[test]
+fn error_codes() {
+    let err = M64::from_u8_array(b"M64\x1A").unwrap_err();
+    assert_eq!(err.code(), "P004");
+    assert_eq!(err.severity(), Severity::Error);
+    let err = M64::from_u8_array(b"M65\x1A").unwrap_err();
+    assert_eq!(err.code(), "P001");
+
+    let issue = ValidationIssue::IncompleteFrame {
+        samples: 3,
+        present: 2,
+    };
+    assert_eq!(issue.code(), "V002");
+    assert_eq!(issue.severity(), Severity::Warning);
+
+    let m64 = M64::default();
+    let finding = vault_check(&m64)
+        .into_iter()
+        .find(|finding| finding.rule == LintRule::NoAuthor)
+        .unwrap();
+    assert_eq!(finding.code(), "L004");
+    assert_eq!(finding.severity(), Severity::Error);
+}
+
+#[test]
+fn movie_summary() {
+    let file = include_bytes!("./m64s/120 star tas (2012).m64");
+    let m64 = M64::from_u8_array(file).unwrap();
+    let summary = m64.summary().to_string();
+
+    let lines = summary.lines().collect::<Vec<_>>();
+    assert!(lines[0].starts_with("ROM:          SUPER MARIO 64 (CRC32 "));
+    assert_eq!(lines[2], "Length:       1:20:46.36 (290491 VIs)");
+    assert_eq!(lines[4], "Rerecords:    2136942");
+    assert!(lines.contains(&"Description:  18:08.33 saved over Rikku."));
+    assert_eq!(lines.last(), Some(&"UID:          1272727295"));
+    assert!(lines.contains(&"Resets:       125, first on frame 65843"));
+
+    // a single reset is shown too
+    let mut m64 = M64::from_u8_array(include_bytes!("./m64s/bitfs_noreds2.m64")).unwrap();
+    assert!(!m64.summary().to_string().contains("Resets:"));
+    m64.inputs[300].reserved_1 = true;
+    m64.inputs[300].reserved_2 = true;
+    let summary = m64.summary().to_string();
+    assert!(summary
+        .lines()
+        .any(|line| line == "Resets:       1, first on frame 150"));
+}
+
+#[test]
+fn hexdump_header() {
+    let file = include_bytes!("./m64s/120 star tas (2012).m64");
+    let m64 = M64::from_u8_array(file).unwrap();
+    let dump = m64.hexdump_header();
+
+    let lines = dump.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 76);
+    assert!(lines.iter().all(|line| !line.ends_with(' ')));
+    assert!(lines[0].starts_with("0x000  4D 36 34 1A"));
+    assert!(lines[0].ends_with("signature: \"M64\\u{1a}\""));
+    assert!(lines
+        .iter()
+        .any(|line| line.starts_with("0x008  ") && line.ends_with("uid: 1272727295")));
+    assert!(lines
+        .iter()
+        .any(|line| line.ends_with("rom_internal_name: \"SUPER MARIO 64\"")));
+}
+
+#[test]
+fn raw_header_round_trip() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64");
+    let m64 = M64::from_u8_array(file).unwrap();
+    let header = m64.raw_header();
+    assert_eq!(header, file[..0x400]);
+
+    let mut edited = header;
+    // rerecord count, written byte by byte
+    edited[0x010..0x014].copy_from_slice(&7u32.to_le_bytes());
+    let rebuilt = M64::from_header_and_inputs(&edited, m64.inputs.clone()).unwrap();
+    assert_eq!(rebuilt.rerecords, 7);
+    assert_eq!(rebuilt.inputs, m64.inputs);
+    assert_eq!(rebuilt.input_frames, m64.input_frames);
+
+    edited[0x01E] = 1;
+    assert!(matches!(
+        M64::from_header_and_inputs(&edited, []),
+        Err(M64ParseError::ReservedNotZero(0x1E))
+    ));
+}
+
+#[test]
+fn validate_movie() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64");
+    let mut m64 = M64::from_u8_array(file).unwrap();
+    m64.inputs.truncate(1666);
+    m64.input_frames = 1666;
+    assert!(m64.validate().is_empty());
+
+    m64.input_frames += 1;
+    m64.fps = 50;
+    m64.inputs[10].reserved_1 = true;
+    m64.inputs[20].reserved_2 = true;
+    for frame in 100..103 {
+        m64.inputs[frame * 2].reserved_1 = true;
+        m64.inputs[frame * 2].reserved_2 = true;
+    }
+    m64.author = PaddedString::truncated(&"a".repeat(222)).0;
+
+    let issues = m64.validate();
+    assert_eq!(
+        issues,
+        [
+            ValidationIssue::InputFramesMismatch {
+                input_frames: 1667,
+                samples: 1666
+            },
+            ValidationIssue::RegionFpsMismatch {
+                fps: 50,
+                region: Region::Ntsc
+            },
+            ValidationIssue::ReservedBitSet {
+                first_sample: 10,
+                samples: 2
+            },
+            ValidationIssue::ConsecutiveResets {
+                first_frame: 100,
+                frames: 3
+            },
+            ValidationIssue::UnterminatedString(FieldName::Author),
+        ]
+    );
+    assert_eq!(issues[0].code(), "V004");
+    assert_eq!(issues[4].severity(), Severity::Info);
+    assert_eq!(
+        issues[0].suggested_fix(),
+        "set input_frames to the number of input samples"
+    );
+}
+
+#[test]
+fn repair_movie() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64");
+    let mut m64 = M64::from_u8_array(file).unwrap();
+    m64.inputs[10].reserved_2 = true;
+    m64.description = PaddedString::truncated("tas\0old description").0;
+
+    let repairs = m64.repair(RepairOptions::default());
+    assert_eq!(
+        repairs,
+        [
+            Repair::PaddedIncompleteFrame { samples: 1 },
+            Repair::RecountedInputFrames {
+                from: 1200,
+                to: 1668
+            },
+            Repair::ClearedReservedBits { samples: 1 },
+            Repair::NormalizedString(FieldName::Description),
+        ]
+    );
+    assert!(m64.validate().is_empty());
+    assert!(m64.repair(RepairOptions::default()).is_empty());
+
+    let mut m64 = M64::from_u8_array(file).unwrap();
+    let options = RepairOptions {
+        pad_incomplete_frame: false,
+        ..Default::default()
+    };
+    assert_eq!(
+        m64.repair(options),
+        [Repair::RecountedInputFrames {
+            from: 1200,
+            to: 1667
+        }]
+    );
+}
+
+#[test]
+fn metadata_only() {
+    let file = include_bytes!("./m64s/120 star tas (2012).m64");
+    let star = M64::from_u8_array(file).unwrap();
+    let metadata = M64Metadata::from(&star);
+    assert_eq!(metadata, star.metadata());
+    assert_eq!(metadata.with_inputs(star.inputs.clone()), star);
+
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64");
+    let mut bitfs = M64::from_u8_array(file).unwrap();
+    let inputs = bitfs.inputs.clone();
+    metadata.apply_to(&mut bitfs);
+    assert_eq!(bitfs.metadata(), metadata);
+    assert_eq!(bitfs.inputs, inputs);
+}
+
+#[test]
+fn vertical_rate() {
+    let file = include_bytes!("./m64s/120 star tas (2012).m64");
+    let mut m64 = M64::from_u8_array(file).unwrap();
+    let rate = m64.vertical_rate();
+    assert_eq!(rate, VerticalRate::new(60, Region::Ntsc));
+    assert_eq!(rate.stored(), 60);
+    assert_eq!(rate.nominal(), 60);
+    assert!((rate.exact() - 59.94).abs() < 0.01);
+    assert!(rate.is_consistent());
+
+    assert!((m64.input_frames_per_second() - 28.984).abs() < 0.001);
+    assert_eq!(
+        m64.frame_time(m64.frame_count()).as_millis(),
+        m64.duration().as_millis()
+    );
+
+    m64.fps = 50;
+    assert!(!m64.vertical_rate().is_consistent());
+    assert_eq!(m64.vertical_rate().region(), Region::Ntsc);
+}
+
+#[test]
+fn existing_snapshot_start_type() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64");
+    let mut bytes = file.to_vec();
+    bytes[0x1C] = 8;
+    let m64 = M64::from_u8_array(&bytes).unwrap();
+    assert_eq!(m64.movie_start_type, MovieStartType::ExistingSnapshot);
+    assert!(m64.movie_start_type.is_snapshot());
+    assert!(!MovieStartType::PowerOn.is_snapshot());
+    assert_eq!(m64.to_bytes(), bytes);
+    assert!(vault_check(&m64)
+        .iter()
+        .any(|finding| finding.rule == LintRule::SnapshotStart));
+}
+
+#[test]
+fn public_sub_parsers() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64");
+    let m64 = M64::from_u8_array(file).unwrap();
+
+    // a movie embedded in a bundle, followed by other data
+    let mut bundle = b"BNDL".to_vec();
+    bundle.extend_from_slice(&file[..parser::HEADER_SIZE + 8]);
+    bundle.extend_from_slice(b"rest");
+
+    let (rest, header) = parser::header(&bundle[4..]).unwrap();
+    assert_eq!(header, m64.metadata());
+    let (rest, first) = parser::input(rest).unwrap();
+    let (rest, second) = parser::input(rest).unwrap();
+    assert_eq!([first, second], m64.inputs[..2]);
+    assert_eq!(rest, b"rest");
+
+    assert!(parser::signature(b"M64\x1A").is_ok());
+    assert!(parser::signature(b"BNDL").is_err());
+    assert!(parser::version(&2u32.to_le_bytes()).is_err());
+
+    // errors name the field and keep the input they failed on
+    let Err(nom::Err::Error(err)) = parser::header(&file[..0x0C4 + 8]) else {
+        panic!("header parsed from a truncated file");
+    };
+    assert_eq!(
+        err.field,
+        Some(ParseField::Header(FieldName::RomInternalName))
+    );
+    assert_eq!(err.kind, nom::error::ErrorKind::Eof);
+    assert_eq!(err.input.len(), 8);
+}
+
+#[test]
+fn detect_movie_format() {
+    let detected = |format, confidence| DetectedFormat { format, confidence };
+
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64");
+    assert_eq!(
+        detect_format(file),
+        detected(MovieFileFormat::M64, Confidence::High)
+    );
+    assert_eq!(
+        detect_format(&file[..100]),
+        detected(MovieFileFormat::M64, Confidence::Medium)
+    );
+
+    let m64 = M64::from_u8_array(file).unwrap();
+    let mut tasd = Vec::new();
+    m64.write_tasd(&mut tasd).unwrap();
+    assert_eq!(
+        detect_format(&tasd),
+        detected(MovieFileFormat::Tasd, Confidence::High)
+    );
+
+    let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+    gzip.write_all(file).unwrap();
+    let gzip = gzip.finish().unwrap();
+    assert_eq!(
+        detect_format(&gzip),
+        detected(MovieFileFormat::Gzip, Confidence::High)
+    );
+
+    assert_eq!(
+        detect_format(b"PK\x03\x04\x14\x00\x00\x00\x08\x00"),
+        detected(MovieFileFormat::Bk2, Confidence::Low)
+    );
+    assert_eq!(
+        detect_format(b"garbage"),
+        detected(MovieFileFormat::Unknown, Confidence::Low)
+    );
+
+    let err = M64::from_u8_array(&gzip).unwrap_err();
+    assert!(matches!(
+        err,
+        M64ParseError::WrongFormat(MovieFileFormat::Gzip)
+    ));
+    assert_eq!(err.code(), "P010");
+}
+
+#[test]
+fn movie_format_trait() {
+    let file = include_bytes!("./m64s/120 star tas (2012).m64");
+    let m64 = M64::from_u8_array(file).unwrap();
+    let movie: Box<dyn MovieFormat> = Box::new(m64.clone());
+
+    assert_eq!(movie.format_name(), "m64");
+    assert_eq!(movie.rom_name(), "SUPER MARIO 64");
+    assert_eq!(movie.description(), "18:08.33 saved over Rikku.");
+    assert_eq!(movie.rerecords(), 2136942);
+    assert_eq!(movie.controller_count(), 1);
+    assert_eq!(movie.frame_count(), 140467);
+    assert_eq!(movie.frames().nth(1000), m64.frames().nth(1000));
+    assert_eq!(movie.to_m64(), m64);
+
+    let mut bytes = Vec::new();
+    movie.write_movie(&mut bytes).unwrap();
+    assert_eq!(bytes, file);
+
+    // a format that only has the inputs of 2 controllers, converted with the provided method
+    struct Inputs(Vec<Frame>);
+    impl MovieFormat for Inputs {
+        fn format_name(&self) -> &'static str {
+            "inputs"
+        }
+        fn author(&self) -> &str {
+            "author"
+        }
+        fn description(&self) -> &str {
+            ""
+        }
+        fn rom_name(&self) -> &str {
+            ""
+        }
+        fn rerecords(&self) -> u64 {
+            u64::MAX
+        }
+        fn controller_count(&self) -> usize {
+            2
+        }
+        fn frame_count(&self) -> usize {
+            self.0.len()
+        }
+        fn frames(&self) -> Box<dyn Iterator<Item = Frame> + '_> {
+            Box::new(self.0.iter().cloned())
+        }
+        fn write_movie(&self, _: &mut dyn Write) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let frame = Frame::from_inputs(&[Input::from(1), Input::from(2)]).unwrap();
+    let converted = Inputs(vec![frame.clone(); 3]).to_m64();
+    assert_eq!(converted.rerecords, u32::MAX);
+    assert_eq!(converted.controller_count, 2);
+    assert!(converted.controller_flags[1].controller_present);
+    assert_eq!(converted.author.as_str(), "author");
+    assert_eq!(converted.input_frames, 6);
+    assert_eq!(converted.frames().last(), Some(frame));
+}
+
+#[test]
+fn convert_tasd() {
+    let mut m64 = M64::from_u8_array(include_bytes!("./m64s/bitfs_noreds2.m64")).unwrap();
+    m64.inputs.truncate(1666);
+    m64.input_frames = 1666;
+
+    let tasd = Converted::<TasdFile>::try_from(&m64).unwrap();
+    assert!(!tasd.report.lossy_inputs);
+    assert!(tasd.report.lossy_fields.contains(&FieldName::Uid));
+    assert!(tasd.report.lossy_fields.contains(&FieldName::RomCrc32));
+    assert!(!tasd.report.lossy_fields.contains(&FieldName::Author));
+    assert!(!tasd.report.lossy_fields.contains(&FieldName::Rerecords));
+    assert!(tasd.report.unmapped.is_empty());
+
+    let mut file = tasd.movie;
+    // an unknown packet with a 1 byte payload
+    file.0.extend_from_slice(&[0xAB, 0xCD, 1, 1, 0]);
+    let converted = Converted::<M64>::try_from(&file).unwrap();
+    assert_eq!(converted.movie.inputs, m64.inputs);
+    assert_eq!(converted.movie.author, m64.author);
+    assert_eq!(
+        converted.report.unmapped,
+        vec![("packet".to_string(), "0xABCD".to_string())]
+    );
+    assert!(!converted.report.is_lossless());
+
+    assert!(Converted::<M64>::try_from(&TasdFile(b"TASB".to_vec())).is_err());
+}
+
+#[test]
+fn extended_header() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64");
+    let m64 = M64::from_u8_array(file).unwrap();
+    assert_eq!(m64.extended, None);
+    assert_eq!(M64::from_u8_array_strict(file).unwrap(), m64);
+
+    let mut extended = ExtendedHeader::new();
+    extended.flags.wii_vc = true;
+    extended.bruteforce_data = 0xDEADBEEF;
+    let m64 = M64 {
+        extended: Some(extended),
+        ..m64
+    };
+    let bytes = m64.to_bytes();
+    assert_eq!(bytes[0x16..0x18], [1, 1]);
+    assert_eq!(&bytes[0x24..0x28], b"MUPN");
+    assert_eq!(bytes[0x28..0x2C], 0xDEADBEEFu32.to_le_bytes());
+
+    let parsed = M64::from_u8_array(&bytes).unwrap();
+    assert_eq!(parsed, m64);
+    assert_eq!(
+        parsed
+            .extended
+            .and_then(|extended| extended.authorship().map(str::to_string)),
+        Some("MUPN".to_string())
+    );
+    assert!(matches!(
+        M64::from_u8_array_strict(&bytes),
+        Err(M64ParseError::ReservedNotZero(0x16))
+    ));
+
+    // extended flags without an extended version
+    let mut invalid = bytes.clone();
+    invalid[0x16] = 0;
+    assert!(matches!(
+        M64::from_u8_array(&invalid),
+        Err(M64ParseError::ReservedNotZero(0x16))
+    ));
+    // the reserved bytes after the extended data
+    let mut invalid = bytes;
+    invalid[0x50] = 1;
+    assert!(matches!(
+        M64::from_u8_array(&invalid),
+        Err(M64ParseError::ReservedNotZero(0x24))
+    ));
+
+    // an extended header with version 0 is written as no extended header
+    extended.version = 0;
+    let m64 = M64 {
+        extended: Some(extended),
+        ..m64
+    };
+    let bytes = m64.to_bytes();
+    assert!(bytes[0x16..0x18].iter().all(|&b| b == 0));
+    assert!(bytes[0x24..0x44].iter().all(|&b| b == 0));
+    let parsed = M64::from_u8_array(&bytes).unwrap();
+    assert_eq!(parsed.extended, None);
+}
+
+#[test]
+fn wii_vc_flag() {
+    assert_eq!(
+        ExtendedFlags::from_u8(0x81),
+        ExtendedFlags {
+            wii_vc: true,
+            reserved: 0x80
+        }
+    );
+    assert_eq!(ExtendedFlags::from_u8(0x81).to_u8(), 0x81);
+
+    let mut m64 = M64::from_u8_array(include_bytes!("./m64s/bitfs_noreds2.m64")).unwrap();
+    assert!(!m64.is_wii_vc());
+    m64.set_wii_vc(false);
+    assert_eq!(m64.extended, None);
+
+    m64.set_wii_vc(true);
+    assert!(m64.is_wii_vc());
+    let bytes = m64.to_bytes();
+    assert_eq!(bytes[0x17], 1);
+    assert!(M64::from_u8_array(&bytes).unwrap().is_wii_vc());
+    assert!(m64.summary().to_string().contains("Timing:       Wii VC\n"));
+
+    m64.set_wii_vc(false);
+    assert!(!m64.is_wii_vc());
+    assert_eq!(m64.to_bytes()[0x17], 0);
+}
+
+#[test]
+fn extended_rerecords() {
+    let mut m64 = M64::from_u8_array(include_bytes!("./m64s/bitfs_noreds2.m64")).unwrap();
+    assert_eq!(m64.total_rerecords(), m64.rerecords as u64);
+    m64.set_total_rerecords(12);
+    assert_eq!(m64.extended, None);
+
+    let count = (3 << 32) | 5;
+    m64.set_total_rerecords(count);
+    assert_eq!(m64.rerecords, 5);
+    assert_eq!(m64.extended.unwrap().rerecords_high, 3);
+    let bytes = m64.to_bytes();
+    assert_eq!(bytes[0x2C..0x30], 3u32.to_le_bytes());
+    let parsed = M64::from_u8_array(&bytes).unwrap();
+    assert_eq!(parsed.total_rerecords(), count);
+
+    let mut editor = m64.editor(RerecordPolicy::PerEdit);
+    editor.apply(|_| ());
+    assert_eq!(m64.total_rerecords(), count + 1);
+    m64.set_total_rerecords(u32::MAX as u64);
+    m64.editor(RerecordPolicy::Once).apply(|_| ());
+    assert_eq!(m64.total_rerecords(), 1 << 32);
+
+    let mut tasd = Vec::new();
+    m64.write_tasd(&mut tasd).unwrap();
+    assert_eq!(M64::from_tasd(&tasd).unwrap().total_rerecords(), 1 << 32);
+}
+
+#[test]
+fn metadata_block() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64");
+    let mut m64 = M64::from_u8_array(file).unwrap();
+    assert!(m64.metadata_block.is_empty());
+    assert_eq!(m64.to_bytes(), file);
+
+    let authors = "Alice, Bob, Ça, 名前";
+    assert_eq!(m64.metadata_block.insert(AUTHORS, authors), Ok(None));
+    m64.metadata_block
+        .insert(URL, "https://tasvideos.org")
+        .unwrap();
+    m64.metadata_block.insert("tool.data", [1, 2, 3]).unwrap();
+    assert_eq!(
+        m64.metadata_block.insert(URL, "https://example.com"),
+        Ok(Some(b"https://tasvideos.org".to_vec()))
+    );
+    // keys have to fit their u16 length
+    let long_key = "k".repeat(0x10000);
+    assert_eq!(
+        m64.metadata_block.insert(long_key.as_str(), []),
+        Err(MetadataBlockError::KeyTooLong(0x10000))
+    );
+    assert_eq!(m64.metadata_block.entries().len(), 3);
+    assert!(MetadataBlock::try_from(vec![(long_key, Vec::new())]).is_err());
+
+    let bytes = m64.to_bytes();
+    assert_eq!(&bytes[bytes.len() - 4..], b"M64M");
+    assert!((bytes.len() - file.len()).is_multiple_of(4));
+    let parsed = M64::from_u8_array(&bytes).unwrap();
+    assert_eq!(parsed, m64);
+    assert_eq!(parsed.metadata_block.get_str(AUTHORS), Some(authors));
+    assert_eq!(parsed.metadata_block.get("tool.data"), Some(&[1, 2, 3][..]));
+
+    // strict parsers read the block as extra input samples
+    let strict = M64::from_u8_array_strict(&bytes).unwrap();
+    assert!(strict.metadata_block.is_empty());
+    assert!(strict.inputs.len() > m64.inputs.len());
+    assert_eq!(strict.input_frames, m64.input_frames);
+
+    assert_eq!(m64.metadata_block.remove(AUTHORS), Some(authors.into()));
+    assert_eq!(m64.metadata_block.get(AUTHORS), None);
+    assert_eq!(MetadataBlock::split_from(&file[0x400..]), None);
+
+    // input samples that look like an empty block are kept
+    let mut m64 = M64::from_u8_array(file).unwrap();
+    m64.metadata_block = MetadataBlock::default();
+    m64.inputs.push(Input::from(0));
+    m64.inputs.push(Input::from(u32::from_le_bytes(*b"M64M")));
+    m64.input_frames = m64.inputs.len() as u32;
+    let bytes = m64.to_bytes();
+    assert!(bytes.ends_with(b"\0\0\0\0M64M"));
+    assert_eq!(MetadataBlock::split_from(&bytes[0x400..]), None);
+    assert_eq!(M64::from_u8_array(&bytes).unwrap(), m64);
+    // a block inside the declared input samples is read as inputs
+    m64.metadata_block
+        .insert(URL, "https://tasvideos.org")
+        .unwrap();
+    let mut bytes = m64.to_bytes();
+    let samples = (bytes.len() - 0x400) / 4;
+    bytes[0x018..0x01C].copy_from_slice(&(samples as u32).to_le_bytes());
+    let parsed = M64::from_u8_array(&bytes).unwrap();
+    assert!(parsed.metadata_block.is_empty());
+    assert_eq!(parsed.inputs.len(), samples);
+}
+
+#[test]
+fn padded_string() {
+    let m64 = M64::from_u8_array(include_bytes!("./m64s/120 star tas (2012).m64")).unwrap();
+    assert!(!m64.rom_internal_name.as_str().ends_with('\0'));
+    assert_eq!(m64.rom_internal_name.as_padded_str().len(), 32);
+    assert_eq!(
+        m64.rom_internal_name.to_string(),
+        m64.rom_internal_name.as_str()
+    );
+
+    let (name, cut) = PaddedString::<4>::truncated("ab名");
+    assert!(cut);
+    assert_eq!(name, "ab");
+    assert_eq!(name.as_padded_str(), "ab\0\0");
+    assert!(PaddedString::<4>::try_from("abcde").is_err());
+
+    let mut name = PaddedString::<4>::try_from("a\0b").unwrap();
+    assert_eq!(name, "a\0b");
+    assert!(name.normalize());
+    assert_eq!(name.as_padded_str(), "a\0\0\0");
+    assert!(!name.normalize());
+}
+
+#[test]
+fn from_path() {
+    let file = include_bytes!("./m64s/120 star tas (2012).m64");
+    let dir = env::temp_dir().join(format!("m64-from-path-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let path = dir.join("movie.m64");
+    fs::write(&path, file).unwrap();
+    assert_eq!(
+        M64::from_path(&path).unwrap(),
+        M64::from_u8_array(file).unwrap()
+    );
+
+    let missing = dir.join("missing.m64");
+    let err = M64::from_path(&missing).unwrap_err();
+    assert!(matches!(err, LoadError::Open { .. }));
+    assert_eq!(err.path(), missing);
+    assert!(err.to_string().contains("missing.m64"));
+
+    // directories can be opened but not read on unix
+    #[cfg(unix)]
+    assert!(matches!(
+        M64::from_path(&dir).unwrap_err(),
+        LoadError::Read { .. }
+    ));
+
+    fs::write(&path, b"not a movie").unwrap();
+    let err = M64::from_path(&path).unwrap_err();
+    assert!(matches!(err, LoadError::Parse { .. }));
+    assert_eq!(err.path(), path);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn raw_header() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64");
+    let mut m64 = M64::from_u8_array(file).unwrap();
+    assert_eq!(m64.raw_header(), file[..0x400]);
+
+    m64.set_total_rerecords(u64::MAX);
+    m64.metadata_block
+        .insert(URL, "https://tasvideos.org")
+        .unwrap();
+    let bytes = m64.to_bytes();
+    assert_eq!(bytes.len(), bytes.capacity());
+    assert_eq!(bytes[..0x400], m64.raw_header());
+
+    let mut written = Vec::new();
+    m64.write_m64(&mut written).unwrap();
+    assert_eq!(written, bytes);
+    assert_eq!(M64::from_u8_array(&bytes).unwrap(), m64);
+}
+
+#[test]
+fn in_place_editing() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64");
+    let mut m64 = M64::from_u8_array(file).unwrap();
+    m64.metadata_block
+        .insert(URL, "https://tasvideos.org")
+        .unwrap();
+    let mut bytes = m64.to_bytes();
+
+    let mut movie = InPlaceM64::new(&mut bytes).unwrap();
+    assert_eq!(movie.sample_count(), m64.inputs.len());
+    assert_eq!(movie.input(5), Some(m64.inputs[5]));
+    assert_eq!(movie.input(m64.inputs.len()), None);
+
+    let input = Input::from(0x1234_8000);
+    assert_eq!(movie.set_input(5, input).unwrap(), m64.inputs[5]);
+    assert!(matches!(
+        movie.set_input(m64.inputs.len(), input),
+        Err(InPlaceError::InputOutOfRange { .. })
+    ));
+
+    movie
+        .set_field(FieldName::Rerecords, &4321u32.to_le_bytes())
+        .unwrap();
+    assert!(matches!(
+        movie.set_field(FieldName::Rerecords, &[0; 2]),
+        Err(InPlaceError::FieldSize { expected: 4, .. })
+    ));
+    // the field is checked before it's written
+    assert!(matches!(
+        movie.set_field(FieldName::MovieStartType, &[9, 0]),
+        Err(InPlaceError::Parse(M64ParseError::InvalidMovieStartType))
+    ));
+
+    let mut header = movie.header();
+    assert_eq!(header.rerecords, 4321);
+    header.author = PaddedString::truncated("in place").0;
+    movie.set_header(&header).unwrap();
+    // the header is checked before it's written
+    let mut unversioned = header.clone();
+    unversioned.extended = Some(ExtendedHeader {
+        version: 0,
+        flags: ExtendedFlags {
+            wii_vc: true,
+            reserved: 0,
+        },
+        ..ExtendedHeader::new()
+    });
+    // an extended header with version 0 isn't written
+    movie.set_header(&unversioned).unwrap();
+    assert_eq!(movie.header(), header);
+
+    m64.inputs[5] = input;
+    m64.rerecords = 4321;
+    m64.author = header.author;
+    assert_eq!(M64::from_u8_array(&bytes).unwrap(), m64);
+
+    let mut short = file[..0x402].to_vec();
+    assert!(matches!(
+        InPlaceM64::new(&mut short),
+        Err(InPlaceError::Parse(M64ParseError::InputNot4BytesAligned(2)))
+    ));
+}
+
+#[test]
+fn tracked_changes() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64");
+    let mut m64 = M64::from_u8_array(file).unwrap();
+    m64.input_frames = m64.inputs.len() as u32;
+    let mut saved = m64.to_bytes();
+    let mut tracked = TrackedM64::new(m64);
+    assert!(!tracked.is_dirty());
+    assert!(tracked.take_changes().is_empty());
+
+    let input = Input::from(0x1234_8000);
+    tracked.set_input(11, input);
+    tracked.frames_mut(3..4)[1] = input;
+    tracked.edit_header(|header| header.rerecords += 1);
+    assert!(tracked.is_dirty());
+    assert_eq!(tracked.changed_fields(), [FieldName::Rerecords]);
+    assert_eq!(tracked.changed_frames(), [3..4, 5..6]);
+
+    let changes = tracked.take_changes();
+    assert_eq!(changes.len, saved.len());
+    assert_eq!(
+        changes
+            .changes
+            .iter()
+            .map(|change| (change.offset, change.bytes.len()))
+            .collect::<Vec<_>>(),
+        [(0x010, 1), (0x400 + 6 * 4, 8), (0x400 + 11 * 4, 4)]
+    );
+    changes.apply(&mut saved);
+    assert_eq!(saved, tracked.to_bytes());
+    assert!(!tracked.is_dirty());
+
+    // removing frames moves the metadata block, and shortens the file
+    tracked.edit_header(|header| {
+        header
+            .metadata_block
+            .insert(URL, "https://tasvideos.org")
+            .unwrap();
+    });
+    tracked.replace_frames(100..110, []);
+    tracked.edit_header(|header| header.input_frames -= 20);
+    let changes = tracked.take_changes();
+    assert!(changes.len < saved.len());
+    changes.apply(&mut saved);
+    assert_eq!(saved, tracked.to_bytes());
+    assert_eq!(M64::from_u8_array(&saved).unwrap(), *tracked);
 }
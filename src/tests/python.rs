@@ -0,0 +1,52 @@
+use pyo3::{
+    ffi::c_str,
+    prelude::*,
+    types::{PyBytes, PyDict},
+};
+
+use crate::python::{PyInput, PyM64};
+
+#[test]
+fn python_module() {
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let file = include_bytes!("./m64s/bitfs_noreds2.m64");
+        let locals = PyDict::new(py);
+        locals.set_item("data", PyBytes::new(py, file)).unwrap();
+        locals.set_item("M64", py.get_type::<PyM64>()).unwrap();
+        locals.set_item("Input", py.get_type::<PyInput>()).unwrap();
+
+        py.run(
+            c_str!(
+                r#"
+m64 = M64.from_bytes(data)
+assert m64.rom_internal_name == "SM64 Multiplayer"
+assert m64.rerecords == 3377
+assert len(m64) == 1667
+assert m64.movie_start_type == "snapshot"
+
+m64.author = "someone"
+assert m64.author == "someone"
+try:
+    m64.author = "a" * 300
+    assert False
+except ValueError:
+    pass
+
+m64.set_input(0, Input(0x0080))
+assert m64[0].buttons == ["A"] and m64[0].pressed("A")
+assert m64.inputs[0] == Input(0x0080)
+assert len(m64.controller_inputs(1)) == 833
+
+stats = m64.stats()
+assert len(stats) == 2 and stats[0]["frames"] == 834
+assert m64.diff(m64) == []
+assert M64.from_bytes(m64.to_bytes()).author == "someone"
+"#
+            ),
+            None,
+            Some(&locals),
+        )
+        .unwrap();
+    });
+}
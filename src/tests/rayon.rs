@@ -0,0 +1,62 @@
+use crate::{analysis::pattern::InputPredicate, controller::Input, m64::M64, stats::InputStats};
+
+#[test]
+fn parallel_input_decoding() {
+    let mut m64 = M64::default();
+    m64.extend((0..200_000u32).map(|i| Input::from(i.wrapping_mul(2_654_435_761))));
+
+    let mut written = Vec::new();
+    m64.write_m64(&mut written).unwrap();
+    assert_eq!(M64::from_u8_array(&written).unwrap(), m64);
+
+    written.push(0);
+    assert!(M64::from_u8_array(&written).is_err());
+}
+
+#[test]
+fn parallel_analysis() {
+    // long holds and a pattern that crosses the chunk boundaries
+    let inputs = (0..100_000u32)
+        .map(|i| Input::from(if (i / 777) % 3 == 0 { 0x8000 } else { i % 5 }))
+        .collect::<Vec<_>>();
+    let mut m64 = M64 {
+        controller_count: 2,
+        ..Default::default()
+    };
+    m64.extend(inputs.iter().copied());
+
+    let stats = m64.stats();
+    for (controller, stats) in stats.iter().enumerate() {
+        let sequential = InputStats::from_inputs(
+            inputs.iter().skip(controller).step_by(2).copied(),
+            m64.input_frames_per_second(),
+        );
+        assert_eq!(*stats, sequential);
+    }
+
+    let a = Input::from(0x8000);
+    let pattern = [
+        InputPredicate::Exact(a),
+        InputPredicate::new(move |input| *input != a),
+    ];
+    let expected = m64
+        .inputs_for_controller(0)
+        .collect::<Vec<_>>()
+        .windows(2)
+        .enumerate()
+        .filter(|(_, window)| window[0] == a && window[1] != a)
+        .map(|(frame, _)| frame)
+        .collect::<Vec<_>>();
+    assert!(!expected.is_empty());
+    assert_eq!(m64.find_pattern(0, &pattern), expected);
+
+    let mut other = m64.clone();
+    other.inputs[80_001] = Input::from(1);
+    other.inputs[90_000] = Input::from(1);
+    let divergence = m64.first_divergence(&other).unwrap();
+    assert_eq!(
+        (divergence.frame, divergence.controller),
+        (40_000, 1),
+        "{divergence:?}"
+    );
+}
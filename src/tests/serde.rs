@@ -0,0 +1,16 @@
+use crate::m64::M64;
+
+#[test]
+fn serde_round_trip() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64").to_vec();
+    let m64 = M64::from_u8_array(&file).unwrap();
+
+    let json = serde_json::to_string(&m64).unwrap();
+    assert!(json.contains(r#""movie_start_type":"SnapShot""#));
+    let deserialized = serde_json::from_str::<M64>(&json).unwrap();
+    assert_eq!(deserialized, m64);
+
+    let mut written = Vec::new();
+    deserialized.write_m64(&mut written).unwrap();
+    assert_eq!(written, file);
+}
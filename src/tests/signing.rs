@@ -0,0 +1,41 @@
+use std::{env, fs};
+
+use crate::{
+    error::SignatureError,
+    m64::M64,
+    signing::{signature_path, MovieSignature, SigningKey},
+};
+
+#[test]
+fn sign_and_verify() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64");
+    let mut m64 = M64::from_u8_array(file).unwrap();
+    let key = SigningKey::from_bytes(&[7; 32]);
+
+    let signature = m64.sign(&key);
+    assert_eq!(signature.key, key.verifying_key());
+    m64.verify_signature(&signature).unwrap();
+    assert_eq!(
+        MovieSignature::from_bytes(&signature.to_bytes()).unwrap(),
+        signature
+    );
+
+    m64.inputs[100].a_button = !m64.inputs[100].a_button;
+    assert!(matches!(
+        m64.verify_signature(&signature),
+        Err(SignatureError::Mismatch)
+    ));
+
+    let path = env::temp_dir().join(format!("m64-signing-{}.m64", std::process::id()));
+    fs::write(&path, file).unwrap();
+    assert_eq!(M64::sign_file(&path, &key).unwrap(), signature);
+    assert_eq!(M64::verify_file(&path).unwrap(), signature);
+
+    fs::write(&path, m64.to_bytes()).unwrap();
+    assert!(matches!(
+        M64::verify_file(&path),
+        Err(SignatureError::Mismatch)
+    ));
+    fs::remove_file(signature_path(&path)).unwrap();
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,57 @@
+use crate::{
+    controller::Input,
+    games::sm64::{a_presses, effective_stick, intended_magnitude, long_jumps},
+    m64::M64,
+};
+
+#[test]
+fn sm64_stick() {
+    assert_eq!(effective_stick(7, -7).magnitude, 0.0);
+    let stick = effective_stick(8, -20);
+    assert_eq!((stick.x, stick.y), (2.0, -14.0));
+    assert_eq!(effective_stick(127, 127).magnitude, 64.0);
+
+    assert_eq!(intended_magnitude(0, 127), 32.0);
+    assert_eq!(intended_magnitude(0, 38), 8.0);
+    assert_eq!(intended_magnitude(-5, 3), 0.0);
+}
+
+#[test]
+fn sm64_presses_and_long_jumps() {
+    let mut m64 = M64::default();
+    // Z, then A 2 frames later, then a held Z with a late A, which is a backflip
+    let a = Input::from(0x0080);
+    let z = Input::from(0x0020);
+    let za = Input::from(0x00A0);
+    m64.extend([
+        Input::default(),
+        z,
+        z,
+        za,
+        za,
+        Input::default(),
+        z,
+        z,
+        z,
+        z,
+        z,
+        z,
+        za,
+        a,
+    ]);
+
+    assert_eq!(a_presses(&m64, 0), 2);
+    assert_eq!(long_jumps(&m64, 0, 3), [3]);
+    assert!(long_jumps(&m64, 0, 1).is_empty());
+    assert_eq!(long_jumps(&m64, 0, 10), [3, 12]);
+
+    let file = include_bytes!("./m64s/120 star tas (2012).m64");
+    let m64 = M64::from_u8_array(file).unwrap();
+    assert_eq!(
+        a_presses(&m64, 0),
+        m64.stats()[0]
+            .button(crate::controller::Buttons::A)
+            .unwrap()
+            .presses
+    );
+}
@@ -0,0 +1,10 @@
+use crate::m64::M64;
+
+#[test]
+fn recording_offset_date_time() {
+    let file = include_bytes!("./m64s/120 star tas (2012).m64");
+    let m64 = M64::from_u8_array(file).unwrap();
+    let time = m64.recording_offset_date_time();
+    assert_eq!(time.unix_timestamp(), 1272727295);
+    assert_eq!(time.offset(), ::time::UtcOffset::UTC);
+}
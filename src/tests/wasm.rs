@@ -0,0 +1,17 @@
+use crate::wasm::WasmM64;
+
+#[test]
+fn wasm_bindings() {
+    let file = include_bytes!("./m64s/bitfs_noreds2.m64");
+    let m64 = WasmM64::parse(file).unwrap_or_else(|_| panic!("movie should parse"));
+
+    assert_eq!(m64.rom_internal_name(), "SM64 Multiplayer");
+    assert_eq!(m64.movie_start_type(), "snapshot");
+    assert_eq!(m64.input_count(), 1667);
+    assert_eq!(m64.inputs().len(), 1667);
+    assert_eq!(m64.controller_inputs(1).len(), 833);
+    assert_eq!(m64.input(0).unwrap().raw(), m64.raw_inputs()[0]);
+    assert!(m64.input(1667).is_none());
+    assert_eq!(m64.to_bytes(), file);
+    assert!(m64.to_json().starts_with('{'));
+}
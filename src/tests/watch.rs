@@ -0,0 +1,50 @@
+use std::{env, fs};
+
+use crate::{controller::Input, m64::M64, watch::MovieWatcher};
+
+#[test]
+fn watch_recording() {
+    let path = env::temp_dir().join(format!("m64-watch-{}.m64", std::process::id()));
+    fs::write(&path, []).unwrap();
+    let mut watcher = MovieWatcher::open(&path).unwrap();
+    assert_eq!(watcher.poll().unwrap(), None);
+
+    let inputs = (0..10).map(Input::from).collect::<Vec<_>>();
+    let mut m64 = M64::default();
+    m64.extend(inputs[..6].iter().copied());
+    // the last sample is still being written
+    let mut bytes = m64.to_bytes();
+    bytes.truncate(bytes.len() - 2);
+    fs::write(&path, &bytes).unwrap();
+
+    let update = watcher.poll().unwrap().unwrap();
+    assert_eq!(update.first_sample, 0);
+    assert_eq!(update.inputs, inputs[..5]);
+    assert_eq!(update.header.input_frames, 6);
+
+    fs::write(&path, m64.to_bytes()).unwrap();
+    let update = watcher.poll().unwrap().unwrap();
+    assert_eq!((update.first_sample, update.rewound_to), (5, None));
+    assert_eq!(update.inputs, inputs[5..6]);
+    assert!(watcher.poll().unwrap().unwrap().is_empty());
+
+    // loading a savestate goes back to sample 3, then records again before the next poll
+    m64.inputs.truncate(3);
+    m64.extend(inputs[7..].iter().copied());
+    m64.rerecords += 1;
+    fs::write(&path, m64.to_bytes()).unwrap();
+    let update = watcher.poll().unwrap().unwrap();
+    assert_eq!((update.first_sample, update.rewound_to), (3, Some(3)));
+    assert_eq!(update.inputs, inputs[7..10]);
+
+    // going back without recording yet
+    m64.input_frames = 4;
+    m64.rerecords += 1;
+    fs::write(&path, m64.to_bytes()).unwrap();
+    let update = watcher.poll().unwrap().unwrap();
+    assert_eq!(update.rewound_to, Some(4));
+    assert!(update.inputs.is_empty());
+    assert_eq!(watcher.samples_read(), 4);
+
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,96 @@
+//! Converting between input frames, VIs and time.
+//!
+//! A movie stores one input sample per controller for each input frame, and games can poll for input less often than
+//! every VI, so converting between the two needs the region, the controller count and the VIs per input frame.
+use core::time::Duration;
+
+use crate::{m64::M64, region::Region};
+
+/// Converts between input frames, input samples, VIs and time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Timing {
+    /// Video region, which sets the VI rate.
+    pub region: Region,
+    /// Number of input samples stored for each input frame.
+    pub controllers: usize,
+    /// Number of VIs for each input frame.
+    pub vis_per_input_frame: f64,
+}
+
+impl Timing {
+    /// Creates a timing where the game polls for input every VI.
+    /// - `controllers` is clamped to at least 1.
+    pub fn new(region: Region, controllers: usize) -> Self {
+        Timing {
+            region,
+            controllers: controllers.max(1),
+            vis_per_input_frame: 1.0,
+        }
+    }
+
+    /// Returns the time from the start of the movie at which the input frame starts.
+    pub fn frame_to_time(&self, frame: usize) -> Duration {
+        self.vi_to_time(frame as f64 * self.vis_per_input_frame)
+    }
+
+    /// Returns the input frame being shown at the time from the start of the movie.
+    pub fn time_to_frame(&self, time: Duration) -> usize {
+        (self.time_to_vi(time) / self.vis_per_input_frame) as usize
+    }
+
+    /// Returns the input frame being shown on the VI.
+    pub fn vi_to_input_frame(&self, vi: u64) -> usize {
+        (vi as f64 / self.vis_per_input_frame) as usize
+    }
+
+    /// Returns the VI the input frame starts on.
+    pub fn input_frame_to_vi(&self, frame: usize) -> u64 {
+        (frame as f64 * self.vis_per_input_frame) as u64
+    }
+
+    /// Returns the input frame the input sample belongs to.
+    pub fn sample_to_frame(&self, sample: usize) -> usize {
+        sample / self.controllers
+    }
+
+    /// Returns the index of the first input sample of the input frame.
+    pub fn frame_to_sample(&self, frame: usize) -> usize {
+        frame * self.controllers
+    }
+
+    fn vi_to_time(&self, vi: f64) -> Duration {
+        Duration::from_secs_f64(vi / self.region.vi_rate_exact())
+    }
+
+    fn time_to_vi(&self, time: Duration) -> f64 {
+        time.as_secs_f64() * self.region.vi_rate_exact()
+    }
+}
+
+impl M64 {
+    /// Returns the timing of the movie, with its [region](M64::region) and controller count.
+    /// - The VIs per input frame are estimated from the header, see [`M64::input_frames_per_second`].
+    pub fn timing(&self) -> Timing {
+        let frames = self.input_frames as f64 / self.samples_per_frame() as f64;
+        let vis_per_input_frame = if self.vi_frames == 0 || frames == 0.0 {
+            1.0
+        } else {
+            self.vi_frames as f64 / frames
+        };
+
+        Timing {
+            vis_per_input_frame,
+            ..Timing::new(self.region(), self.samples_per_frame())
+        }
+    }
+
+    /// Returns the time from the start of the movie at which the input frame starts, see [`Timing::frame_to_time`].
+    pub fn frame_to_time(&self, frame: usize) -> Duration {
+        self.timing().frame_to_time(frame)
+    }
+
+    /// Returns the input frame being shown at the time from the start of the movie, see [`Timing::time_to_frame`].
+    pub fn time_to_frame(&self, time: Duration) -> usize {
+        self.timing().time_to_frame(time)
+    }
+}
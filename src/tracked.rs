@@ -0,0 +1,272 @@
+//! Tracking the changes made to a movie, so only the changed bytes of its file need to be written again.
+//!
+//! [`TrackedM64`] wraps a movie that matches a file, such as one that was just loaded or saved.
+//! Edits go through it, and [`TrackedM64::take_changes`] returns the byte ranges of the file that changed since,
+//! which can be written over the old file for an autosave, or sent to keep another copy of the movie in sync.
+use alloc::vec::Vec;
+use core::ops::{Deref, Range, RangeBounds};
+
+use crate::{
+    controller::Input, error::FieldName, m64::M64, metadata::M64Metadata, parser::HEADER_SIZE,
+};
+
+/// Bytes of the file that changed, starting at `offset`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ByteChange {
+    /// Offset of the bytes in the file.
+    pub offset: usize,
+    /// The new bytes.
+    pub bytes: Vec<u8>,
+}
+
+/// The changes to a file since the movie was last saved, see [`TrackedM64::take_changes`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct FileChanges {
+    /// The new length of the file, which the file has to be cut off at or extended to.
+    pub len: usize,
+    /// The changed bytes, in order of their offset and never overlapping.
+    pub changes: Vec<ByteChange>,
+}
+
+impl FileChanges {
+    /// Returns `true` if nothing changed.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Applies the changes to the bytes of the old file.
+    pub fn apply(&self, file: &mut Vec<u8>) {
+        file.resize(self.len, 0);
+        for change in &self.changes {
+            file[change.offset..change.offset + change.bytes.len()].copy_from_slice(&change.bytes);
+        }
+    }
+}
+
+/// A movie that keeps track of the changes made to it since it was last saved.
+/// - The inputs are changed through the methods of the wrapper, and the movie is read through `Deref`.
+/// - Header changes are found by comparing the header against the one last saved, so every field is tracked.
+#[derive(Debug, Clone)]
+pub struct TrackedM64 {
+    m64: M64,
+    /// The header as it was last saved.
+    saved_header: [u8; HEADER_SIZE],
+    /// The metadata block as it was last saved.
+    saved_block: Vec<u8>,
+    /// Number of input samples when the movie was last saved.
+    saved_samples: usize,
+    /// Ranges of input samples changed since, sorted and never overlapping or touching.
+    dirty: Vec<Range<usize>>,
+}
+
+impl TrackedM64 {
+    /// Starts tracking the changes to a movie, which is taken to match the file it was loaded from.
+    pub fn new(m64: M64) -> Self {
+        TrackedM64 {
+            saved_header: m64.raw_header(),
+            saved_block: m64.metadata_block.to_bytes(),
+            saved_samples: m64.inputs.len(),
+            dirty: Vec::new(),
+            m64,
+        }
+    }
+
+    /// Returns the movie, dropping the tracked changes.
+    pub fn into_inner(self) -> M64 {
+        self.m64
+    }
+
+    /// Returns `true` if anything changed since the movie was last saved.
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+            || self.m64.inputs.len() != self.saved_samples
+            || self.m64.raw_header() != self.saved_header
+            || self.m64.metadata_block.to_bytes() != self.saved_block
+    }
+
+    /// Returns the header fields that changed since the movie was last saved.
+    /// - Changes to the reserved bytes, such as the [extended header](crate::extended), aren't listed.
+    pub fn changed_fields(&self) -> Vec<FieldName> {
+        let header = self.m64.raw_header();
+        HEADER_FIELDS
+            .into_iter()
+            .filter(|field| {
+                let range = field.offset()..field.offset() + field.size();
+                header[range.clone()] != self.saved_header[range]
+            })
+            .collect()
+    }
+
+    /// Returns the ranges of frames whose inputs changed since the movie was last saved, in order.
+    pub fn changed_frames(&self) -> Vec<Range<usize>> {
+        let stride = self.m64.samples_per_frame();
+        let mut frames: Vec<Range<usize>> = Vec::new();
+        for range in &self.dirty {
+            let range = range.start / stride..range.end.div_ceil(stride);
+            match frames.last_mut() {
+                Some(last) if last.end >= range.start => last.end = last.end.max(range.end),
+                _ => frames.push(range),
+            }
+        }
+        frames
+    }
+
+    /// Edits the header, see [`M64Metadata`] for the fields.
+    pub fn edit_header<F>(&mut self, edit: F)
+    where
+        F: FnOnce(&mut M64Metadata),
+    {
+        let mut metadata = self.m64.metadata();
+        edit(&mut metadata);
+        metadata.apply_to(&mut self.m64);
+    }
+
+    /// Replaces the input sample at `index`, returning the previous one, or `None` if it's out of range.
+    pub fn set_input(&mut self, index: usize, input: Input) -> Option<Input> {
+        let previous = self.m64.set_input(index, input)?;
+        if previous != input {
+            self.mark(index..index + 1);
+        }
+        Some(previous)
+    }
+
+    /// Returns the input samples of a range of frames for editing, marking all of them as changed.
+    /// - The range is clamped to the frames of the movie, see [`M64::frame_range_to_samples`].
+    pub fn frames_mut<R>(&mut self, frames: R) -> &mut [Input]
+    where
+        R: RangeBounds<usize>,
+    {
+        let range = self.m64.frame_range_to_samples(frames);
+        self.mark(range.clone());
+        &mut self.m64.inputs[range]
+    }
+
+    /// Replaces the input samples of a range of frames with `inputs`, which can be a different number of samples.
+    /// - The range is clamped to the frames of the movie, see [`M64::frame_range_to_samples`].
+    /// - If the number of samples changes, every sample after the start of the range is marked as changed.
+    pub fn replace_frames<R, I>(&mut self, frames: R, inputs: I)
+    where
+        R: RangeBounds<usize>,
+        I: IntoIterator<Item = Input>,
+    {
+        let range = self.m64.frame_range_to_samples(frames);
+        let len = self.m64.inputs.len();
+        self.m64.inputs.splice(range.clone(), inputs);
+
+        if self.m64.inputs.len() == len {
+            self.mark(range);
+        } else {
+            self.mark(range.start..self.m64.inputs.len().max(len));
+        }
+    }
+
+    /// Returns the changes since the movie was last saved, and marks the movie as saved.
+    /// - A change to the number of input samples also moves the metadata block, which is then written again.
+    pub fn take_changes(&mut self) -> FileChanges {
+        let mut changes = Vec::new();
+
+        // header, as runs of changed bytes
+        let header = self.m64.raw_header();
+        let mut offset = 0;
+        while offset < HEADER_SIZE {
+            if header[offset] == self.saved_header[offset] {
+                offset += 1;
+                continue;
+            }
+            let start = offset;
+            while offset < HEADER_SIZE && header[offset] != self.saved_header[offset] {
+                offset += 1;
+            }
+            changes.push(ByteChange {
+                offset: start,
+                bytes: header[start..offset].to_vec(),
+            });
+        }
+
+        // inputs
+        let samples = self.m64.inputs.len();
+        for range in core::mem::take(&mut self.dirty) {
+            let range = range.start.min(samples)..range.end.min(samples);
+            if range.is_empty() {
+                continue;
+            }
+            changes.push(ByteChange {
+                offset: HEADER_SIZE + range.start * 4,
+                bytes: self.m64.inputs[range]
+                    .iter()
+                    .flat_map(|&input| u32::from(input).to_le_bytes())
+                    .collect(),
+            });
+        }
+
+        // metadata block
+        let block = self.m64.metadata_block.to_bytes();
+        if (samples != self.saved_samples || block != self.saved_block) && !block.is_empty() {
+            changes.push(ByteChange {
+                offset: HEADER_SIZE + samples * 4,
+                bytes: block.clone(),
+            });
+        }
+
+        let len = HEADER_SIZE + samples * 4 + block.len();
+        self.saved_header = header;
+        self.saved_block = block;
+        self.saved_samples = samples;
+        FileChanges { len, changes }
+    }
+
+    /// Marks a range of input samples as changed, merging it with the ranges it overlaps or touches.
+    fn mark(&mut self, range: Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
+
+        let first = self.dirty.partition_point(|dirty| dirty.end < range.start);
+        let last = self.dirty.partition_point(|dirty| dirty.start <= range.end);
+        let merged = match self.dirty[first..last] {
+            [] => range,
+            ref overlapping => {
+                overlapping[0].start.min(range.start)
+                    ..overlapping[overlapping.len() - 1].end.max(range.end)
+            }
+        };
+        self.dirty.splice(first..last, [merged]);
+    }
+}
+
+impl Deref for TrackedM64 {
+    type Target = M64;
+
+    fn deref(&self) -> &Self::Target {
+        &self.m64
+    }
+}
+
+impl From<M64> for TrackedM64 {
+    /// Same as [`TrackedM64::new`].
+    fn from(m64: M64) -> Self {
+        Self::new(m64)
+    }
+}
+
+/// Every header field, in file order.
+const HEADER_FIELDS: [FieldName; 18] = [
+    FieldName::Version,
+    FieldName::Uid,
+    FieldName::ViFrames,
+    FieldName::Rerecords,
+    FieldName::Fps,
+    FieldName::ControllerCount,
+    FieldName::InputFrames,
+    FieldName::MovieStartType,
+    FieldName::ControllerFlags,
+    FieldName::RomInternalName,
+    FieldName::RomCrc32,
+    FieldName::RomCountryCode,
+    FieldName::VideoPlugin,
+    FieldName::SoundPlugin,
+    FieldName::InputPlugin,
+    FieldName::RspPlugin,
+    FieldName::Author,
+    FieldName::Description,
+];
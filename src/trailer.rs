@@ -0,0 +1,199 @@
+//! An optional block of keyed metadata after the input samples, for data that doesn't fit in the fixed header fields.
+//!
+//! The block is laid out as:
+//! - The entries, each a `u16` key length, the UTF-8 key, a `u32` value length and the value.
+//! - NUL padding up to a multiple of 4 bytes.
+//! - A `u32` holding the length of the entries and padding, followed by the signature `M64M`.
+//!
+//! Lengths are little endian. The block is found from the end of the file, but only past the `input_frames` samples the header declares,
+//! so the inputs of a movie are never taken for a block. As it's a multiple of 4 bytes,
+//! parsers that don't know about it read it as extra input samples past `input_frames`.
+//! [`M64::from_u8_array_strict`](crate::M64::from_u8_array_strict) does the same.
+use alloc::{string::String, vec::Vec};
+
+use crate::error::MetadataBlockError;
+
+/// Signature at the end of the metadata block.
+pub const SIGNATURE: &[u8; 4] = b"M64M";
+
+/// Key of the full list of authors, as a UTF-8 string.
+pub const AUTHORS: &str = "authors";
+/// Key of a URL for the movie, such as its submission page.
+pub const URL: &str = "url";
+
+/// Keyed metadata stored after the input samples.
+/// - The entries are kept in order, and an empty block isn't written at all.
+/// - Values are raw bytes, so tools can store their own data. Text values are UTF-8.
+/// - Keys are at most [`u16::MAX`] bytes, and the entries with their padding at most [`u32::MAX`] bytes, so their lengths fit in the block.
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "Vec<(String, Vec<u8>)>", into = "Vec<(String, Vec<u8>)>")
+)]
+pub struct MetadataBlock {
+    /// The entries as key value pairs.
+    entries: Vec<(String, Vec<u8>)>,
+}
+
+impl MetadataBlock {
+    /// Returns the entries as key value pairs, in order.
+    pub fn entries(&self) -> &[(String, Vec<u8>)] {
+        &self.entries
+    }
+
+    /// Returns `true` if the block has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the value of the first entry with the key.
+    pub fn get(&self, key: &str) -> Option<&[u8]> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_slice())
+    }
+
+    /// Returns the value of the first entry with the key, if it's UTF-8.
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        core::str::from_utf8(self.get(key)?).ok()
+    }
+
+    /// Sets the value of the first entry with the key, or appends a new entry.
+    /// - Returns the previous value.
+    /// - Fails without changing the block if the key is too long, or the entries would be too large for the block.
+    pub fn insert<K, V>(&mut self, key: K, value: V) -> Result<Option<Vec<u8>>, MetadataBlockError>
+    where
+        K: Into<String>,
+        V: Into<Vec<u8>>,
+    {
+        let key = key.into();
+        let value = value.into();
+        let index = self.entries.iter().position(|(k, _)| *k == key);
+        let others = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| Some(*i) != index)
+            .map(|(_, (k, v))| (k.as_str(), v.as_slice()));
+        check_entries(others.chain([(key.as_str(), value.as_slice())]))?;
+
+        match index {
+            Some(index) => Ok(Some(core::mem::replace(&mut self.entries[index].1, value))),
+            None => {
+                self.entries.push((key, value));
+                Ok(None)
+            }
+        }
+    }
+
+    /// Removes every entry with the key, returning the value of the first one.
+    pub fn remove(&mut self, key: &str) -> Option<Vec<u8>> {
+        let index = self.entries.iter().position(|(k, _)| k == key)?;
+        let (_, value) = self.entries.remove(index);
+        self.entries.retain(|(k, _)| k != key);
+        Some(value)
+    }
+
+    /// Returns the block as it's written after the input samples, or nothing if it's empty.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        if self.is_empty() {
+            return bytes;
+        }
+
+        // the lengths fit, as the entries are checked when they're added
+        for (key, value) in &self.entries {
+            bytes.extend_from_slice(&(key.len() as u16).to_le_bytes());
+            bytes.extend_from_slice(key.as_bytes());
+            bytes.extend_from_slice(&(value.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(value);
+        }
+        bytes.resize(bytes.len().next_multiple_of(4), 0);
+
+        let len = bytes.len() as u32;
+        bytes.extend_from_slice(&len.to_le_bytes());
+        bytes.extend_from_slice(SIGNATURE);
+        bytes
+    }
+
+    /// Splits the block off the end of `data`, returning the data before it along with the block.
+    /// - Returns `None` if `data` doesn't end with a well formed block, or the block is empty as an empty block is never written.
+    pub fn split_from(data: &[u8]) -> Option<(&[u8], MetadataBlock)> {
+        let (rest, signature) = data.split_last_chunk::<4>()?;
+        if signature != SIGNATURE {
+            return None;
+        }
+        let (rest, len) = rest.split_last_chunk::<4>()?;
+        let len = u32::from_le_bytes(*len) as usize;
+        if len == 0 || !len.is_multiple_of(4) || len > rest.len() {
+            return None;
+        }
+
+        let (rest, mut entries) = rest.split_at(rest.len() - len);
+        let mut block = MetadataBlock::default();
+        // the padding is at most 3 NUL bytes
+        while entries.len() >= 4 || entries.iter().any(|&b| b != 0) {
+            let (key_len, tail) = entries.split_first_chunk::<2>()?;
+            let key_len = u16::from_le_bytes(*key_len) as usize;
+            let key = core::str::from_utf8(tail.get(..key_len)?).ok()?;
+            let (value_len, tail) = tail[key_len..].split_first_chunk::<4>()?;
+            let value_len = u32::from_le_bytes(*value_len) as usize;
+            let value = tail.get(..value_len)?;
+
+            block.entries.push((key.into(), value.into()));
+            entries = &tail[value_len..];
+        }
+
+        Some((rest, block))
+    }
+
+    /// Splits the block off the end of the bytes after the header of a movie, returning the input samples along with the block.
+    /// - The block is only looked for after the `input_frames` samples the header declares,
+    ///   so input samples that happen to look like a block are never taken for one.
+    pub fn split_from_inputs(data: &[u8], input_frames: u32) -> Option<(&[u8], MetadataBlock)> {
+        let declared = (input_frames as usize).saturating_mul(4).min(data.len());
+        let (extra, block) = Self::split_from(&data[declared..])?;
+        Some((&data[..declared + extra.len()], block))
+    }
+}
+
+impl TryFrom<Vec<(String, Vec<u8>)>> for MetadataBlock {
+    type Error = MetadataBlockError;
+
+    /// Creates a block from key value pairs, keeping every entry even if keys repeat.
+    fn try_from(entries: Vec<(String, Vec<u8>)>) -> Result<Self, Self::Error> {
+        check_entries(entries.iter().map(|(k, v)| (k.as_str(), v.as_slice())))?;
+        Ok(MetadataBlock { entries })
+    }
+}
+
+impl From<MetadataBlock> for Vec<(String, Vec<u8>)> {
+    fn from(block: MetadataBlock) -> Self {
+        block.entries
+    }
+}
+
+/// Checks that the entries fit in a block, so their lengths can be written without truncating.
+fn check_entries<'a, I>(entries: I) -> Result<(), MetadataBlockError>
+where
+    I: IntoIterator<Item = (&'a str, &'a [u8])>,
+{
+    let mut size = 0usize;
+    for (key, value) in entries {
+        if key.len() > u16::MAX as usize {
+            return Err(MetadataBlockError::KeyTooLong(key.len()));
+        }
+        size = size
+            .saturating_add(2 + key.len())
+            .saturating_add(4)
+            .saturating_add(value.len());
+    }
+
+    // the size is padded up to a multiple of 4
+    if size > u32::MAX as usize - 3 {
+        return Err(MetadataBlockError::BlockTooLarge(size));
+    }
+    Ok(())
+}
@@ -0,0 +1,317 @@
+//! Semantic checks of a movie, for movies that parse but can't play back as intended.
+use std::fmt;
+
+use crate::{
+    error::{FieldName, Severity},
+    m64::M64,
+    region::Region,
+};
+
+/// An inconsistency found by [`M64::validate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValidationIssue {
+    /// `controller_count` doesn't match the number of controllers flagged as present.
+    /// - The inputs are interleaved by `controller_count`, so the samples are likely read for the wrong controllers.
+    ControllerCountMismatch {
+        /// The `controller_count` header field.
+        controller_count: u8,
+        /// The number of controllers flagged as present.
+        present: usize,
+    },
+    /// The number of input samples isn't a multiple of the number of present controllers, so the last frame is incomplete.
+    IncompleteFrame {
+        /// The number of input samples.
+        samples: usize,
+        /// The number of controllers flagged as present.
+        present: usize,
+    },
+    /// Input samples in the slot of a controller that isn't flagged as present aren't neutral.
+    /// - A sign of inputs interleaved for a different controller layout, often by an editor.
+    AbsentControllerInput {
+        /// The controller port the slot is read for.
+        port: usize,
+        /// The first frame with input for the controller.
+        first_frame: usize,
+        /// The number of frames with input for the controller.
+        frames: usize,
+    },
+    /// The `input_frames` header field doesn't match the number of input samples.
+    InputFramesMismatch {
+        /// The `input_frames` header field.
+        input_frames: u32,
+        /// The number of input samples.
+        samples: usize,
+    },
+    /// The movie has fewer VIs than input frames, but the game can't poll for input more often than once per VI.
+    FewerVisThanFrames {
+        /// The `vi_frames` header field.
+        vi_frames: u32,
+        /// The number of frames.
+        frames: usize,
+    },
+    /// The `fps` header field doesn't match the VI rate of the ROM region.
+    RegionFpsMismatch {
+        /// The `fps` header field.
+        fps: u8,
+        /// The region of the ROM country code.
+        region: Region,
+    },
+    /// Input samples have a single reserved bit set, which does nothing, as a reset needs both.
+    ReservedBitSet {
+        /// The first sample with a single reserved bit set.
+        first_sample: usize,
+        /// The number of samples with a single reserved bit set.
+        samples: usize,
+    },
+    /// Consecutive frames trigger a reset, which resets the console on each of them.
+    ConsecutiveResets {
+        /// The first frame of the resets.
+        first_frame: usize,
+        /// The number of consecutive frames that trigger a reset.
+        frames: usize,
+    },
+    /// A string field fills the whole field with no NUL terminator, so tools reading it as a C string read past its end.
+    UnterminatedString(FieldName),
+}
+
+impl ValidationIssue {
+    /// Returns how serious the issue is.
+    pub fn severity(&self) -> Severity {
+        match self {
+            ValidationIssue::ControllerCountMismatch { .. } => Severity::Error,
+            ValidationIssue::IncompleteFrame { .. }
+            | ValidationIssue::AbsentControllerInput { .. }
+            | ValidationIssue::InputFramesMismatch { .. }
+            | ValidationIssue::FewerVisThanFrames { .. }
+            | ValidationIssue::RegionFpsMismatch { .. }
+            | ValidationIssue::ReservedBitSet { .. }
+            | ValidationIssue::ConsecutiveResets { .. } => Severity::Warning,
+            ValidationIssue::UnterminatedString(_) => Severity::Info,
+        }
+    }
+
+    /// Returns a suggestion on how to fix the issue.
+    pub fn suggested_fix(&self) -> &'static str {
+        match self {
+            ValidationIssue::ControllerCountMismatch { .. } => {
+                "set the controller count to the number of present controllers, or fix the controller flags"
+            }
+            ValidationIssue::IncompleteFrame { .. } => {
+                "remove the samples of the incomplete frame, or complete it with neutral inputs"
+            }
+            ValidationIssue::AbsentControllerInput { .. } => {
+                "interleave the inputs for the present controllers, or clear the inputs of the absent controller"
+            }
+            ValidationIssue::InputFramesMismatch { .. } => {
+                "set input_frames to the number of input samples"
+            }
+            ValidationIssue::FewerVisThanFrames { .. } => {
+                "check the VI count, which should be at least the number of frames"
+            }
+            ValidationIssue::RegionFpsMismatch { .. } => {
+                "set fps to the VI rate of the ROM region"
+            }
+            ValidationIssue::ReservedBitSet { .. } => {
+                "clear the reserved bits, which only have an effect when both are set"
+            }
+            ValidationIssue::ConsecutiveResets { .. } => {
+                "keep each reset on a single frame, unless the console should be reset repeatedly"
+            }
+            ValidationIssue::UnterminatedString(_) => {
+                "shorten the string by at least 1 byte so it's NUL terminated"
+            }
+        }
+    }
+
+    /// Returns the stable code of the issue, which never changes between versions.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ValidationIssue::ControllerCountMismatch { .. } => "V001",
+            ValidationIssue::IncompleteFrame { .. } => "V002",
+            ValidationIssue::AbsentControllerInput { .. } => "V003",
+            ValidationIssue::InputFramesMismatch { .. } => "V004",
+            ValidationIssue::FewerVisThanFrames { .. } => "V005",
+            ValidationIssue::RegionFpsMismatch { .. } => "V006",
+            ValidationIssue::ReservedBitSet { .. } => "V007",
+            ValidationIssue::ConsecutiveResets { .. } => "V008",
+            ValidationIssue::UnterminatedString(_) => "V009",
+        }
+    }
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationIssue::ControllerCountMismatch {
+                controller_count,
+                present,
+            } => write!(
+                f,
+                "controller count is {controller_count}, but {present} controllers are present"
+            ),
+            ValidationIssue::IncompleteFrame { samples, present } => write!(
+                f,
+                "{samples} input samples can't be split evenly between {present} controllers"
+            ),
+            ValidationIssue::AbsentControllerInput {
+                port,
+                first_frame,
+                frames,
+            } => write!(
+                f,
+                "controller {} isn't present, but has input on {frames} frames starting from frame {first_frame}",
+                port + 1
+            ),
+            ValidationIssue::InputFramesMismatch {
+                input_frames,
+                samples,
+            } => write!(
+                f,
+                "header has {input_frames} input samples, but the movie holds {samples}"
+            ),
+            ValidationIssue::FewerVisThanFrames { vi_frames, frames } => {
+                write!(f, "movie has {vi_frames} VIs, but {frames} frames")
+            }
+            ValidationIssue::RegionFpsMismatch { fps, region } => write!(
+                f,
+                "header fps is {fps}, but the ROM is {region:?} which runs at {}",
+                region.vi_rate()
+            ),
+            ValidationIssue::ReservedBitSet {
+                first_sample,
+                samples,
+            } => write!(
+                f,
+                "{samples} input samples starting from sample {first_sample} have a single reserved bit set"
+            ),
+            ValidationIssue::ConsecutiveResets {
+                first_frame,
+                frames,
+            } => write!(
+                f,
+                "{frames} consecutive frames starting from frame {first_frame} trigger a reset"
+            ),
+            ValidationIssue::UnterminatedString(field) => {
+                write!(f, "{field} fills the whole field with no NUL terminator")
+            }
+        }
+    }
+}
+
+impl M64 {
+    /// Checks the movie for inconsistencies that make it play back wrong, or that tools can trip over.
+    /// - The input samples are read for the controllers the same way as [`M64::controllers`] splits them.
+    /// - Returns the issues in the order they are checked, see [`ValidationIssue::severity`] for how serious each is.
+    /// - Returns every issue found, or nothing if the movie is consistent.
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+
+        let present = self
+            .controller_flags
+            .iter()
+            .filter(|flags| flags.controller_present)
+            .count();
+        if self.controller_count as usize != present {
+            issues.push(ValidationIssue::ControllerCountMismatch {
+                controller_count: self.controller_count,
+                present,
+            });
+        }
+        if present > 0 && !self.inputs.len().is_multiple_of(present) {
+            issues.push(ValidationIssue::IncompleteFrame {
+                samples: self.inputs.len(),
+                present,
+            });
+        }
+
+        for (slot, port) in self.controller_ports().into_iter().enumerate() {
+            if self
+                .controller_flags
+                .get(port)
+                .is_some_and(|flags| flags.controller_present)
+            {
+                continue;
+            }
+
+            let mut active = self
+                .inputs_for_controller(slot)
+                .enumerate()
+                .filter(|(_, input)| !input.is_neutral())
+                .map(|(frame, _)| frame);
+            if let Some(first_frame) = active.next() {
+                issues.push(ValidationIssue::AbsentControllerInput {
+                    port,
+                    first_frame,
+                    frames: 1 + active.count(),
+                });
+            }
+        }
+
+        if self.input_frames as usize != self.inputs.len() {
+            issues.push(ValidationIssue::InputFramesMismatch {
+                input_frames: self.input_frames,
+                samples: self.inputs.len(),
+            });
+        }
+        if (self.vi_frames as usize) < self.frame_count() {
+            issues.push(ValidationIssue::FewerVisThanFrames {
+                vi_frames: self.vi_frames,
+                frames: self.frame_count(),
+            });
+        }
+        if let Some(region) = self.country_code().region() {
+            if self.fps as u32 != region.vi_rate() {
+                issues.push(ValidationIssue::RegionFpsMismatch {
+                    fps: self.fps,
+                    region,
+                });
+            }
+        }
+
+        let mut reserved = self
+            .inputs
+            .iter()
+            .enumerate()
+            .filter(|(_, input)| input.reserved_1 != input.reserved_2)
+            .map(|(sample, _)| sample);
+        if let Some(first_sample) = reserved.next() {
+            issues.push(ValidationIssue::ReservedBitSet {
+                first_sample,
+                samples: 1 + reserved.count(),
+            });
+        }
+
+        let mut resets = self.reset_frames().peekable();
+        while let Some(first_frame) = resets.next() {
+            let mut frames = 1;
+            while resets.next_if_eq(&(first_frame + frames)).is_some() {
+                frames += 1;
+            }
+            if frames > 1 {
+                issues.push(ValidationIssue::ConsecutiveResets {
+                    first_frame,
+                    frames,
+                });
+            }
+        }
+
+        for (field, s) in [
+            (
+                FieldName::RomInternalName,
+                self.rom_internal_name.as_padded_str(),
+            ),
+            (FieldName::VideoPlugin, self.video_plugin.as_padded_str()),
+            (FieldName::SoundPlugin, self.sound_plugin.as_padded_str()),
+            (FieldName::InputPlugin, self.input_plugin.as_padded_str()),
+            (FieldName::RspPlugin, self.rsp_plugin.as_padded_str()),
+            (FieldName::Author, self.author.as_padded_str()),
+            (FieldName::Description, self.description.as_padded_str()),
+        ] {
+            if !s.ends_with('\0') {
+                issues.push(ValidationIssue::UnterminatedString(field));
+            }
+        }
+
+        issues
+    }
+}
@@ -0,0 +1,218 @@
+//! WebAssembly bindings for running in the browser.
+//!
+//! Built as a `cdylib` for `wasm32-unknown-unknown` with the `wasm` feature,
+//! then passed through [wasm-bindgen](https://rustwasm.github.io/wasm-bindgen/) for the JavaScript glue.
+//! ```js
+//! import { M64 } from "mupen64-movie-parser";
+//!
+//! const m64 = M64.parse(new Uint8Array(await file.arrayBuffer()));
+//! console.log(m64.author, m64.rerecords);
+//! for (const input of m64.inputs()) {
+//!     console.log(input.buttons, input.x, input.y);
+//! }
+//! ```
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    controller::Input,
+    lint::vault_check,
+    m64::{MovieStartType, M64},
+};
+
+/// A single input sample.
+#[wasm_bindgen(js_name = Input)]
+#[derive(Clone, Copy)]
+pub struct WasmInput(Input);
+
+#[wasm_bindgen(js_class = Input)]
+impl WasmInput {
+    /// Creates an input from the raw value stored in the file.
+    #[wasm_bindgen(constructor)]
+    pub fn new(raw: u32) -> Self {
+        Self(Input::from(raw))
+    }
+
+    /// The raw value stored in the file.
+    #[wasm_bindgen(getter)]
+    pub fn raw(&self) -> u32 {
+        self.0.into()
+    }
+
+    /// Names of the pressed buttons.
+    #[wasm_bindgen(getter)]
+    pub fn buttons(&self) -> Vec<String> {
+        self.0
+            .buttons()
+            .iter_names()
+            .map(|(name, _)| name.to_string())
+            .collect()
+    }
+
+    /// Analog stick X axis.
+    #[wasm_bindgen(getter)]
+    pub fn x(&self) -> i8 {
+        self.0.x_axis
+    }
+
+    /// Analog stick Y axis.
+    #[wasm_bindgen(getter)]
+    pub fn y(&self) -> i8 {
+        self.0.y_axis
+    }
+}
+
+/// A Mupen64-rerecording movie.
+#[wasm_bindgen(js_name = M64)]
+pub struct WasmM64(M64);
+
+#[wasm_bindgen(js_class = M64)]
+impl WasmM64 {
+    /// Parses a movie from the bytes of a `.m64` file.
+    pub fn parse(data: &[u8]) -> Result<WasmM64, JsError> {
+        Ok(Self(M64::from_u8_array(data)?))
+    }
+
+    /// Returns the movie as the bytes of a `.m64` file.
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.0
+            .write_m64(&mut bytes)
+            .expect("writing to a Vec can't fail");
+        bytes
+    }
+
+    /// Returns the movie as JSON, see the [`json`](crate::json) module for the schema.
+    #[wasm_bindgen(js_name = toJson)]
+    pub fn to_json(&self) -> String {
+        self.0.to_json()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn uid(&self) -> u32 {
+        self.0.uid
+    }
+
+    #[wasm_bindgen(getter, js_name = viFrames)]
+    pub fn vi_frames(&self) -> u32 {
+        self.0.vi_frames
+    }
+
+    #[wasm_bindgen(getter, js_name = inputFrames)]
+    pub fn input_frames(&self) -> u32 {
+        self.0.input_frames
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn rerecords(&self) -> u32 {
+        self.0.rerecords
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn fps(&self) -> u8 {
+        self.0.fps
+    }
+
+    #[wasm_bindgen(getter, js_name = controllerCount)]
+    pub fn controller_count(&self) -> u8 {
+        self.0.controller_count
+    }
+
+    /// Movie start type, one of `"snapshot"`, `"power_on"`, `"eeprom"` or `"existing_snapshot"`.
+    #[wasm_bindgen(getter, js_name = movieStartType)]
+    pub fn movie_start_type(&self) -> String {
+        match self.0.movie_start_type {
+            MovieStartType::SnapShot => "snapshot",
+            MovieStartType::PowerOn => "power_on",
+            MovieStartType::Eeprom => "eeprom",
+            MovieStartType::ExistingSnapshot => "existing_snapshot",
+        }
+        .to_string()
+    }
+
+    #[wasm_bindgen(getter, js_name = romInternalName)]
+    pub fn rom_internal_name(&self) -> String {
+        self.0.rom_internal_name.to_string()
+    }
+
+    #[wasm_bindgen(getter, js_name = romCrc32)]
+    pub fn rom_crc_32(&self) -> u32 {
+        self.0.rom_crc_32
+    }
+
+    #[wasm_bindgen(getter, js_name = romCountryCode)]
+    pub fn rom_country_code(&self) -> u16 {
+        self.0.rom_country_code
+    }
+
+    #[wasm_bindgen(getter, js_name = videoPlugin)]
+    pub fn video_plugin(&self) -> String {
+        self.0.video_plugin.to_string()
+    }
+
+    #[wasm_bindgen(getter, js_name = soundPlugin)]
+    pub fn sound_plugin(&self) -> String {
+        self.0.sound_plugin.to_string()
+    }
+
+    #[wasm_bindgen(getter, js_name = inputPlugin)]
+    pub fn input_plugin(&self) -> String {
+        self.0.input_plugin.to_string()
+    }
+
+    #[wasm_bindgen(getter, js_name = rspPlugin)]
+    pub fn rsp_plugin(&self) -> String {
+        self.0.rsp_plugin.to_string()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn author(&self) -> String {
+        self.0.author.to_string()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn description(&self) -> String {
+        self.0.description.to_string()
+    }
+
+    /// Returns the number of input samples.
+    #[wasm_bindgen(js_name = inputCount)]
+    pub fn input_count(&self) -> usize {
+        self.0.inputs.len()
+    }
+
+    /// Returns the input sample at `index`, or `undefined` if it's out of range.
+    pub fn input(&self, index: usize) -> Option<WasmInput> {
+        self.0.inputs.get(index).copied().map(WasmInput)
+    }
+
+    /// Returns all input samples, interleaved between controllers.
+    pub fn inputs(&self) -> Vec<WasmInput> {
+        self.0.inputs.iter().copied().map(WasmInput).collect()
+    }
+
+    /// Returns the raw input samples as a `Uint32Array`.
+    #[wasm_bindgen(js_name = rawInputs)]
+    pub fn raw_inputs(&self) -> Vec<u32> {
+        self.0.inputs.iter().map(|&input| input.into()).collect()
+    }
+
+    /// Returns the input samples of a single controller.
+    #[wasm_bindgen(js_name = controllerInputs)]
+    pub fn controller_inputs(&self, controller: usize) -> Vec<WasmInput> {
+        self.0
+            .inputs_for_controller(controller)
+            .map(WasmInput)
+            .collect()
+    }
+
+    /// Checks the movie against the TASVideos submission requirements, see [`vault_check`].
+    /// - Returns the findings as messages, ordered from the most serious.
+    #[wasm_bindgen(js_name = vaultCheck)]
+    pub fn vault_check(&self) -> Vec<String> {
+        vault_check(&self.0)
+            .iter()
+            .map(|finding| finding.to_string())
+            .collect()
+    }
+}
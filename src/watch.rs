@@ -0,0 +1,158 @@
+//! Following a movie while it's being recorded.
+//!
+//! The emulator keeps the header counters up to date and appends input samples as the movie is recorded.
+//! [`MovieWatcher`] polls the file for those changes, treating the header `input_frames` as the number of valid samples.
+//! - When a savestate is loaded during recording, `input_frames` goes back and the samples after it are rewritten,
+//!   which is reported with [`WatchUpdate::rewound_to`].
+//! - A rerecord can happen between two polls, so whenever the rerecord count changes,
+//!   every sample is read again and compared with the ones read before to find where the movie went back to.
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+use crate::{controller::Input, error::M64ParseError, m64::M64, parser::HEADER_SIZE};
+
+/// Follows a movie file that is being recorded.
+#[derive(Debug)]
+pub struct MovieWatcher {
+    path: PathBuf,
+    file: File,
+    header: Option<M64>,
+    samples: Vec<u32>,
+}
+
+/// The changes to a movie since the last poll.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WatchUpdate {
+    /// The current header, with no inputs.
+    pub header: M64,
+    /// The sample index the movie went back to, if samples that were already read were discarded.
+    pub rewound_to: Option<usize>,
+    /// Index of the first sample in `inputs`.
+    pub first_sample: usize,
+    /// The newly appended input samples.
+    pub inputs: Vec<Input>,
+}
+
+impl WatchUpdate {
+    /// Returns if nothing changed since the last poll.
+    pub fn is_empty(&self) -> bool {
+        self.rewound_to.is_none() && self.inputs.is_empty()
+    }
+}
+
+impl MovieWatcher {
+    /// Starts following the movie at `path`, with no samples read yet.
+    /// - The file can still be missing its header, which is then read once it's written.
+    pub fn open<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref().to_path_buf();
+        Ok(Self {
+            file: File::open(&path)?,
+            path,
+            header: None,
+            samples: Vec::new(),
+        })
+    }
+
+    /// Returns the path of the movie.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the header from the last poll that read one.
+    pub fn header(&self) -> Option<&M64> {
+        self.header.as_ref()
+    }
+
+    /// Returns the number of input samples read so far.
+    pub fn samples_read(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Re-reads the header and returns the input samples appended since the last poll.
+    /// - Returns `None` if the header hasn't been written yet.
+    pub fn poll(&mut self) -> Result<Option<WatchUpdate>, M64ParseError> {
+        let len = self.file.metadata()?.len();
+        if len < HEADER_SIZE as u64 {
+            return Ok(None);
+        }
+
+        let mut header = vec![0; HEADER_SIZE];
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.read_exact(&mut header)?;
+        let header = M64::from_u8_array(&header)?;
+
+        // samples past the end of the file are still being written
+        let written = ((len - HEADER_SIZE as u64) / 4) as usize;
+        let available = (header.input_frames as usize).min(written);
+
+        let rerecorded = self
+            .header
+            .as_ref()
+            .is_some_and(|last| last.total_rerecords() != header.total_rerecords());
+        let start = if rerecorded {
+            0
+        } else {
+            self.samples.len().min(available)
+        };
+
+        let mut bytes = vec![0; (available - start) * 4];
+        self.file
+            .seek(SeekFrom::Start(HEADER_SIZE as u64 + start as u64 * 4))?;
+        self.file.read_exact(&mut bytes)?;
+        let samples = bytes
+            .chunks_exact(4)
+            .map(|sample| u32::from_le_bytes(sample.try_into().unwrap()));
+
+        // the first sample that differs from what was read before, or is missing now
+        let first_sample = start
+            + samples
+                .clone()
+                .zip(&self.samples[start.min(self.samples.len())..])
+                .take_while(|(new, old)| new == *old)
+                .count();
+        let rewound_to = (first_sample < self.samples.len()).then_some(first_sample);
+
+        self.samples.truncate(first_sample);
+        self.samples.extend(samples.skip(first_sample - start));
+        self.header = Some(header.clone());
+        Ok(Some(WatchUpdate {
+            header,
+            rewound_to,
+            first_sample,
+            inputs: self.samples[first_sample..]
+                .iter()
+                .map(|&raw| Input::from(raw))
+                .collect(),
+        }))
+    }
+
+    /// Returns a blocking iterator over the changes to the movie, polling every `interval`.
+    /// - Only updates with changes are returned, and the iterator stops after the first error.
+    pub fn updates(
+        &mut self,
+        interval: Duration,
+    ) -> impl Iterator<Item = Result<WatchUpdate, M64ParseError>> + '_ {
+        let mut failed = false;
+        std::iter::from_fn(move || {
+            while !failed {
+                match self.poll() {
+                    Ok(Some(update)) if !update.is_empty() => return Some(Ok(update)),
+                    Ok(_) => thread::sleep(interval),
+                    Err(err) => {
+                        failed = true;
+                        return Some(Err(err));
+                    }
+                }
+            }
+            None
+        })
+    }
+}